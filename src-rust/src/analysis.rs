@@ -0,0 +1,283 @@
+//! 追踪前的图像分析：颜色直方图、主色、建议的颜色数与预计的色块层数
+//!
+//! 都跳过曲线拟合，耗时远小于一次完整追踪，适合在 UI 中预先给出参数建议。
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Object};
+use visioncortex::ColorImage;
+
+use crate::{
+    check_rgba_len, cluster_color, cluster_image, layer_difference_for, memory, reset_rng, set_js_property, warnings,
+    TraceOptions, TracerConfig,
+};
+
+/// 直方图每个通道保留的位数 (4 位 → 4096 个颜色桶)
+const HISTOGRAM_BITS: u32 = 4;
+
+/// 自动估计斑点阈值时统计的最大色块面积 (像素)
+const MAX_SPECKLE: usize = 64;
+
+/// 自动选择颜色数时依次尝试的候选值 (相邻候选的 `layer_difference` 互不相同)
+const AUTO_COLOR_COUNTS: [u8; 9] = [2, 3, 4, 5, 6, 8, 12, 16, 24];
+
+/// 自动选择颜色数时把图像缩小到长边不超过此值 (像素) 再评估
+const AUTO_COLOR_MAX_SIDE: usize = 256;
+
+/// 自动选择颜色数时允许剩余的误差降幅比例：再增加颜色最多还能把误差降低 (最大 - 最小) 的这一比例
+const AUTO_COLOR_MIN_GAIN: f64 = 0.1;
+
+/// 重建误差 (每通道均方误差) 低于此值视为已还原原图，不再增加颜色
+const AUTO_COLOR_GOOD_ENOUGH: f64 = 4.0;
+
+/// 返回的主色数量上限
+const TOP_COLORS: usize = 16;
+
+/// 建议颜色数的上下限，与 `color_count` 的有效范围一致
+const MIN_SUGGESTED: usize = 2;
+const MAX_SUGGESTED: usize = 64;
+
+/// 一个主色及其覆盖比例
+struct DominantColor {
+    r: u8,
+    g: u8,
+    b: u8,
+    /// 占不透明像素的比例 (0~1)
+    coverage: f64,
+}
+
+/// 分析结果
+struct ImageAnalysis {
+    colors: Vec<DominantColor>,
+    /// 非空颜色桶的数量
+    distinct_colors: usize,
+    suggested_color_count: usize,
+}
+
+/// 颜色桶：像素数与各通道累计值 (用于求桶内平均色)
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    count: u64,
+    r: u64,
+    g: u64,
+    b: u64,
+}
+
+/// 在单调递减的曲线上找拐点，返回其下标；曲线平坦或点数不足时返回 `None`
+///
+/// 两轴都归一化到 [0, 1] 后，取离首尾连线最远的点 (Kneedle 的简化形式)。
+fn knee_index(values: &[f64]) -> Option<usize> {
+    let n = values.len();
+    if n < 3 {
+        return None;
+    }
+
+    let (high, low) = (values[0], values[n - 1]);
+    if high <= low {
+        return None;
+    }
+
+    // 归一化后首尾连线为 x + y = 1，曲线位于其下方，1 - x - y 越大离得越远
+    (0..n)
+        .map(|i| {
+            let x = i as f64 / (n - 1) as f64;
+            let y = (values[i] - low) / (high - low);
+            (i, 1.0 - x - y)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// 在按覆盖率降序排列的曲线上找拐点，作为“自然”颜色数
+fn elbow(coverages: &[f64]) -> usize {
+    let n = coverages.len().min(MAX_SUGGESTED);
+    if n <= MIN_SUGGESTED {
+        return MIN_SUGGESTED;
+    }
+
+    // 拐点之前 (含拐点) 的颜色都算作主要颜色
+    knee_index(&coverages[..n]).map_or(MIN_SUGGESTED, |knee| (knee + 1).clamp(MIN_SUGGESTED, MAX_SUGGESTED))
+}
+
+fn analyze(rgba_data: &[u8]) -> ImageAnalysis {
+    let shift = 8 - HISTOGRAM_BITS;
+    let mut buckets = vec![Bucket::default(); 1 << (HISTOGRAM_BITS * 3)];
+
+    let mut total = 0u64;
+    for px in rgba_data.chunks_exact(4) {
+        // 完全透明的像素不参与统计
+        if px[3] == 0 {
+            continue;
+        }
+        let index = ((px[0] as usize >> shift) << (HISTOGRAM_BITS * 2))
+            | ((px[1] as usize >> shift) << HISTOGRAM_BITS)
+            | (px[2] as usize >> shift);
+        let bucket = &mut buckets[index];
+        bucket.count += 1;
+        bucket.r += px[0] as u64;
+        bucket.g += px[1] as u64;
+        bucket.b += px[2] as u64;
+        total += 1;
+    }
+
+    let mut used: Vec<Bucket> = buckets.into_iter().filter(|b| b.count > 0).collect();
+    used.sort_by_key(|b| std::cmp::Reverse(b.count));
+
+    let total = total.max(1) as f64;
+    let coverages: Vec<f64> = used.iter().map(|b| b.count as f64 / total).collect();
+
+    let colors = used
+        .iter()
+        .take(TOP_COLORS)
+        .map(|b| DominantColor {
+            r: (b.r / b.count) as u8,
+            g: (b.g / b.count) as u8,
+            b: (b.b / b.count) as u8,
+            coverage: b.count as f64 / total,
+        })
+        .collect();
+
+    ImageAnalysis {
+        colors,
+        distinct_colors: used.len(),
+        suggested_color_count: elbow(&coverages),
+    }
+}
+
+/// 分析图像的颜色分布，用于在追踪前建议 `color_count`
+///
+/// 像素按每通道 4 位量化到 4096 个颜色桶统计 (完全透明的像素不计入)，返回对象：
+/// - `colors`：覆盖率最高的至多 16 个主色，`{ r, g, b, coverage }`，`coverage` 为占不透明像素的比例
+/// - `distinct_colors`：非空颜色桶的数量
+/// - `suggested_color_count`：在覆盖率降序曲线上取拐点得到的建议颜色数 (2~64)
+///
+/// 只做一次像素遍历，不聚类也不拟合曲线，耗时远小于完整追踪。
+#[wasm_bindgen]
+pub fn analyze_image(rgba_data: &[u8], width: u32, height: u32) -> Result<JsValue, JsValue> {
+    check_rgba_len(rgba_data, width as usize, height as usize)?;
+
+    let analysis = analyze(rgba_data);
+
+    let colors = Array::new();
+    for color in &analysis.colors {
+        let entry = Object::new();
+        set_js_property(&entry, "r", &color.r.into());
+        set_js_property(&entry, "g", &color.g.into());
+        set_js_property(&entry, "b", &color.b.into());
+        set_js_property(&entry, "coverage", &color.coverage.into());
+        colors.push(&entry);
+    }
+
+    let result = Object::new();
+    set_js_property(&result, "colors", &colors.into());
+    set_js_property(&result, "distinct_colors", &(analysis.distinct_colors as u32).into());
+    set_js_property(&result, "suggested_color_count", &(analysis.suggested_color_count as u32).into());
+
+    Ok(result.into())
+}
+
+/// 只运行聚类阶段，返回给定参数在这张图上实际会产生的色块层数 (`clusters_output` 的数量)
+///
+/// 实际层数往往与 `color_count` 不同：层次聚类按颜色差异与面积分层，而不是严格取 N 种颜色。
+/// 跳过了曲线拟合，耗时约为完整追踪中的聚类部分，可用于在正式追踪前校准参数。
+#[wasm_bindgen]
+pub fn preview_color_count(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<usize, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let clusters = cluster_image(color_image, &cfg);
+
+    Ok(clusters.view().clusters_output.len())
+}
+
+/// 根据色块面积分布估计 `filter_speckle`
+///
+/// 先以不过滤斑点的参数聚类一次，统计 "面积不小于 a 的色块数" 随 a (1~64) 的变化：
+/// 噪点产生大量极小的色块，曲线在小面积处陡降，之后趋于平缓。取该曲线的拐点作为阈值，
+/// 拐点左侧的陡降部分视为噪点。没有明显拐点 (例如几乎没有小色块) 时返回 0，即不过滤。
+pub(crate) fn estimate_speckle(color_image: &ColorImage, cfg: &TracerConfig) -> usize {
+    let mut probe = cfg.clone();
+    probe.filter_speckle = 0;
+    let clusters = cluster_image(color_image.clone(), &probe);
+    let view = clusters.view();
+
+    // counts[a] = 面积恰为 a 的色块数，超过上限的都计入最后一格
+    let mut counts = vec![0usize; MAX_SPECKLE + 1];
+    for &index in view.clusters_output {
+        counts[view.get_cluster(index).area().min(MAX_SPECKLE)] += 1;
+    }
+
+    let mut surviving = Vec::with_capacity(MAX_SPECKLE);
+    let mut remaining: usize = counts[1..].iter().sum();
+    for count in &counts[1..] {
+        surviving.push(remaining as f64);
+        remaining -= count;
+    }
+
+    // 第 i 个点对应 filter_speckle = i + 1
+    knee_index(&surviving).map_or(0, |knee| knee + 1)
+}
+
+/// 聚类结果的重建误差：每个色块填充其输出颜色后，与原图不透明像素 RGB 的均方误差
+fn reconstruction_error(color_image: &ColorImage, cfg: &TracerConfig) -> f64 {
+    let clusters = cluster_image(color_image.clone(), cfg);
+    let view = clusters.view();
+    let mut rendered = ColorImage::new_w_h(color_image.width, color_image.height);
+    for &index in view.clusters_output.iter().rev() {
+        let cluster = view.get_cluster(index);
+        cluster.render_to_color_image_with_color(&view, &mut rendered, &cluster_color(cluster, cfg));
+    }
+
+    let (mut sum, mut count) = (0.0, 0usize);
+    for (original, traced) in color_image.pixels.chunks_exact(4).zip(rendered.pixels.chunks_exact(4)) {
+        if original[3] == 0 {
+            continue;
+        }
+        sum += (0..3).map(|c| (original[c] as f64 - traced[c] as f64).powi(2)).sum::<f64>();
+        count += 3;
+    }
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+/// 自动选择 `color_count`：按 `AUTO_COLOR_COUNTS` 依次聚类，取重建误差曲线的拐点
+///
+/// 每个候选值都先只做聚类，再把色块按输出颜色填回，计算与原图的均方误差。分层聚类的误差随颜色数
+/// 呈阶梯状下降，相邻两档常常完全相同，因此不比较相邻档，而是取误差已降到
+/// `最小误差 + AUTO_COLOR_MIN_GAIN * (最大误差 - 最小误差)` 以内 (即拿到了 90% 的可获得收益) 的最小候选值。
+/// 某一档的误差已低于 `AUTO_COLOR_GOOD_ENOUGH` 时直接返回，后面的候选值不再尝试。
+/// 评估在长边缩小到 `AUTO_COLOR_MAX_SIDE` 以内的图像上进行，斑点阈值按面积同比缩小。
+pub(crate) fn estimate_color_count(color_image: &ColorImage, cfg: &TracerConfig) -> u8 {
+    let mut sample = color_image.clone();
+    let mut probe = cfg.clone();
+    while sample.width.max(sample.height) > AUTO_COLOR_MAX_SIDE {
+        let (pixels, width, height) = memory::halve(&sample.pixels, sample.width, sample.height);
+        sample = ColorImage { pixels, width, height };
+        probe.filter_speckle /= 4;
+    }
+
+    let mut errors = Vec::with_capacity(AUTO_COLOR_COUNTS.len());
+    for &count in &AUTO_COLOR_COUNTS {
+        probe.color_count = count;
+        probe.layer_difference = layer_difference_for(count);
+        let error = reconstruction_error(&sample, &probe);
+        if error < AUTO_COLOR_GOOD_ENOUGH {
+            return count;
+        }
+        errors.push((count, error));
+    }
+
+    let max = errors.iter().map(|&(_, e)| e).fold(f64::MIN, f64::max);
+    let min = errors.iter().map(|&(_, e)| e).fold(f64::MAX, f64::min);
+    let target = min + AUTO_COLOR_MIN_GAIN * (max - min);
+    errors.iter().find(|&&(_, e)| e <= target).map_or(AUTO_COLOR_COUNTS[0], |&(count, _)| count)
+}
@@ -0,0 +1,93 @@
+//! 动画 GIF 矢量化：逐帧追踪并组装为一个带 SMIL 动画的 SVG
+
+use std::fmt::Write;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use image::AnimationDecoder;
+use image::codecs::gif::GifDecoder;
+use visioncortex::PointF64;
+
+use crate::{cluster_and_fit, reset_rng, svg_root_attrs, svg_root_children, warnings, write_paths, TraceOptions};
+
+/// GIF 帧延迟为 0 时浏览器通常按 100ms 播放，这里保持一致
+const DEFAULT_FRAME_DELAY_MS: f64 = 100.0;
+
+/// 将动画 GIF 的每一帧分别矢量化，组装为一个循环播放的动画 SVG
+/// 
+/// 每帧输出为一个 `<g>`，通过 `<animate attributeName="display">` 按 GIF 的帧延迟
+/// 依次切换可见性 (离散动画，无跨帧插值)。帧由 `image` 的 GIF 解码器合成为完整画布，
+/// 因此每帧都是独立完整的一层。
+/// 
+/// 注意：输出体积约等于单帧 SVG 体积乘以帧数，较长的 GIF 会生成非常大的文件，
+/// 建议先在 JS 端抽帧或限制帧数。
+#[wasm_bindgen]
+pub fn trace_gif_to_svg(gif_bytes: &[u8], options: &TraceOptions) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    let decoder = GifDecoder::new(Cursor::new(gif_bytes))
+        .map_err(|e| JsValue::from_str(&format!("GIF 解析失败: {}", e)))?;
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .map_err(|e| JsValue::from_str(&format!("GIF 帧解码失败: {}", e)))?;
+    
+    let Some(first) = frames.first() else {
+        return Err(JsValue::from_str("GIF 不包含任何帧"));
+    };
+    let width = first.buffer().width();
+    let height = first.buffer().height();
+    
+    // 各帧起始时间 (毫秒)
+    let mut starts = Vec::with_capacity(frames.len() + 1);
+    let mut total_ms = 0.0;
+    for frame in &frames {
+        starts.push(total_ms);
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay = numer as f64 / denom.max(1) as f64;
+        total_ms += if delay > 0.0 { delay } else { DEFAULT_FRAME_DELAY_MS };
+    }
+    starts.push(total_ms);
+    
+    let cfg = options.to_config();
+    let frame_count = frames.len();
+    
+    let mut svg = String::with_capacity(1024 * 64 * frame_count);
+    writeln!(svg, r#"<svg{} viewBox="0 0 {} {}">"#, svg_root_attrs(&cfg, width as f64, height as f64), width, height).ok();
+    svg.push_str(&svg_root_children(&cfg));
+    
+    for (i, frame) in frames.into_iter().enumerate() {
+        // 每帧独立播种，保证单帧结果与帧序无关
+        reset_rng();
+        let color_image = visioncortex::ColorImage {
+            pixels: frame.into_buffer().into_raw(),
+            width: width as usize,
+            height: height as usize,
+        };
+        let path_results = cluster_and_fit(color_image, &cfg);
+        
+        if frame_count == 1 {
+            writeln!(svg, "<g>").ok();
+        } else {
+            let begin = starts[i] / total_ms;
+            let end = starts[i + 1] / total_ms;
+            let (display, values, key_times) = if i == 0 {
+                ("inline", "inline;none".to_owned(), format!("0;{:.4}", end))
+            } else if i == frame_count - 1 {
+                ("none", "none;inline".to_owned(), format!("0;{:.4}", begin))
+            } else {
+                ("none", "none;inline;none".to_owned(), format!("0;{:.4};{:.4}", begin, end))
+            };
+            writeln!(svg, r#"<g display="{}">"#, display).ok();
+            writeln!(
+                svg,
+                r#"<animate attributeName="display" values="{}" keyTimes="{}" dur="{}ms" calcMode="discrete" repeatCount="indefinite"/>"#,
+                values, key_times, total_ms
+            ).ok();
+        }
+        write_paths(&mut svg, &path_results, &cfg, PointF64::default());
+        writeln!(svg, "</g>").ok();
+    }
+    
+    writeln!(svg, "</svg>").ok();
+    
+    Ok(svg)
+}
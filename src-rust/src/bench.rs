@@ -0,0 +1,106 @@
+//! 基准测试：一次调用同时返回 SVG 与各阶段耗时、输出规模，便于与其它矢量化工具对比及跟踪性能回归
+//!
+//! 计时在原生环境使用 `std::time::Instant`，在 WASM 中使用 `Date.now()` (只有毫秒精度，
+//! 单次调用耗时较短时应多次运行取平均)。
+
+use wasm_bindgen::prelude::*;
+use visioncortex::ColorImage;
+
+use crate::{build_svg, check_empty, check_rgba_len, cluster_and_fit_timed, lines, reset_rng, warnings, TraceOptions};
+
+/// 聚类与拟合两个阶段的耗时 (毫秒)
+pub(crate) struct PhaseTimes {
+    pub cluster_ms: f64,
+    pub fit_ms: f64,
+}
+
+/// 当前时间戳 (毫秒)，只用于求差值
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+/// 当前时间戳 (毫秒)，只用于求差值
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// 基准测试结果：输出的 SVG 及各阶段耗时 (毫秒) 与输出规模
+#[wasm_bindgen(getter_with_clone)]
+pub struct BenchResult {
+    #[wasm_bindgen(readonly)]
+    pub svg: String,
+    /// 输入准备耗时：RGBA 输入无需解码，这里是把像素复制为内部图像的耗时
+    #[wasm_bindgen(readonly)]
+    pub decode_ms: f64,
+    /// 聚类耗时 (含 `auto_speckle`、`refine_passes` 的预估)
+    #[wasm_bindgen(readonly)]
+    pub cluster_ms: f64,
+    /// 路径拟合耗时 (含 `protect_dark`、`max_paths` 等拟合后的处理)
+    #[wasm_bindgen(readonly)]
+    pub fit_ms: f64,
+    /// 生成 SVG 文本的耗时
+    #[wasm_bindgen(readonly)]
+    pub svg_ms: f64,
+    /// 输出的 `<path>` 元素数量
+    #[wasm_bindgen(readonly)]
+    pub path_count: u32,
+    /// 输出 SVG 的字节数 (UTF-8)
+    #[wasm_bindgen(readonly)]
+    pub byte_size: u32,
+}
+
+/// 与 `trace_rgba_with_options` 相同的流程，额外返回各阶段耗时与输出统计
+///
+/// 输出的 SVG 与 `trace_rgba_with_options` 逐字节一致。开启 `line_extraction` 时
+/// 线稿提取没有聚类阶段，全部耗时计入 `fit_ms`。
+#[wasm_bindgen]
+pub fn trace_benchmark(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<BenchResult, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let start = now_ms();
+
+    let (svg, decode_ms, times, svg_ms, path_count) = match cfg.line_extraction {
+        Some(threshold) => {
+            let svg = lines::trace_lines(rgba_data, w, h, threshold, &cfg);
+            let times = PhaseTimes { cluster_ms: 0.0, fit_ms: now_ms() - start };
+            let path_count = svg.matches("<path").count();
+            (svg, 0.0, times, 0.0, path_count)
+        }
+        None => {
+            let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+            let decode_ms = now_ms() - start;
+            let (path_results, times) = cluster_and_fit_timed(color_image, &cfg);
+            check_empty(&path_results, &cfg)?;
+            let render_start = now_ms();
+            let svg = build_svg(w, h, &path_results, &cfg);
+            (svg, decode_ms, times, now_ms() - render_start, path_results.len())
+        }
+    };
+
+    Ok(BenchResult {
+        byte_size: svg.len() as u32,
+        svg,
+        decode_ms,
+        cluster_ms: times.cluster_ms,
+        fit_ms: times.fit_ms,
+        svg_ms,
+        path_count: path_count as u32,
+    })
+}
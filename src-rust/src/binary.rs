@@ -0,0 +1,219 @@
+//! 紧凑二进制输出：体积受限的原生应用中代替文本 SVG 嵌入矢量图
+//!
+//! # 格式 (版本 1)
+//!
+//! 所有整数均为 LEB128 无符号变长整数 (varint)；有符号值先做 zigzag 编码
+//! (`(n << 1) ^ (n >> 31)`) 再写为 varint。
+//!
+//! ```text
+//! 文件头:
+//!   magic          4 字节  "SVB1"
+//!   fraction_bits  1 字节  坐标定点小数位数 (坐标 = 整数值 / 2^fraction_bits 像素)
+//!   width          varint  图像宽度 (像素)
+//!   height         varint  图像高度 (像素)
+//!   path_count     varint
+//! 每条路径:
+//!   r, g, b        3 字节  填充色
+//!   subpath_count  varint
+//! 每条子路径:
+//!   kind           1 字节  0 = 折线 (依次连线), 1 = 三次样条 (起点 + 每段 3 个控制点)
+//!   point_count    varint  点数 (样条为 1 + 3n)
+//!   points         2 * point_count 个 zigzag varint，坐标相对上一个点的增量
+//! ```
+//!
+//! 增量在整个文件内连续计算 (起始参考点为原点)，子路径之间不重置；所有子路径都是闭合的。
+
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use visioncortex::{ColorImage, CompoundPathElement, PointF64};
+
+use crate::{check_empty, check_rgba_len, cluster_and_fit, reset_rng, warnings, TraceOptions};
+
+const MAGIC: &[u8; 4] = b"SVB1";
+
+/// 坐标定点小数位数：2 位即 1/4 像素精度
+const FRACTION_BITS: u8 = 2;
+
+const KIND_POLYLINE: u8 = 0;
+const KIND_SPLINE: u8 = 1;
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_signed(out: &mut Vec<u8>, value: i32) {
+    write_varint(out, ((value << 1) ^ (value >> 31)) as u32 as u64);
+}
+
+/// 按顺序写出点坐标的增量，`cursor` 为上一个点的定点坐标
+fn write_points(out: &mut Vec<u8>, points: &[PointF64], cursor: &mut (i32, i32)) {
+    let scale = (1u32 << FRACTION_BITS) as f64;
+    write_varint(out, points.len() as u64);
+    for p in points {
+        let x = (p.x * scale).round() as i32;
+        let y = (p.y * scale).round() as i32;
+        write_signed(out, x - cursor.0);
+        write_signed(out, y - cursor.1);
+        *cursor = (x, y);
+    }
+}
+
+/// 矢量化并输出紧凑二进制格式 (格式说明见模块文档)
+///
+/// 坐标以 1/4 像素定点、增量 varint 编码，体积通常只有文本 SVG 的 1/3~1/5 (压缩前)。
+/// `decode_binary_to_svg` 可把结果还原为 SVG，用于校验与调试。
+#[wasm_bindgen]
+pub fn trace_rgba_to_binary(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<Vec<u8>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let path_results = cluster_and_fit(color_image, &cfg);
+    check_empty(&path_results, &cfg)?;
+
+    let mut out = Vec::with_capacity(1024 * 16);
+    out.extend_from_slice(MAGIC);
+    out.push(FRACTION_BITS);
+    write_varint(&mut out, width as u64);
+    write_varint(&mut out, height as u64);
+    write_varint(&mut out, path_results.len() as u64);
+
+    let mut cursor = (0, 0);
+    for (compound_path, color) in &path_results {
+        out.extend_from_slice(&[color.r, color.g, color.b]);
+        write_varint(&mut out, compound_path.paths.len() as u64);
+        for element in &compound_path.paths {
+            match element {
+                CompoundPathElement::PathI32(p) => {
+                    out.push(KIND_POLYLINE);
+                    let points: Vec<_> = p.path.iter().map(|p| p.to_point_f64()).collect();
+                    write_points(&mut out, &points, &mut cursor);
+                }
+                CompoundPathElement::PathF64(p) => {
+                    out.push(KIND_POLYLINE);
+                    write_points(&mut out, &p.path, &mut cursor);
+                }
+                CompoundPathElement::Spline(s) => {
+                    out.push(KIND_SPLINE);
+                    write_points(&mut out, &s.points, &mut cursor);
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// 二进制数据读取器，越界时返回格式错误
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self.bytes.get(self.pos).ok_or("数据意外结束")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint 过长".to_owned())
+    }
+
+    fn signed(&mut self) -> Result<i32, String> {
+        let v = self.varint()? as u32;
+        Ok((v >> 1) as i32 ^ -((v & 1) as i32))
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<String, String> {
+    if !bytes.starts_with(MAGIC) {
+        return Err("不是 SVB1 格式的数据".to_owned());
+    }
+    let mut r = Reader { bytes, pos: MAGIC.len() };
+    let fraction_bits = r.byte()?;
+    if fraction_bits > 16 {
+        return Err(format!("不支持的定点小数位数 {}", fraction_bits));
+    }
+    let scale = (1u32 << fraction_bits) as f64;
+    let width = r.varint()?;
+    let height = r.varint()?;
+    let path_count = r.varint()?;
+
+    let mut svg = String::with_capacity(bytes.len() * 4);
+    writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}">"#, width, height).ok();
+
+    let mut cursor = (0i32, 0i32);
+    for _ in 0..path_count {
+        let (cr, cg, cb) = (r.byte()?, r.byte()?, r.byte()?);
+        let subpath_count = r.varint()?;
+
+        let mut d = String::new();
+        for _ in 0..subpath_count {
+            let kind = r.byte()?;
+            let point_count = r.varint()? as usize;
+            let mut points = Vec::with_capacity(point_count.min(bytes.len()));
+            for _ in 0..point_count {
+                cursor.0 += r.signed()?;
+                cursor.1 += r.signed()?;
+                points.push((cursor.0 as f64 / scale, cursor.1 as f64 / scale));
+            }
+            let Some(&(x0, y0)) = points.first() else {
+                continue;
+            };
+            write!(d, "M{} {} ", x0, y0).ok();
+            match kind {
+                KIND_POLYLINE => {
+                    for &(x, y) in &points[1..] {
+                        write!(d, "L{} {} ", x, y).ok();
+                    }
+                }
+                KIND_SPLINE => {
+                    for c in points[1..].chunks_exact(3) {
+                        write!(d, "C{} {} {} {} {} {} ", c[0].0, c[0].1, c[1].0, c[1].1, c[2].0, c[2].1).ok();
+                    }
+                }
+                other => return Err(format!("未知的子路径类型 {}", other)),
+            }
+            d.push_str("Z ");
+        }
+
+        writeln!(svg, r##"<path d="{}" fill="#{:02x}{:02x}{:02x}"/>"##, d.trim_end(), cr, cg, cb).ok();
+    }
+
+    writeln!(svg, "</svg>").ok();
+    Ok(svg)
+}
+
+/// 把 `trace_rgba_to_binary` 的输出还原为 SVG (绝对坐标)，用于校验往返与调试
+#[wasm_bindgen]
+pub fn decode_binary_to_svg(bytes: &[u8]) -> Result<String, JsValue> {
+    decode(bytes).map_err(|e| JsValue::from_str(&format!("二进制数据解析失败: {}", e)))
+}
@@ -0,0 +1,59 @@
+//! 聚类边界图：把聚类阶段得到的分区边界画成与输入同尺寸的 RGBA 叠加层
+
+use wasm_bindgen::prelude::*;
+use visioncortex::ColorImage;
+
+use crate::{check_rgba_len, cluster_image, reset_rng, warnings, TraceOptions};
+
+/// 边界线颜色 (品红，不透明)
+const BOUNDARY_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// 输出聚类边界叠加层，用于在原图上检查分割是否过细/过粗
+///
+/// 返回与输入同尺寸的 RGBA 缓冲区：背景全透明，聚类边界为 1px 品红线。
+/// 叠加层只含全透明与不透明像素，预乘与非预乘完全相同，因此不受 `premultiply_output` 影响。
+/// 只执行聚类，不做曲线拟合，反映的是拟合前的像素级分区。
+///
+/// 每个像素按绘制顺序归属于最上层覆盖它的聚类；
+/// 与右侧或下方像素归属不同的像素被视为边界。
+#[wasm_bindgen]
+pub fn trace_rgba_boundaries(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<Vec<u8>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let clusters = cluster_image(color_image, &cfg);
+    let view = clusters.view();
+
+    // 像素归属：按绘制顺序覆盖，后绘制的聚类在上层
+    let mut labels = vec![u32::MAX; w * h];
+    for (order, &cluster_index) in view.clusters_output.iter().rev().enumerate() {
+        for &i in view.get_cluster(cluster_index).iter() {
+            labels[i as usize] = order as u32;
+        }
+    }
+
+    let mut overlay = vec![0u8; w * h * 4];
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let differs_right = x + 1 < w && labels[i + 1] != labels[i];
+            let differs_down = y + 1 < h && labels[i + w] != labels[i];
+            if differs_right || differs_down {
+                overlay[i * 4..i * 4 + 4].copy_from_slice(&BOUNDARY_COLOR);
+            }
+        }
+    }
+
+    Ok(overlay)
+}
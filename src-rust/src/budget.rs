@@ -0,0 +1,63 @@
+//! 路径数量预算：拟合完成后把输出的 `<path>` 元素数量限制在给定上限内
+
+use visioncortex::{Color, CompoundPath};
+
+use crate::geometry::compound_path_area;
+use crate::palette::rgb_distance_sq;
+use crate::warnings;
+
+/// 只保留面积最大的 `max_paths` 条路径，其余路径的子路径并入颜色最接近的保留路径
+///
+/// 并入的子路径改用保留路径的颜色和绘制层级，因此可能被原本在其下方的路径遮挡；
+/// 好处是画面不会出现空洞，元素数量精确等于上限。保留/合并的数量通过警告通道报告。
+pub(crate) fn apply(path_results: &mut Vec<(CompoundPath, Color)>, max_paths: usize) -> Vec<usize> {
+    let max_paths = max_paths.max(1);
+    if path_results.len() <= max_paths {
+        return (0..path_results.len()).collect();
+    }
+
+    let mut by_area: Vec<(usize, f64)> = path_results
+        .iter()
+        .enumerate()
+        .map(|(i, (path, _))| (i, compound_path_area(path)))
+        .collect();
+    by_area.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut keep = vec![false; path_results.len()];
+    for &(i, _) in &by_area[..max_paths] {
+        keep[i] = true;
+    }
+
+    // 按原绘制顺序拆分为保留与待合并两组
+    let mut survivors = Vec::with_capacity(max_paths);
+    let mut merged = Vec::new();
+    let mut targets = vec![0; path_results.len()];
+    for (i, item) in path_results.drain(..).enumerate() {
+        if keep[i] {
+            targets[i] = survivors.len();
+            survivors.push(item);
+        } else {
+            merged.push((i, item));
+        }
+    }
+
+    let merged_count = merged.len();
+    for (i, (path, color)) in merged {
+        let target = survivors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, c))| rgb_distance_sq(*c, color))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        survivors[target].0.paths.extend(path.paths);
+        targets[i] = target;
+    }
+
+    warnings::emit(
+        "max_paths",
+        &format!("保留 {} 条路径，{} 条路径并入颜色最接近的保留路径", survivors.len(), merged_count),
+    );
+
+    *path_results = survivors;
+    targets
+}
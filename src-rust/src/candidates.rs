@@ -0,0 +1,110 @@
+//! 候选追踪：按一小组参数分别追踪，栅格化后与原图比较，按质量评分排序返回，供 "选一个喜欢的" 界面使用
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Object};
+use visioncortex::ColorImage;
+
+use crate::raster::rasterize_paths;
+use crate::{build_svg, check_rgba_len, cluster_and_fit, reset_rng, set_js_property, TraceOptions};
+
+/// 参数网格：颜色数
+const GRID_COLOR_COUNTS: [u8; 3] = [4, 8, 16];
+
+/// 参数网格：斑点过滤阈值 (像素)
+const GRID_FILTER_SPECKLES: [u32; 3] = [2, 8, 32];
+
+/// 评分时栅格化的子采样数，只需大致还原边缘的覆盖比例
+const SCORE_SAMPLE_COUNT: u8 = 2;
+
+/// 文件大小在评分中的权重
+const SIZE_WEIGHT: f64 = 0.05;
+
+/// 一组参数的追踪结果
+struct Candidate {
+    svg: String,
+    score: f64,
+    fidelity: f64,
+    color_count: u8,
+    filter_speckle: u32,
+}
+
+/// 预乘 alpha 的 RGB (0~255) 与白色背景合成
+fn over_white(rgb: [f64; 3], alpha: f64) -> [f64; 3] {
+    rgb.map(|c| c + 255.0 * (1.0 - alpha))
+}
+
+/// 还原度：栅格化结果与原图 (均与白色背景合成) 的 RGB 均方根误差映射到 0~1，1 为完全一致
+fn fidelity(rgba_data: &[u8], canvas: &[[f32; 4]]) -> f64 {
+    if canvas.is_empty() {
+        return 1.0;
+    }
+    let mut squared = 0.0;
+    for (px, rendered) in rgba_data.chunks_exact(4).zip(canvas) {
+        let alpha = px[3] as f64 / 255.0;
+        let original = over_white([px[0], px[1], px[2]].map(|c| c as f64 * alpha), alpha);
+        let traced = over_white([0, 1, 2].map(|c| rendered[c] as f64 * 255.0), rendered[3] as f64);
+        squared += original.iter().zip(traced).map(|(a, b)| (a - b) * (a - b)).sum::<f64>();
+    }
+    1.0 - (squared / (canvas.len() * 3) as f64).sqrt() / 255.0
+}
+
+/// 按参数网格生成多个候选追踪，返回评分最高的 `n` 个 (降序)
+///
+/// 网格为 `color_count` ∈ {4, 8, 16} 与 `filter_speckle` ∈ {2, 8, 32} 的全部 9 种组合，
+/// 其余参数取 `TraceOptions` 的默认值。每个候选按 `render_svg_preview` 的方式把路径栅格化到原图大小
+/// (每方向 2 个子采样)，与原图一同合成到白色背景上比较：
+/// - `fidelity`：`1 - RMSE / 255`，RMSE 为 RGB 三个通道的均方根误差，1 表示与原图完全一致；
+/// - `score`：`fidelity - 0.05 * 字节数 / 最大字节数`，最大字节数为全部候选中最大的 SVG，
+///   即在还原度相近时偏向更小的文件，相差 0.05 的还原度大致抵得上从最大体积减到零。
+///
+/// 返回数组的每一项为 `{ svg, score, fidelity, params }`，`params` 为 `{ color_count, filter_speckle }`，
+/// 可直接赋给 `TraceOptions` 重现该候选。`n` 截断到 1~9；评分相同的候选按网格顺序排列。
+/// 需要完整追踪并栅格化 9 次，耗时约为单次 `trace_rgba_with_options` 的 10~20 倍，大图宜先缩小。
+#[wasm_bindgen]
+pub fn trace_rgba_candidates(rgba_data: &[u8], width: u32, height: u32, n: u32) -> Result<JsValue, JsValue> {
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let mut candidates = Vec::new();
+    for &color_count in &GRID_COLOR_COUNTS {
+        for &filter_speckle in &GRID_FILTER_SPECKLES {
+            reset_rng();
+            let options = TraceOptions { color_count, filter_speckle, ..TraceOptions::default() };
+            let cfg = options.to_config();
+            let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+            let path_results = cluster_and_fit(color_image, &cfg);
+            let canvas = rasterize_paths(&path_results, w, h, Some(SCORE_SAMPLE_COUNT));
+            let fidelity = fidelity(rgba_data, &canvas);
+            candidates.push(Candidate {
+                svg: build_svg(w, h, &path_results, &cfg),
+                score: fidelity,
+                fidelity,
+                color_count,
+                filter_speckle,
+            });
+        }
+    }
+
+    let max_bytes = candidates.iter().map(|c| c.svg.len()).max().unwrap_or(1).max(1) as f64;
+    for candidate in &mut candidates {
+        candidate.score -= SIZE_WEIGHT * candidate.svg.len() as f64 / max_bytes;
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate((n as usize).clamp(1, GRID_COLOR_COUNTS.len() * GRID_FILTER_SPECKLES.len()));
+
+    let result = Array::new();
+    for candidate in candidates {
+        let params = Object::new();
+        set_js_property(&params, "color_count", &candidate.color_count.into());
+        set_js_property(&params, "filter_speckle", &candidate.filter_speckle.into());
+        let item = Object::new();
+        set_js_property(&item, "svg", &JsValue::from_str(&candidate.svg));
+        set_js_property(&item, "score", &candidate.score.into());
+        set_js_property(&item, "fidelity", &candidate.fidelity.into());
+        set_js_property(&item, "params", &params.into());
+        result.push(&item);
+    }
+    Ok(result.into())
+}
@@ -0,0 +1,238 @@
+//! 缩小聚类：在缩小的图像上聚类，把色块标签放大回原图后按原分辨率拟合
+
+use visioncortex::color_clusters::{Cluster, ClusterIndex, ClustersView};
+use visioncortex::{BoundingRect, Color, ColorImage};
+
+use crate::{prepare_pixels, run_clusters, TracerConfig};
+
+/// 放大回原分辨率的聚类结果，字段与 `Clusters` 相同，可直接交给拟合阶段
+pub(crate) struct UpscaledClusters {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    clusters: Vec<Cluster>,
+    cluster_indices: Vec<ClusterIndex>,
+    clusters_output: Vec<ClusterIndex>,
+}
+
+impl UpscaledClusters {
+    pub(crate) fn view(&self) -> ClustersView<'_> {
+        ClustersView {
+            width: self.width,
+            height: self.height,
+            pixels: &self.pixels,
+            clusters: &self.clusters,
+            cluster_indices: &self.cluster_indices,
+            clusters_output: &self.clusters_output,
+        }
+    }
+}
+
+/// 按面积平均缩小到 `sw` x `sh`
+///
+/// 需要键控透明像素时，一格中过半像素完全透明则结果完全透明，否则只平均不透明的像素，
+/// 避免透明像素残留的 RGB 混入边缘颜色。
+fn downsample(pixels: &[u8], w: usize, h: usize, sw: usize, sh: usize, keyed: bool) -> Vec<u8> {
+    let mut small = vec![0u8; sw * sh * 4];
+    for sy in 0..sh {
+        let (y0, y1) = (sy * h / sh, ((sy + 1) * h / sh).max(sy * h / sh + 1));
+        for sx in 0..sw {
+            let (x0, x1) = (sx * w / sw, ((sx + 1) * w / sw).max(sx * w / sw + 1));
+            let mut sum = [0u32; 4];
+            let (mut opaque, mut total) = (0u32, 0u32);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let px = &pixels[(y * w + x) * 4..(y * w + x) * 4 + 4];
+                    total += 1;
+                    if keyed && px[3] == 0 {
+                        continue;
+                    }
+                    opaque += 1;
+                    for (s, &v) in sum.iter_mut().zip(px) {
+                        *s += v as u32;
+                    }
+                }
+            }
+            let out = &mut small[(sy * sw + sx) * 4..(sy * sw + sx) * 4 + 4];
+            if opaque * 2 > total || (!keyed && opaque > 0) {
+                for (o, s) in out.iter_mut().zip(sum) {
+                    *o = ((s + opaque / 2) / opaque) as u8;
+                }
+            }
+        }
+    }
+    small
+}
+
+/// 在按 `scale` 缩小的图像上聚类，再把色块放大回原图尺寸
+///
+/// 缩小图的每个像素记下画面上可见 (最后绘制) 的输出色块。原图的每个像素取缩小图上
+/// 与之相邻的 (至多 4 个) 像素的可见色块作为候选，选颜色与自身最接近的一个，
+/// 因此色块边界按原图像素重新定位，而不是放大后的锯齿。层次聚类中色块的区域包含
+/// 画在其上的子色块，像素除了归入自己的可见色块，也归入包含该色块的全部下层色块，
+/// 保持叠放关系。斑点过滤的面积阈值按缩小后的面积折算。
+pub(crate) fn cluster(color_image: ColorImage, scale: f64, cfg: &TracerConfig) -> UpscaledClusters {
+    let (w, h) = (color_image.width, color_image.height);
+    let mut pixels = color_image.pixels;
+    prepare_pixels(&mut pixels, w, cfg);
+    let keyed = cfg.key_transparent || cfg.auto_background;
+
+    let sw = ((w as f64 * scale).round() as usize).clamp(1, w.max(1));
+    let sh = ((h as f64 * scale).round() as usize).clamp(1, h.max(1));
+    let small_cfg = TracerConfig {
+        filter_speckle: ((cfg.filter_speckle as f64 * scale * scale).round() as usize).max(1),
+        ..cfg.clone()
+    };
+    let small_image = ColorImage { pixels: downsample(&pixels, w, h, sw, sh, keyed), width: sw, height: sh };
+    let clusters = run_clusters(small_image, &small_cfg);
+    let small = clusters.view();
+
+    // 缩小图上每个像素的可见色块
+    let mut owners: Vec<Option<ClusterIndex>> = vec![None; sw * sh];
+    for &index in small.clusters_output.iter().rev() {
+        for &i in small.get_cluster(index).iter() {
+            owners[i as usize] = Some(index);
+        }
+    }
+    // 包含各色块的下层色块：层次聚类的区域要么嵌套、要么不相交，检查一个可见像素即可
+    let mut representative = vec![None; small.clusters.len()];
+    for (i, owner) in owners.iter().enumerate() {
+        if let Some(index) = owner {
+            representative[index.0 as usize].get_or_insert(i as u32);
+        }
+    }
+    let mut representing: Vec<Option<ClusterIndex>> = vec![None; sw * sh];
+    for (cluster, &pixel) in representative.iter().enumerate() {
+        if let Some(pixel) = pixel {
+            representing[pixel as usize] = Some(ClusterIndex(cluster as u32));
+        }
+    }
+    let mut ancestors: Vec<Vec<ClusterIndex>> = vec![Vec::new(); small.clusters.len()];
+    for &index in small.clusters_output {
+        for &i in small.get_cluster(index).iter() {
+            if let Some(inner) = representing[i as usize].filter(|&inner| inner != index) {
+                ancestors[inner.0 as usize].push(index);
+            }
+        }
+    }
+
+    let mut colors = vec![Color::default(); small.clusters.len()];
+    for &index in small.clusters_output {
+        colors[index.0 as usize] = small.get_cluster(index).residue_color();
+    }
+    let mut clusters_full: Vec<Cluster> = small
+        .clusters
+        .iter()
+        .map(|c| Cluster { indices: Vec::new(), holes: Vec::new(), num_holes: 0, rect: BoundingRect::default(), ..c.clone() })
+        .collect();
+    let mut cluster_indices = vec![ClusterIndex::default(); w * h];
+    let diff = |c: Color, px: &[u8]| c.r.abs_diff(px[0]) as u32 + c.g.abs_diff(px[1]) as u32 + c.b.abs_diff(px[2]) as u32;
+    for y in 0..h {
+        let fy = ((y as f64 + 0.5) * sh as f64 / h as f64 - 0.5).max(0.0);
+        let rows = [(fy as usize).min(sh - 1), (fy as usize + 1).min(sh - 1)];
+        for x in 0..w {
+            let i = y * w + x;
+            let px = &pixels[i * 4..i * 4 + 4];
+            if keyed && px[3] == 0 {
+                continue;
+            }
+            let fx = ((x as f64 + 0.5) * sw as f64 / w as f64 - 0.5).max(0.0);
+            let columns = [(fx as usize).min(sw - 1), (fx as usize + 1).min(sw - 1)];
+            let owner = rows
+                .iter()
+                .flat_map(|&sy| columns.iter().map(move |&sx| sy * sw + sx))
+                .filter_map(|j| owners[j])
+                .min_by_key(|index| diff(colors[index.0 as usize], px));
+            let Some(owner) = owner else { continue };
+            cluster_indices[i] = owner;
+            for &index in std::iter::once(&owner).chain(&ancestors[owner.0 as usize]) {
+                let cluster = &mut clusters_full[index.0 as usize];
+                cluster.indices.push(i as u32);
+                cluster.rect.add_x_y(x as i32, y as i32);
+            }
+        }
+    }
+    let clusters_output = small
+        .clusters_output
+        .iter()
+        .copied()
+        .filter(|index| !clusters_full[index.0 as usize].indices.is_empty())
+        .collect();
+
+    UpscaledClusters {
+        width: w as u32,
+        height: h as u32,
+        pixels,
+        clusters: clusters_full,
+        cluster_indices,
+        clusters_output,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cluster_and_fit_timed, TraceOptions};
+
+    /// 带纹理的大图：多个方向的条纹叠加，色块多、边界长
+    fn textured(width: usize, height: usize) -> ColorImage {
+        let pixels = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = ((i % width) as f64, (i / width) as f64);
+                let wave = |fx: f64, fy: f64| ((x * fx + y * fy).sin() * 0.5 + 0.5) * 255.0;
+                [wave(0.11, 0.03) as u8, wave(-0.05, 0.13) as u8, wave(0.07, 0.07) as u8, 255]
+            })
+            .collect();
+        ColorImage { pixels, width, height }
+    }
+
+    #[test]
+    fn half_scale_clusters_a_quarter_of_the_pixels() {
+        let image = textured(64, 48);
+        let mut options = TraceOptions::new();
+        options.cluster_scale = 0.5;
+        let cfg = options.to_config();
+
+        // 聚类的输入是 2x2 平均后的 32x24 图像
+        let small = downsample(&image.pixels, 64, 48, 32, 24, false);
+        assert_eq!(small.len(), 32 * 24 * 4);
+        let at = |x: usize, y: usize, c: usize| image.pixels[(y * 64 + x) * 4 + c] as u32;
+        for c in 0..4 {
+            let sum = at(10, 6, c) + at(11, 6, c) + at(10, 7, c) + at(11, 7, c);
+            assert_eq!(small[(3 * 32 + 5) * 4 + c] as u32, (sum + 2) / 4);
+        }
+
+        // 放大回原图后每个像素都归入某个输出色块
+        let clusters = cluster(image, 0.5, &cfg);
+        let view = clusters.view();
+        assert_eq!((view.width, view.height), (64, 48));
+        let mut covered = vec![false; 64 * 48];
+        for &index in view.clusters_output {
+            for &i in view.get_cluster(index).iter() {
+                covered[i as usize] = true;
+            }
+        }
+        assert!(covered.iter().all(|&c| c));
+    }
+
+    #[test]
+    #[ignore = "比较耗时，受机器负载影响，需要时用 cargo test -- --ignored 单独运行"]
+    fn half_scale_clustering_is_faster() {
+        let image = textured(192, 192);
+        // 取两次中较快的一次，减少其它测试并行运行带来的干扰
+        let cluster_ms = |scale: f64| {
+            let mut options = TraceOptions::new();
+            options.cluster_scale = scale;
+            let cfg = options.to_config();
+            (0..2)
+                .map(|_| {
+                    let (paths, times) = cluster_and_fit_timed(image.clone(), &cfg);
+                    assert!(!paths.is_empty());
+                    times.cluster_ms
+                })
+                .fold(f64::MAX, f64::min)
+        };
+        let (full, half) = (cluster_ms(1.0), cluster_ms(0.5));
+        assert!(half < full * 0.8, "full {:.1} ms, half {:.1} ms", full, half);
+    }
+}
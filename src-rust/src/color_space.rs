@@ -0,0 +1,110 @@
+//! 颜色空间：RGB 与 CIE LAB 之间的转换
+//! 
+//! 聚类器按字节通道比较颜色，因此 LAB 模式下把像素编码为 "LAB 字节"
+//! (L 映射到 0~255，a/b 加 128 偏移) 后再聚类，输出前解码回 sRGB。
+
+use std::sync::OnceLock;
+use visioncortex::Color;
+
+/// 聚类与最近色匹配使用的颜色空间
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ColorSpace {
+    Rgb,
+    Lab,
+}
+
+impl ColorSpace {
+    /// 解析 `"rgb"` / `"lab"`，未知取值回退为 RGB
+    pub(crate) fn parse(name: &str) -> ColorSpace {
+        match name {
+            "lab" => ColorSpace::Lab,
+            _ => ColorSpace::Rgb,
+        }
+    }
+}
+
+// D65 白点
+const WHITE_X: f64 = 0.95047;
+const WHITE_Y: f64 = 1.0;
+const WHITE_Z: f64 = 1.08883;
+
+/// sRGB 字节 → 线性亮度，只有 256 种取值，查表避免逐像素的 powf
+fn srgb_to_linear(c: u8) -> f64 {
+    static TABLE: OnceLock<[f64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let c = i as f64 / 255.0;
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        })
+    })[c as usize]
+}
+
+fn linear_to_srgb(c: f64) -> u8 {
+    let c = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (c * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+fn lab_f(t: f64) -> f64 {
+    if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 }
+}
+
+fn lab_f_inv(t: f64) -> f64 {
+    if t * t * t > 216.0 / 24389.0 { t * t * t } else { (116.0 * t - 16.0) * 27.0 / 24389.0 }
+}
+
+/// sRGB → CIE LAB (L: 0~100, a/b: 约 -128~127)
+pub(crate) fn rgb_to_lab(color: Color) -> [f64; 3] {
+    let r = srgb_to_linear(color.r);
+    let g = srgb_to_linear(color.g);
+    let b = srgb_to_linear(color.b);
+    
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / WHITE_X;
+    let y = (0.2126 * r + 0.7152 * g + 0.0722 * b) / WHITE_Y;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / WHITE_Z;
+    
+    let (fx, fy, fz) = (lab_f(x), lab_f(y), lab_f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// CIE LAB → sRGB
+pub(crate) fn lab_to_rgb(lab: [f64; 3]) -> Color {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    
+    let x = lab_f_inv(fx) * WHITE_X;
+    let y = lab_f_inv(fy) * WHITE_Y;
+    let z = lab_f_inv(fz) * WHITE_Z;
+    
+    let r = 3.2406 * x - 1.5372 * y - 0.4986 * z;
+    let g = -0.9689 * x + 1.8758 * y + 0.0415 * z;
+    let b = 0.0557 * x - 0.2040 * y + 1.0570 * z;
+    
+    Color::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+}
+
+/// LAB 空间的平方距离 (CIE76)
+pub(crate) fn lab_distance_sq(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+/// 把 RGBA 像素原地编码为 LAB 字节，alpha 通道保持不变
+pub(crate) fn encode_lab_pixels(pixels: &mut [u8]) {
+    for px in pixels.chunks_exact_mut(4) {
+        let lab = rgb_to_lab(Color::new(px[0], px[1], px[2]));
+        px[0] = (lab[0] * 2.55).round().clamp(0.0, 255.0) as u8;
+        px[1] = (lab[1] + 128.0).round().clamp(0.0, 255.0) as u8;
+        px[2] = (lab[2] + 128.0).round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// 把 LAB 字节编码的颜色解码回 sRGB
+pub(crate) fn decode_lab_color(color: Color) -> Color {
+    let mut decoded = lab_to_rgb([
+        color.r as f64 / 2.55,
+        color.g as f64 - 128.0,
+        color.b as f64 - 128.0,
+    ]);
+    decoded.a = color.a;
+    decoded
+}
@@ -0,0 +1,85 @@
+//! 平滑收敛统计：检测曲线拟合前的细分平滑是否在 `max_iterations` 次内收敛
+//!
+//! visioncortex 的细分平滑在某一轮没有再细分任何线段时提前结束，但这个"提前结束"的信号
+//! 不对外公开。这里对每条轮廓分别以 `max_iterations` 与 `max_iterations + 1` 次平滑，
+//! 多平滑一轮后点数仍在增加，说明原本的平滑在上限处被截断、尚未收敛；点数不变则已收敛。
+//! 结果与 visioncortex 内部的判断完全一致，代价是每条轮廓多平滑两遍。
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use visioncortex::{BinaryImage, PathI32, PathSimplifyMode};
+
+use crate::TracerConfig;
+
+/// 与 visioncortex 的 `Cluster::OUTSET_RATIO` 一致
+const OUTSET_RATIO: f64 = 8.0;
+
+/// 一次追踪的收敛统计，拟合阶段各线程共享
+#[derive(Default)]
+pub(crate) struct ConvergenceStats {
+    /// 达到迭代上限仍未收敛的轮廓数
+    capped: AtomicUsize,
+    /// 全部轮廓中最大的末轮变化量 (`f64` 的位表示，变化量非负，可直接按整数比较)
+    max_change: AtomicU64,
+}
+
+/// 收敛统计的汇总结果
+#[derive(Clone, Copy)]
+pub(crate) struct Summary {
+    pub capped: usize,
+    pub max_change: f64,
+}
+
+impl ConvergenceStats {
+    fn record(&self, change: f64) {
+        if change > 0.0 {
+            self.capped.fetch_add(1, Ordering::Relaxed);
+            self.max_change.fetch_max(change.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn summary(&self) -> Summary {
+        Summary {
+            capped: self.capped.load(Ordering::Relaxed),
+            max_change: f64::from_bits(self.max_change.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// 统计一个连通块的全部轮廓 (外轮廓与不接触边界的孔洞)，只在样条模式下有意义
+    ///
+    /// 轮廓的拆分方式与 visioncortex 的 `Cluster::image_to_splines` 相同。
+    pub(crate) fn measure(&self, image: &BinaryImage, cfg: &TracerConfig) {
+        if !matches!(cfg.mode, PathSimplifyMode::Spline) {
+            return;
+        }
+        let mut outer = image.clone();
+        let mut boundaries = Vec::new();
+        for hole in image.negative().to_clusters(false).iter() {
+            if hole.rect.left == 0
+                || hole.rect.top == 0
+                || hole.rect.right as usize == image.width
+                || hole.rect.bottom as usize == image.height
+            {
+                continue;
+            }
+            for p in hole.points.iter() {
+                outer.set_pixel(p.x as usize, p.y as usize, true);
+            }
+            boundaries.push(hole.to_binary_image());
+        }
+        boundaries.insert(0, outer);
+
+        let corner_threshold = (cfg.corner_threshold as f64).to_radians();
+        for (i, boundary) in boundaries.iter().enumerate() {
+            let path = PathI32::image_to_path(boundary, i == 0, PathSimplifyMode::Polygon);
+            if path.is_empty() {
+                continue;
+            }
+            let smooth = |iterations| path.smooth(corner_threshold, OUTSET_RATIO, cfg.length_threshold, iterations).len();
+            let capped_len = smooth(cfg.max_iterations.max(1));
+            let next_len = smooth(cfg.max_iterations.max(1) + 1);
+            self.record(next_len.saturating_sub(capped_len) as f64 / capped_len.max(1) as f64);
+        }
+    }
+}
+
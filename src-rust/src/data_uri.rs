@@ -0,0 +1,63 @@
+//! Data URI 输出：追踪结果直接作为 `<img src>` 可用的 `data:image/svg+xml` 字符串
+
+use wasm_bindgen::prelude::*;
+
+use crate::{trace_rgba_with_options, TraceOptions};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// 标准 Base64 (带 `=` 填充)
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// 百分号编码：保留 URI 中可以原样出现的 ASCII 字符，其余字节 (含换行、引号、`#`、`%` 与非 ASCII) 编码为 `%XX`
+///
+/// SVG 文本大部分是数字、字母与空格，只有少量字符需要编码，结果通常比 Base64 (固定增大 1/3) 更短。
+/// 空格保留原样：HTML 的 `src` 属性可以直接使用，写进 CSS 的 `url()` 时需要加引号。
+fn encode_percent(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 8);
+    for &b in text.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b' ' | b'-' | b'_' | b'.' | b'~' | b'!' | b'$' | b'&'
+            | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' | b'/' | b'?' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// 矢量化并返回 `data:image/svg+xml` 形式的 Data URI，可直接用作 `<img>` 的 `src`
+///
+/// `base64` 为 `true` 时输出 `data:image/svg+xml;base64,...`；为 `false` 时输出百分号编码的
+/// `data:image/svg+xml,...`，对 SVG 文本通常更短。SVG 内容与 `trace_rgba_with_options` 相同。
+#[wasm_bindgen]
+pub fn trace_rgba_to_data_uri(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    base64: bool,
+) -> Result<String, JsValue> {
+    let svg = trace_rgba_with_options(rgba_data, width, height, options)?;
+    Ok(if base64 {
+        format!("data:image/svg+xml;base64,{}", encode_base64(svg.as_bytes()))
+    } else {
+        format!("data:image/svg+xml,{}", encode_percent(&svg))
+    })
+}
@@ -0,0 +1,211 @@
+//! 输入图片解码：在 `image` 的基础上补充 CMYK JPEG 的处理
+//! 
+//! 印刷流程导出的 JPEG 常为 CMYK (4 通道)。`image` 对这类文件统一按 Adobe 反相约定转换，
+//! 没有 Adobe APP14 标记的文件颜色会整体反相；这里单独解码并按标记决定是否反相。
+
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use image::RgbaImage;
+use jpeg_decoder::PixelFormat;
+
+/// 判断 JPEG 在图像数据之前是否带有 Adobe APP14 标记段
+/// 
+/// Photoshop 等 Adobe 软件写出的 CMYK JPEG 以反相形式存储 (0 表示满墨)，并写入该标记。
+fn has_adobe_marker(bytes: &[u8]) -> bool {
+    let mut i = 2;
+    while i + 4 <= bytes.len() {
+        if bytes[i] != 0xFF {
+            return false;
+        }
+        let marker = bytes[i + 1];
+        // 填充字节
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        // SOS 之后是熵编码数据，不再有 APP 段
+        if marker == 0xDA {
+            return false;
+        }
+        let len = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+        if marker == 0xEE && bytes[i + 4..].starts_with(b"Adobe") {
+            return true;
+        }
+        i += 2 + len;
+    }
+    false
+}
+
+/// 把 jpeg-decoder 输出的 CMYK 数据转换为 RGBA
+/// 
+/// jpeg-decoder 总是按 Adobe 反相约定解码，输出的是墨量 (255 表示满墨)；
+/// 没有 Adobe 标记的文件按非反相存储处理，需要再反相一次。
+/// 转换使用朴素公式 `R = (255 - C) * (255 - K) / 255`，不处理嵌入的 ICC 配置文件。
+fn cmyk_to_rgba(cmyk: &[u8], inverted: bool) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(cmyk.len());
+    for px in cmyk.chunks_exact(4) {
+        let ink = |v: u8| if inverted { v as u32 } else { 255 - v as u32 };
+        let k = 255 - ink(px[3]);
+        for &v in &px[..3] {
+            rgba.push(((255 - ink(v)) * k / 255) as u8);
+        }
+        rgba.push(255);
+    }
+    rgba
+}
+
+/// 尝试按 CMYK JPEG 解码；不是 CMYK JPEG 时返回 `Ok(None)`
+fn decode_cmyk_jpeg(bytes: &[u8]) -> Result<Option<RgbaImage>, JsValue> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return Ok(None);
+    }
+    
+    let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(bytes));
+    // 头部都读不出来时交给 image 报告通用错误
+    if decoder.read_info().is_err() {
+        return Ok(None);
+    }
+    let Some(info) = decoder.info() else {
+        return Ok(None);
+    };
+    if info.pixel_format != PixelFormat::CMYK32 {
+        return Ok(None);
+    }
+    
+    let cmyk = decoder.decode().map_err(|e| {
+        JsValue::from_str(&format!(
+            "CMYK JPEG 解码失败: {}。该图片使用印刷用的 CMYK 色彩模式，可先转换为 RGB 后重试",
+            e
+        ))
+    })?;
+    
+    let rgba = cmyk_to_rgba(&cmyk, has_adobe_marker(bytes));
+    RgbaImage::from_raw(info.width as u32, info.height as u32, rgba)
+        .map(Some)
+        .ok_or_else(|| JsValue::from_str("CMYK JPEG 解码失败: 像素数据长度与图片尺寸不符"))
+}
+
+/// 解码任意支持格式的图片字节为 RGBA，CMYK JPEG 会先正确转换为 RGB
+pub(crate) fn load_rgba(bytes: &[u8]) -> Result<RgbaImage, JsValue> {
+    if let Some(img) = decode_cmyk_jpeg(bytes)? {
+        return Ok(img);
+    }
+    
+    image::load_from_memory(bytes)
+        .map(|img| img.to_rgba8())
+        .map_err(|e| JsValue::from_str(&format!("图片解析失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按位写出熵编码数据，`0xFF` 之后补 `0x00`
+    struct BitWriter {
+        bytes: Vec<u8>,
+        current: u32,
+        filled: u32,
+    }
+
+    impl BitWriter {
+        fn put(&mut self, value: u32, bits: u32) {
+            for i in (0..bits).rev() {
+                self.current = (self.current << 1) | ((value >> i) & 1);
+                self.filled += 1;
+                if self.filled == 8 {
+                    self.flush_byte();
+                }
+            }
+        }
+
+        fn flush_byte(&mut self) {
+            let byte = self.current as u8;
+            self.bytes.push(byte);
+            if byte == 0xFF {
+                self.bytes.push(0x00);
+            }
+            self.current = 0;
+            self.filled = 0;
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            // 剩余位以 1 填充
+            while self.filled != 0 {
+                self.put(1, 1);
+            }
+            self.bytes
+        }
+    }
+
+    fn segment(out: &mut Vec<u8>, marker: u8, payload: &[u8]) {
+        out.extend([0xFF, marker]);
+        out.extend(((payload.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(payload);
+    }
+
+    /// 构造一张 8×8、单一颜色的基线 CMYK JPEG，`stored` 为文件中存储的四个通道值
+    ///
+    /// 量化表全为 1，只有直流系数；直流表的类别 0~11 各用 4 位定长码，交流表只有一个 1 位的 EOB。
+    fn cmyk_jpeg(stored: [u8; 4], adobe: bool) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8];
+        if adobe {
+            // 版本 100，两组标志为 0，变换 0 (未变换的 CMYK)
+            segment(&mut out, 0xEE, &[b'A', b'd', b'o', b'b', b'e', 0, 100, 0, 0, 0, 0, 0]);
+        }
+        let mut dqt = vec![0];
+        dqt.extend([1; 64]);
+        segment(&mut out, 0xDB, &dqt);
+        let mut sof = vec![8, 0, 8, 0, 8, 4];
+        for id in 1..=4 {
+            sof.extend([id, 0x11, 0]);
+        }
+        segment(&mut out, 0xC0, &sof);
+        let mut dc = vec![0x00, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        dc.extend(0..12);
+        segment(&mut out, 0xC4, &dc);
+        segment(&mut out, 0xC4, &[0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00]);
+        segment(&mut out, 0xDA, &[4, 1, 0x00, 2, 0x00, 3, 0x00, 4, 0x00, 0, 63, 0]);
+
+        let mut writer = BitWriter { bytes: Vec::new(), current: 0, filled: 0 };
+        for value in stored {
+            // 均匀块的直流系数为 8 × (样本 - 128)，各通道的预测值从 0 开始
+            let diff = 8 * (value as i32 - 128);
+            let category = 32 - diff.unsigned_abs().leading_zeros();
+            writer.put(category, 4);
+            let bits = if diff < 0 { diff - 1 } else { diff };
+            writer.put(bits as u32 & ((1 << category) - 1), category);
+            writer.put(0, 1);
+        }
+        out.extend(writer.finish());
+        out.extend([0xFF, 0xD9]);
+        out
+    }
+
+    fn assert_all_pixels(img: &RgbaImage, expected: [u8; 4]) {
+        assert_eq!(img.dimensions(), (8, 8));
+        for px in img.pixels() {
+            let close = px.0.iter().zip(expected).all(|(&a, b)| a.abs_diff(b) <= 2);
+            assert!(close, "{:?} != {:?}", px.0, expected);
+        }
+    }
+
+    #[test]
+    fn plain_cmyk_jpeg_decodes_to_rgb() {
+        // 未反相存储：满品红 + 满黄 = 红色
+        let bytes = cmyk_jpeg([0, 255, 255, 0], false);
+        assert!(!has_adobe_marker(&bytes));
+        assert_all_pixels(&load_rgba(&bytes).unwrap(), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn adobe_cmyk_jpeg_is_not_inverted_twice() {
+        // Adobe 反相存储的同一颜色
+        let bytes = cmyk_jpeg([255, 0, 0, 255], true);
+        assert!(has_adobe_marker(&bytes));
+        assert_all_pixels(&load_rgba(&bytes).unwrap(), [255, 0, 0, 255]);
+
+        // 满黄加一半黑墨
+        let bytes = cmyk_jpeg([255, 255, 0, 128], true);
+        assert_all_pixels(&load_rgba(&bytes).unwrap(), [127, 127, 0, 255]);
+    }
+}
@@ -0,0 +1,57 @@
+//! 重复形状去重：平移后相同的路径只在 `<defs>` 中输出一次，各处改用 `<use>` 引用
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use visioncortex::{Color, CompoundPath, PointF64};
+
+use crate::{primitives, svg_tools, TracerConfig};
+
+/// 路径数据不超过该长度 (字节) 的形状直接输出：`<use>` 本身的开销与之相当，共享不会变小
+const MIN_SHARED_LEN: usize = 24;
+
+/// 找出重复出现的形状，返回 `<defs>` 内容与每条路径引用的形状编号及其平移量
+///
+/// `to_svg_string` 输出的路径数据以首个点为原点，平移量单独放在 `transform` 中，
+/// 因此按输出精度取整后路径数据相同的两条路径就是平移后相同的形状，直接以路径数据为键匹配。
+/// 会被识别为图元的路径不参与。
+pub(crate) fn shared_shapes(
+    path_results: &[(CompoundPath, Color)],
+    cfg: &TracerConfig,
+    origin: PointF64,
+) -> (String, Vec<Option<(usize, PointF64)>>) {
+    let encoded: Vec<Option<(String, PointF64)>> = path_results
+        .iter()
+        .map(|(path, _)| {
+            if cfg.detect_primitives && primitives::detect(path).is_some() {
+                return None;
+            }
+            let (d, offset) = path.to_svg_string(true, origin, cfg.path_precision);
+            (d.len() > MIN_SHARED_LEN).then_some((d, offset))
+        })
+        .collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (d, _) in encoded.iter().flatten() {
+        *counts.entry(d.as_str()).or_default() += 1;
+    }
+
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut defs = String::new();
+    let refs = encoded
+        .iter()
+        .map(|entry| {
+            let (d, offset) = entry.as_ref()?;
+            if counts[d.as_str()] < 2 {
+                return None;
+            }
+            let next = ids.len();
+            let id = *ids.entry(d.as_str()).or_insert_with(|| {
+                writeln!(defs, r#"<path id="snapsvg-shape-{}" d="{}"/>"#, next, svg_tools::close_subpaths(d)).ok();
+                next
+            });
+            Some((id, *offset))
+        })
+        .collect();
+
+    (defs, refs)
+}
@@ -0,0 +1,43 @@
+//! 差异追踪：只矢量化两帧之间发生变化的像素，用于洋葱皮动画与增量矢量动画
+
+use wasm_bindgen::prelude::*;
+
+use crate::{check_rgba_len, trace_rgba_masked, TraceOptions};
+
+/// 逐像素比较两帧，RGBA 四个通道中最大的差值不小于 `threshold` 的像素记为变化 (掩码 255)
+fn change_mask(a: &[u8], b: &[u8], threshold: u8) -> Vec<u8> {
+    a.chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .map(|(pa, pb)| {
+            let diff = pa.iter().zip(pb).map(|(&x, &y)| x.abs_diff(y)).max().unwrap_or(0);
+            if diff >= threshold.max(1) { 255 } else { 0 }
+        })
+        .collect()
+}
+
+/// 只矢量化帧 `a` 到帧 `b` 之间变化的区域，输出 `b` 在这些区域的内容
+///
+/// 任一通道 (含 alpha) 的差值达到 `threshold` 即视为变化，`threshold` 为 0 时按 1 处理
+/// (完全相同的像素总是视为未变化)。未变化的像素按 `trace_rgba_masked` 的方式处理为透明，
+/// 输出中保持空白，叠放在帧 `a` 的矢量图之上即得到帧 `b`。
+/// 变化区域中 `b` 本身透明的像素 (内容被擦除) 同样不会产生路径。
+#[wasm_bindgen]
+pub fn trace_rgba_diff(
+    a: &[u8],
+    b: &[u8],
+    width: u32,
+    height: u32,
+    threshold: u8,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    if a.len() != b.len() {
+        return Err(JsValue::from_str(&format!(
+            "两帧的数据长度不一致: a 为 {} 字节, b 为 {} 字节",
+            a.len(), b.len()
+        )));
+    }
+    check_rgba_len(a, width as usize, height as usize)?;
+
+    let mask = change_mask(a, b, threshold);
+    trace_rgba_masked(b, &mask, width, height, options)
+}
@@ -0,0 +1,314 @@
+//! 几何辅助函数：路径展平、加密采样等
+//! 
+//! 供图元识别、多边形导出等需要逐点处理路径的功能共享
+
+use visioncortex::{CompoundPath, CompoundPathElement, PathF64, PointF64};
+
+/// 计算三次贝塞尔曲线在 `t` 处的点
+fn cubic_bezier(p0: PointF64, p1: PointF64, p2: PointF64, p3: PointF64, t: f64) -> PointF64 {
+    let mt = 1.0 - t;
+    let a = mt * mt * mt;
+    let b = 3.0 * mt * mt * t;
+    let c = 3.0 * mt * t * t;
+    let d = t * t * t;
+    PointF64::new(
+        a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    )
+}
+
+/// 把单条子路径展平为折线点（闭合路径的首尾点相同）
+/// 
+/// 折线路径原样返回；样条的每段三次曲线均匀采样 `segments_per_curve` 段。
+pub(crate) fn flatten_element(element: &CompoundPathElement, segments_per_curve: usize) -> Vec<PointF64> {
+    match element {
+        CompoundPathElement::PathI32(p) => p.path.iter().map(|p| p.to_point_f64()).collect(),
+        CompoundPathElement::PathF64(p) => p.path.clone(),
+        CompoundPathElement::Spline(s) => {
+            let segments = segments_per_curve.max(1);
+            let mut points = Vec::with_capacity(s.num_curves() * segments + 1);
+            if let Some(&first) = s.points.first() {
+                points.push(first);
+            }
+            for curve in s.get_control_points() {
+                for i in 1..=segments {
+                    let t = i as f64 / segments as f64;
+                    points.push(cubic_bezier(curve[0], curve[1], curve[2], curve[3], t));
+                }
+            }
+            points
+        }
+    }
+}
+
+/// 在相邻点之间插值，保证任意两个相邻采样点的距离不超过 `max_step`
+pub(crate) fn densify(points: &[PointF64], max_step: f64) -> Vec<PointF64> {
+    let mut dense = Vec::with_capacity(points.len());
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let steps = (a.distance_to(b) / max_step).ceil().max(1.0) as usize;
+        for i in 0..steps {
+            let t = i as f64 / steps as f64;
+            dense.push(PointF64::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t));
+        }
+    }
+    if let Some(&last) = points.last() {
+        dense.push(last);
+    }
+    dense
+}
+
+/// 按弦高误差自适应细分三次贝塞尔曲线，追加除起点外的采样点
+fn flatten_cubic(p0: PointF64, p1: PointF64, p2: PointF64, p3: PointF64, flatness: f64, depth: u32, out: &mut Vec<PointF64>) {
+    // 控制点到弦的距离上界：两控制点均足够接近弦即认为已足够平直
+    let dx = p3.x - p0.x;
+    let dy = p3.y - p0.y;
+    let len = (dx * dx + dy * dy).sqrt();
+    let deviation = |p: PointF64| {
+        if len < 1e-9 {
+            p.distance_to(p0)
+        } else {
+            ((p.x - p0.x) * dy - (p.y - p0.y) * dx).abs() / len
+        }
+    };
+    if depth >= 16 || (deviation(p1) <= flatness && deviation(p2) <= flatness) {
+        out.push(p3);
+        return;
+    }
+    
+    // de Casteljau 在 t = 0.5 处一分为二
+    let mid = |a: PointF64, b: PointF64| PointF64::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+    let p01 = mid(p0, p1);
+    let p12 = mid(p1, p2);
+    let p23 = mid(p2, p3);
+    let p012 = mid(p01, p12);
+    let p123 = mid(p12, p23);
+    let center = mid(p012, p123);
+    flatten_cubic(p0, p01, p012, center, flatness, depth + 1, out);
+    flatten_cubic(center, p123, p23, p3, flatness, depth + 1, out);
+}
+
+/// 把单条子路径展平为折线点，曲线与折线的最大偏差不超过 `flatness` (像素)
+/// 
+/// 与 `flatten_element` 的均匀采样不同，平直段只取很少的点，弯曲处自动加密。
+pub(crate) fn flatten_element_adaptive(element: &CompoundPathElement, flatness: f64) -> Vec<PointF64> {
+    match element {
+        CompoundPathElement::Spline(s) => {
+            let flatness = flatness.max(1e-3);
+            let mut points = Vec::with_capacity(s.num_curves() * 4 + 1);
+            if let Some(&first) = s.points.first() {
+                points.push(first);
+            }
+            for curve in s.get_control_points() {
+                flatten_cubic(curve[0], curve[1], curve[2], curve[3], flatness, 0, &mut points);
+            }
+            points
+        }
+        _ => flatten_element(element, 1),
+    }
+}
+
+/// 闭合折线的有向面积 (鞋带公式，像素坐标系 y 轴向下时，屏幕上顺时针为正)
+/// 
+/// 首尾点是否重复不影响结果；少于 3 个点时为 0。
+pub(crate) fn polygon_area(points: &[PointF64]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// 复合路径的填充面积 (平方像素)：各子路径有向面积之和的绝对值
+/// 
+/// 拟合结果中孔洞与外轮廓方向相反，按非零环绕规则渲染时孔洞内不填充，求和即扣除孔洞面积；
+/// 同向的子路径 (如合并后的多个色块) 面积相加。曲线按每段 4 个采样点展平。
+/// 所有按面积判断的功能 (路径预算、同色合并的加权、清单中的面积等) 都应使用此函数。
+pub(crate) fn compound_path_area(path: &CompoundPath) -> f64 {
+    path.paths
+        .iter()
+        .map(|element| polygon_area(&flatten_element(element, 4)))
+        .sum::<f64>()
+        .abs()
+}
+
+/// 把闭合点序列 (首尾点相同) 的每个点沿法向偏移 `distance`
+/// 
+/// 法向取相邻两点连线的垂线；`distance` 为正时沿正向环 (有向面积为正) 的外侧偏移。
+fn offset_ring(points: &mut [PointF64], distance: f64) {
+    let n = points.len().saturating_sub(1);
+    if n < 3 {
+        return;
+    }
+    let original = points[..n].to_vec();
+    for i in 0..n {
+        let prev = original[(i + n - 1) % n];
+        let next = original[(i + 1) % n];
+        let (dx, dy) = (next.x - prev.x, next.y - prev.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len < 1e-9 {
+            continue;
+        }
+        points[i] = PointF64::new(original[i].x + dy / len * distance, original[i].y - dx / len * distance);
+    }
+    points[n] = points[0];
+}
+
+/// 把复合路径覆盖的区域向外扩张约 `distance` 像素：外轮廓外扩，孔洞收缩
+/// 
+/// 外轮廓按面积最大的子路径判定，与其方向相反的子路径视为孔洞。样条直接偏移控制点，
+/// 对亚像素级的扩张量足够准确；整数折线会被转换为浮点折线。
+pub(crate) fn dilate(path: &mut CompoundPath, distance: f64) {
+    let outer_sign = path
+        .paths
+        .iter()
+        .map(|element| polygon_area(&flatten_element(element, 4)))
+        .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .map(f64::signum)
+        .unwrap_or(1.0);
+    let distance = distance * outer_sign;
+
+    for element in &mut path.paths {
+        if let CompoundPathElement::PathI32(p) = element {
+            let points = p.path.iter().map(|p| p.to_point_f64()).collect();
+            *element = CompoundPathElement::PathF64(PathF64 { path: points });
+        }
+        match element {
+            CompoundPathElement::PathF64(p) => offset_ring(&mut p.path, distance),
+            CompoundPathElement::Spline(s) => offset_ring(&mut s.points, distance),
+            CompoundPathElement::PathI32(_) => {}
+        }
+    }
+}
+
+/// 闭合环的周长
+fn ring_length(ring: &[PointF64]) -> f64 {
+    ring.iter().zip(ring.iter().cycle().skip(1)).map(|(a, b)| a.distance_to(*b)).sum()
+}
+
+/// 复合路径全部子路径 (均按闭合处理) 的总长度，样条按 0.1 像素的偏差展平，略短于真实弧长
+pub(crate) fn path_length(path: &CompoundPath) -> f64 {
+    path.paths.iter().map(|element| ring_length(&flatten_element_adaptive(element, 0.1))).sum()
+}
+
+/// 复合路径的平均宽度：`2 * 面积 / 周长`
+/// 
+/// 对长条形状约等于条带的宽度 (周长约为长度的两倍)，对接近圆形或方形的形状约为直径的一半。
+pub(crate) fn mean_width(path: &CompoundPath) -> f64 {
+    let rings: Vec<Vec<PointF64>> = path.paths.iter().map(|element| flatten_element(element, 4)).collect();
+    let area = rings.iter().map(|ring| polygon_area(ring)).sum::<f64>().abs();
+    let perimeter: f64 = rings.iter().map(|ring| ring_length(ring)).sum();
+    if perimeter > 0.0 { 2.0 * area / perimeter } else { 0.0 }
+}
+
+/// 复合路径的所有坐标是否都是有限值 (退化聚类的拟合可能产生 NaN)
+pub(crate) fn is_finite(path: &CompoundPath) -> bool {
+    let finite = |p: &PointF64| p.x.is_finite() && p.y.is_finite();
+    path.paths.iter().all(|element| match element {
+        CompoundPathElement::PathI32(_) => true,
+        CompoundPathElement::PathF64(p) => p.path.iter().all(finite),
+        CompoundPathElement::Spline(s) => s.points.iter().all(finite),
+    })
+}
+
+/// 复合路径的首个点，`CompoundPath::to_svg_string` 以它作为输出的偏移
+pub(crate) fn first_point(path: &CompoundPath) -> Option<PointF64> {
+    match path.paths.first()? {
+        CompoundPathElement::PathI32(p) => p.path.first().map(|q| q.to_point_f64()),
+        CompoundPathElement::PathF64(p) => p.path.first().copied(),
+        CompoundPathElement::Spline(p) => p.points.first().copied(),
+    }
+}
+
+/// 复合路径所有子路径存储的点数之和 (样条含控制点)
+pub(crate) fn point_count(path: &CompoundPath) -> usize {
+    path.paths.iter().map(|element| match element {
+        CompoundPathElement::PathI32(p) => p.path.len(),
+        CompoundPathElement::PathF64(p) => p.path.len(),
+        CompoundPathElement::Spline(s) => s.points.len(),
+    }).sum()
+}
+
+/// 点 `p` 到直线 `ab` 的距离，`a`、`b` 重合时为到该点的距离
+pub(crate) fn line_distance(p: PointF64, a: PointF64, b: PointF64) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len < 1e-9 {
+        p.distance_to(a)
+    } else {
+        ((p.x - a.x) * dy - (p.y - a.y) * dx).abs() / len
+    }
+}
+
+/// Douglas-Peucker 折线简化，返回每个点是否保留 (首尾总是保留)
+pub(crate) fn douglas_peucker(points: &[PointF64], tolerance: f64) -> Vec<bool> {
+    let mut keep = vec![false; points.len()];
+    if points.len() < 3 {
+        keep.fill(true);
+        return keep;
+    }
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let (a, b) = (points[start], points[end]);
+        let farthest = (start + 1..end)
+            .map(|i| (i, line_distance(points[i], a, b)))
+            .max_by(|x, y| x.1.total_cmp(&y.1));
+        if let Some((i, d)) = farthest {
+            if d > tolerance {
+                keep[i] = true;
+                stack.push((start, i));
+                stack.push((i, end));
+            }
+        }
+    }
+    keep
+}
+
+/// Douglas-Peucker 折线简化
+pub(crate) fn simplify(points: &[PointF64], tolerance: f64) -> Vec<PointF64> {
+    points.iter().zip(douglas_peucker(points, tolerance)).filter(|(_, k)| *k).map(|(p, _)| *p).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> Vec<PointF64> {
+        points.iter().map(|&(x, y)| PointF64::new(x, y)).collect()
+    }
+
+    #[test]
+    fn square_area() {
+        let square = ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        assert_eq!(polygon_area(&square), 100.0);
+        // 首尾重复不影响结果，反向时符号相反
+        let mut closed = square.clone();
+        closed.push(square[0]);
+        assert_eq!(polygon_area(&closed), 100.0);
+        closed.reverse();
+        assert_eq!(polygon_area(&closed), -100.0);
+    }
+
+    #[test]
+    fn triangle_area() {
+        let triangle = ring(&[(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]);
+        assert_eq!(polygon_area(&triangle), 6.0);
+        assert_eq!(polygon_area(&triangle[..2]), 0.0);
+    }
+
+    #[test]
+    fn ring_area_subtracts_hole() {
+        let outer = ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]);
+        let hole = ring(&[(3.0, 3.0), (3.0, 7.0), (7.0, 7.0), (7.0, 3.0), (3.0, 3.0)]);
+        let mut path = CompoundPath::new();
+        path.add_path_f64(PathF64::from_points(outer));
+        path.add_path_f64(PathF64::from_points(hole));
+        assert_eq!(compound_path_area(&path), 84.0);
+    }
+}
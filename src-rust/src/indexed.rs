@@ -0,0 +1,213 @@
+//! 调色板图像追踪：索引色 PNG/GIF 直接读取调色板与索引，把每个调色板颜色当作固定颜色追踪
+//!
+//! `image` 解码时总会把索引展开为 RGBA，丢失 "图像只用到这几种颜色" 的信息，
+//! 重新聚类还可能把相近的调色板颜色合并或拆开；这里改用 `png`/`gif` 解码器直接读取索引。
+
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use visioncortex::{Color, ColorImage};
+
+use crate::{build_svg, check_empty, cluster_and_fit, decode, lines, reset_rng, trace_rgba_with_options, warnings, TraceOptions};
+
+/// 解码得到的索引图像：每像素一个调色板下标
+struct IndexedImage {
+    width: usize,
+    height: usize,
+    indices: Vec<u8>,
+    /// RGB 三元组
+    palette: Vec<Color>,
+    /// 各下标的 alpha，缺省为 255
+    alpha: Vec<u8>,
+    /// 各像素是否位于帧内 (GIF 的帧可以小于画布)，`None` 表示整张画布都被覆盖
+    covered: Option<Vec<bool>>,
+}
+
+/// 读取索引色 PNG，非索引色 (真彩色、灰度) 返回 `None`
+fn decode_indexed_png(bytes: &[u8]) -> Result<Option<IndexedImage>, String> {
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::IDENTITY);
+    let mut reader = decoder.read_info().map_err(|e| format!("PNG 解析失败: {}", e))?;
+    if reader.info().color_type != png::ColorType::Indexed {
+        return Ok(None);
+    }
+    let palette: Vec<Color> = reader
+        .info()
+        .palette
+        .as_deref()
+        .unwrap_or_default()
+        .chunks_exact(3)
+        .map(|c| Color::new(c[0], c[1], c[2]))
+        .collect();
+    let alpha = reader.info().trns.as_deref().unwrap_or_default().to_vec();
+
+    let mut buf = vec![0u8; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).map_err(|e| format!("PNG 解码失败: {}", e))?;
+    let (width, height) = (frame.width as usize, frame.height as usize);
+
+    // 低于 8 位的索引按行紧密打包，逐行解包为每像素一个字节
+    let bits = frame.bit_depth as usize;
+    let per_byte = 8 / bits;
+    let mask = ((1u16 << bits) - 1) as u8;
+    let mut indices = Vec::with_capacity(width * height);
+    for row in buf.chunks(frame.line_size).take(height) {
+        indices.extend((0..width).map(|x| {
+            let byte = row[x / per_byte];
+            let shift = 8 - bits * (x % per_byte + 1);
+            (byte >> shift) & mask
+        }));
+    }
+    Ok(Some(IndexedImage { width, height, indices, palette, alpha, covered: None }))
+}
+
+/// 读取 GIF 的第一帧 (帧之外的画布区域视为透明，记录在 `covered` 中)
+fn decode_indexed_gif(bytes: &[u8]) -> Result<IndexedImage, String> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(Cursor::new(bytes)).map_err(|e| format!("GIF 解析失败: {}", e))?;
+    let (width, height) = (decoder.width() as usize, decoder.height() as usize);
+    let global = decoder.global_palette().map(<[u8]>::to_vec);
+    let frame = decoder
+        .read_next_frame()
+        .map_err(|e| format!("GIF 帧解码失败: {}", e))?
+        .ok_or("GIF 不包含任何帧")?;
+
+    let rgb = frame.palette.clone().or(global).unwrap_or_default();
+    let palette: Vec<Color> = rgb.chunks_exact(3).map(|c| Color::new(c[0], c[1], c[2])).collect();
+    let mut alpha = vec![255u8; palette.len()];
+    if let Some(a) = frame.transparent.and_then(|t| alpha.get_mut(t as usize)) {
+        *a = 0;
+    }
+
+    // 画布中帧没有覆盖的部分另行记录，不占用调色板下标 (256 色的调色板可能没有空位)
+    let (left, top, fw) = (frame.left as usize, frame.top as usize, frame.width as usize);
+    let fh = frame.buffer.len() / fw.max(1);
+    let full = left == 0 && top == 0 && fw >= width && fh >= height;
+    let mut covered = (!full).then(|| vec![false; width * height]);
+    let mut indices = vec![0u8; width * height];
+    for (y, row) in frame.buffer.chunks(fw.max(1)).enumerate() {
+        let ty = top + y;
+        if ty >= height {
+            break;
+        }
+        for (x, &index) in row.iter().enumerate().take(width.saturating_sub(left)) {
+            indices[ty * width + left + x] = index;
+            if let Some(covered) = covered.as_mut() {
+                covered[ty * width + left + x] = true;
+            }
+        }
+    }
+    Ok(IndexedImage { width, height, indices, palette, alpha, covered })
+}
+
+/// 矢量化图片字节：索引色 PNG/GIF 按调色板直接追踪，其它图片回退为普通的 RGBA 聚类
+///
+/// 索引色图像的每个调色板颜色都作为固定颜色参与聚类：关闭颜色量化 (`color_precision` 为 8)，
+/// 分层阈值降为最小，相近的调色板颜色不会被合并，输出颜色严格取自调色板 (只含实际用到的颜色)，
+/// `color_count` 不起作用。调色板中 alpha 为 0 的颜色 (PNG 的 `tRNS`、GIF 的透明色) 被抠除，
+/// 不产生路径；半透明的调色板颜色按不透明处理。适合复古游戏精灵等已量化的素材，
+/// 省去调色板提取，结果更干净、更快。
+///
+/// GIF 只追踪第一帧，动画请使用 `trace_gif_to_svg`。真彩色、灰度 PNG 与其它格式与先解码再调用
+/// `trace_rgba_with_options` 的结果相同。
+#[wasm_bindgen]
+pub fn trace_indexed_image(image_bytes: &[u8], options: &TraceOptions) -> Result<String, JsValue> {
+    let indexed = if image_bytes.starts_with(b"\x89PNG") {
+        decode_indexed_png(image_bytes).map_err(|e| JsValue::from_str(&e))?
+    } else if image_bytes.starts_with(b"GIF8") {
+        Some(decode_indexed_gif(image_bytes).map_err(|e| JsValue::from_str(&e))?)
+    } else {
+        None
+    };
+    let Some(image) = indexed else {
+        let rgba = decode::load_rgba(image_bytes)?;
+        return trace_rgba_with_options(rgba.as_raw(), rgba.width(), rgba.height(), options);
+    };
+
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+
+    let opaque = |p: usize| {
+        let i = image.indices[p];
+        image.covered.as_ref().is_none_or(|covered| covered[p]) && image.alpha.get(i as usize).copied().unwrap_or(255) > 0
+    };
+    let mut used = [false; 256];
+    let mut pixels = Vec::with_capacity(image.indices.len() * 4);
+    for (p, &i) in image.indices.iter().enumerate() {
+        let color = image.palette.get(i as usize).copied().unwrap_or(Color::new(0, 0, 0));
+        let alpha = if opaque(p) { 255 } else { 0 };
+        used[i as usize] |= alpha > 0;
+        pixels.extend_from_slice(&[color.r, color.g, color.b, alpha]);
+    }
+    let (w, h) = (image.width, image.height);
+
+    let mut cfg = options.to_config();
+    if let Some(threshold) = cfg.line_extraction {
+        return Ok(lines::trace_lines(&pixels, w, h, threshold, &cfg));
+    }
+    let palette: Vec<Color> = (0..256)
+        .filter(|&i| used[i])
+        .filter_map(|i| image.palette.get(i).copied())
+        .collect();
+    cfg.key_transparent = (0..image.indices.len()).any(|p| !opaque(p));
+    cfg.color_precision = 8;
+    cfg.layer_difference = 1;
+    cfg.refine_passes = 1;
+    cfg.palette = (!palette.is_empty()).then_some(palette);
+
+    let path_results = cluster_and_fit(ColorImage { pixels, width: w, height: h }, &cfg);
+    check_empty(&path_results, &cfg)?;
+
+    Ok(build_svg(w, h, &path_results, &cfg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fills;
+
+    /// 256 色全局调色板的单帧 GIF，下标 `i` 的颜色为 `(i, 255 - i, 128)`，最后一项为 `(200, 30, 30)`
+    fn full_palette_gif(canvas: (u16, u16), frame: gif::Frame) -> Vec<u8> {
+        let mut palette: Vec<u8> = (0..=255u8).flat_map(|i| [i, 255 - i, 128]).collect();
+        palette[765..].copy_from_slice(&[200, 30, 30]);
+        let mut bytes = Vec::new();
+        let mut encoder = gif::Encoder::new(&mut bytes, canvas.0, canvas.1, &palette).unwrap();
+        encoder.write_frame(&frame).unwrap();
+        drop(encoder);
+        bytes
+    }
+
+    /// 左半为下标 255、右半为下标 0 的 `width` x 16 帧
+    fn halves(width: u16) -> gif::Frame<'static> {
+        let pixels: Vec<u8> = (0..16).flat_map(|_| (0..width).map(move |x| if x < width / 2 { 255 } else { 0 })).collect();
+        gif::Frame::from_indexed_pixels(width, 16, pixels, None)
+    }
+
+    #[test]
+    fn last_palette_entry_stays_opaque_without_a_transparent_index() {
+        let bytes = full_palette_gif((32, 16), halves(32));
+        let image = decode_indexed_gif(&bytes).unwrap();
+        assert_eq!(image.palette.len(), 256);
+        assert!(image.alpha.iter().all(|&a| a == 255) && image.covered.is_none());
+
+        let svg = trace_indexed_image(&bytes, &TraceOptions::new()).unwrap();
+        let mut colors = fills(&svg);
+        colors.sort();
+        assert_eq!(colors, ["#00ff80", "#c81e1e"], "{}", svg);
+    }
+
+    #[test]
+    fn canvas_outside_a_full_palette_frame_is_transparent() {
+        let mut frame = halves(24);
+        frame.left = 8;
+        let bytes = full_palette_gif((32, 16), frame);
+        let image = decode_indexed_gif(&bytes).unwrap();
+        let covered = image.covered.as_ref().unwrap();
+        assert_eq!(covered.iter().filter(|&&c| c).count(), 24 * 16);
+        assert!(!covered[0] && covered[8]);
+
+        let svg = trace_indexed_image(&bytes, &TraceOptions::new()).unwrap();
+        let mut colors = fills(&svg);
+        colors.sort();
+        assert_eq!(colors, ["#00ff80", "#c81e1e"], "{}", svg);
+    }
+}
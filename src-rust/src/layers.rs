@@ -0,0 +1,57 @@
+//! 分色图层输出：每种颜色一份独立的 SVG 文档，供逐色加工的切割机等分层导入
+
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use visioncortex::{Color, ColorImage, CompoundPath, PointF64};
+
+use crate::{check_rgba_len, cluster_and_fit, reset_rng, svg_root_attrs, svg_root_children, warnings, write_paths, TraceOptions};
+
+/// 矢量化并按颜色拆成多份独立的 SVG
+///
+/// 只做一次追踪，拟合结果按颜色分组 (按首次绘制的顺序)，每组写成一份完整文档。
+/// 每份文档都使用与原图相同的 `viewBox="0 0 width height"` (以及 `units` 对应的
+/// `width`/`height`)，不按图层内容裁剪，因此各文件单独导入后依然彼此对齐，
+/// 按返回顺序叠放即可还原完整图像。同色的多条路径在所属图层内保持原有的绘制顺序。
+#[wasm_bindgen]
+pub fn trace_rgba_to_layers(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<Vec<String>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let path_results = cluster_and_fit(color_image, &cfg);
+
+    // 按颜色分组，保持首次出现的顺序
+    let mut groups: Vec<(Color, Vec<(CompoundPath, Color)>)> = Vec::new();
+    for (path, color) in path_results {
+        match groups.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, paths)) => paths.push((path, color)),
+            None => groups.push((color, vec![(path, color)])),
+        }
+    }
+
+    let root_attrs = svg_root_attrs(&cfg, w as f64, h as f64);
+    let root_children = svg_root_children(&cfg);
+    let layers = groups
+        .iter()
+        .map(|(_, paths)| {
+            let mut svg = String::with_capacity(1024 * 16);
+            writeln!(svg, r#"<svg{} viewBox="0 0 {} {}">"#, root_attrs, w, h).ok();
+            svg.push_str(&root_children);
+            write_paths(&mut svg, paths, &cfg, PointF64::default());
+            writeln!(svg, "</svg>").ok();
+            svg
+        })
+        .collect();
+
+    Ok(layers)
+}
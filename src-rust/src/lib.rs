@@ -3,20 +3,127 @@
 //! 使用 vtracer/visioncortex 库将位图转换为 SVG 矢量图
 //! 支持 Rayon 并行化以提升大图处理性能
 
+// wasm-bindgen 导出函数只能使用位置参数，参数较多是常态
+#![allow(clippy::too_many_arguments)]
+
 use wasm_bindgen::prelude::*;
 use vtracer::{Config, ColorMode, Hierarchical, ColorImage};
-use visioncortex::color_clusters::{Runner, RunnerConfig, KeyingAction, HIERARCHICAL_MAX};
-use visioncortex::{Color, PathSimplifyMode};
+use visioncortex::color_clusters::{
+    BuilderImpl, Cluster, ClusterIndex, Clusters, ClustersView, NeighbourInfo, Runner, RunnerConfig, KeyingAction,
+    HIERARCHICAL_MAX,
+};
+use visioncortex::{
+    BoundingRect, Color, CompoundPath, CompoundPathElement, PathF64, PathSimplifyMode, PointF64, Shape, Spline,
+};
 use rayon::prelude::*;
-use fastrand;
 use std::fmt::Write;
+use std::sync::Arc;
+
+use color_space::ColorSpace;
+use preprocess::{AlphaMode, HueWindow};
+
+mod analysis;
+mod animation;
+mod bench;
+mod binary;
+mod boundaries;
+mod budget;
+mod cluster_scale;
+mod color_space;
+mod coloring_book;
+mod convergence;
+mod data_uri;
+mod decode;
+mod dedupe;
+mod diff;
+mod geometry;
+mod indexed;
+mod layers;
+mod lines;
+mod manifest;
+mod matte;
+mod memory;
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+mod outer_contour;
+mod palette;
+mod polygons;
+mod postprocess;
+mod precision;
+mod preprocess;
+mod primitives;
+mod progressive;
+mod prune;
+mod reveal;
+mod root_attrs;
+mod svg_tools;
+mod symbol;
+#[cfg(test)]
+mod test_support;
+mod text;
+mod texture;
+mod thumbnail;
+mod tileable;
+mod tree;
+mod triangulate;
+mod warnings;
+mod zoom_levels;
+#[cfg(feature = "raster")]
+mod candidates;
+#[cfg(feature = "raster")]
+pub use candidates::trace_rgba_candidates;
+#[cfg(feature = "raster")]
+mod raster;
+#[cfg(feature = "raster")]
+pub use raster::*;
+#[cfg(feature = "raster")]
+mod spritesheet;
+#[cfg(feature = "raster")]
+pub use spritesheet::{trace_rgba_to_spritesheet, SpriteSheet};
+pub use analysis::{analyze_image, preview_color_count};
+pub use animation::trace_gif_to_svg;
+pub use bench::{trace_benchmark, BenchResult};
+pub use binary::{decode_binary_to_svg, trace_rgba_to_binary};
+pub use boundaries::trace_rgba_boundaries;
+pub use coloring_book::{trace_coloring_book, ColoringBook};
+pub use data_uri::trace_rgba_to_data_uri;
+pub use diff::trace_rgba_diff;
+pub use indexed::trace_indexed_image;
+pub use layers::trace_rgba_to_layers;
+pub use manifest::{trace_rgba_with_manifest, TraceManifest};
+pub use matte::trace_rgba_with_matte;
+pub use memory::trace_rgba_with_memory_budget;
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::trace_core;
+pub use outer_contour::trace_rgba_outer_contour;
+pub use palette::{trace_batch_fixed_palette, trace_rgba_with_palette};
+pub use polygons::trace_rgba_to_polygons;
+pub use postprocess::PostprocessOptions;
+pub use precision::PrecisionOptions;
+pub use progressive::trace_image_progressive;
+pub use svg_tools::{merge_svgs, recolor_svg, validate_svg};
+pub use symbol::trace_rgba_to_symbol;
+pub use thumbnail::trace_rgba_thumbnail;
+pub use tree::trace_rgba_to_tree;
+pub use triangulate::{trace_rgba_to_triangles, TriangleMesh};
+pub use zoom_levels::trace_rgba_zoom_levels;
 
 #[cfg(feature = "wasm-threads")]
 pub use wasm_bindgen_rayon::init_thread_pool;
 
 #[cfg(feature = "wasm-threads")]
 thread_local! {
-    static TLS_FORCE: std::cell::Cell<u32> = std::cell::Cell::new(0);
+    static TLS_FORCE: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+}
+
+/// 重置调用线程的随机数状态，每个公开的追踪入口开始时调用
+/// 
+/// vtracer 选取键控色时通过 `fastrand::Rng::new()` 从线程局部的全局状态派生随机数，
+/// 状态在同一线程的多次调用之间延续；入口处固定种子后，前一次追踪消耗的随机数不会影响下一次，
+/// 在复用的 Worker 中连续追踪不同图像与单独追踪的结果一致。
+/// Rayon 工作线程中不使用随机数，无需重置。
+pub(crate) fn reset_rng() {
+    fastrand::seed(1);
 }
 
 /// 初始化 panic hook，便于调试
@@ -46,6 +153,7 @@ pub fn get_version() -> String {
 // 配置参数转换辅助函数
 // ============================================================================
 
+#[derive(Clone)]
 struct TracerConfig {
     filter_speckle: usize,
     color_precision: i32,
@@ -54,8 +162,130 @@ struct TracerConfig {
     length_threshold: f64,
     max_iterations: usize,
     splice_threshold: i32,
+    /// 输出坐标 (`d` 数据与 `translate` 偏移) 保留的小数位数
     path_precision: Option<u32>,
     mode: PathSimplifyMode,
+    /// 是否尝试把路径识别为 `<rect>`/`<circle>`/`<ellipse>` 图元输出
+    detect_primitives: bool,
+    /// 曲线拟合阶段使用的线程数上限，`None` 表示使用全局线程池
+    max_threads: Option<usize>,
+    /// 描边模式下的线宽，`None` 表示填充模式
+    stroke_width: Option<f64>,
+    /// 描边模式下是否输出 `vector-effect="non-scaling-stroke"`
+    non_scaling_stroke: bool,
+    /// 描边模式下的 `stroke-linejoin`/`stroke-linecap`，`None` 表示不输出 (使用 SVG 默认的 miter/butt)
+    stroke_linejoin: Option<&'static str>,
+    stroke_linecap: Option<&'static str>,
+    /// 聚类与最近色匹配使用的颜色空间
+    color_space: ColorSpace,
+    /// `translate(x,y)` 中两个分量之间的分隔符
+    translate_separator: char,
+    /// `translate` 偏移的小数位数，`None` 表示与 `path_precision` 相同
+    transform_precision: Option<u32>,
+    /// 输入像素的 alpha 语义
+    alpha_mode: AlphaMode,
+    /// 固定调色板：聚类前像素映射到最近的调色板颜色，输出颜色也吸附到调色板
+    palette: Option<Vec<Color>>,
+    /// 内联到 HTML 时省略根元素的 `xmlns` 声明
+    inline: bool,
+    /// 斑点过滤时保留深色的小色块
+    protect_dark: bool,
+    /// 单通道感兴趣区域掩码 (每像素 1 字节)，掩码外的像素视为透明
+    mask: Option<Vec<u8>>,
+    /// 色相窗口，窗口外的像素视为透明
+    hue_window: Option<HueWindow>,
+    /// 聚类时丢弃完全透明的像素，不让它们形成色块
+    key_transparent: bool,
+    /// 完全透明的像素统一为透明黑后参与聚类 (`key_transparent` 为 `false` 时生效)
+    transparent_as_color: bool,
+    /// 输出路径数量上限，`None` 表示不限制
+    max_paths: Option<usize>,
+    /// 聚类前反锐化掩模的强度，0 表示关闭
+    unsharp_amount: f64,
+    /// 自动检测纯色背景并移除
+    auto_background: bool,
+    /// 保留抗锯齿边缘的过渡色，作为细窄的中间色层输出
+    edge_layers: bool,
+    /// 根元素 `width`/`height` 的物理单位：(单位后缀, 每像素对应的单位数)，`None` 表示不输出尺寸
+    physical_unit: Option<(&'static str, f64)>,
+    /// 拟合后每条填充路径向外扩张的距离 (像素)，0 表示不扩张
+    overlap: f64,
+    /// 印刷套准补偿：除最上层外每条填充路径向外扩张的距离 (像素)，0 表示不扩张
+    trap_width: f64,
+    /// 聚类时每轮处理的像素/聚类数
+    batch_size: usize,
+    /// 聚类阶段的图像缩放比例 (0~1)，`None` 表示按原分辨率聚类
+    cluster_scale: Option<f64>,
+    /// 用户指定的颜色数量 (已截断到 2~64，开启 `allow_many_colors` 时为 2~255)，两遍追踪提取主色时使用
+    color_count: u8,
+    /// 追踪遍数：1 为单遍聚类，大于 1 时先用 k-means 修正调色板再聚类
+    refine_passes: u8,
+    /// 线稿模式的梯度阈值，`None` 表示普通的色块追踪
+    line_extraction: Option<f64>,
+    /// 追踪前根据色块面积分布自动确定 `filter_speckle`
+    auto_speckle: bool,
+    /// 聚类时斜向相邻的同色像素是否连通
+    diagonal: bool,
+    /// 无障碍标题与描述，作为根元素的首个子元素输出
+    title: Option<String>,
+    desc: Option<String>,
+    /// 调用方提供的根元素属性 (已校验属性名、转义属性值)
+    root_attributes: Vec<(String, String)>,
+    /// 没有生成任何路径时返回错误而不是空的 SVG
+    fail_on_empty: bool,
+    /// 拟合后合并颜色距离不超过该值的路径，0 表示不合并
+    post_merge_tolerance: f64,
+    /// 层次聚类继续合并的面积上限，`HIERARCHICAL_MAX` 为不限 (叠放分层)
+    hierarchical: u32,
+    /// 把 `viewBox` 裁剪到全部路径的包围盒
+    crop_to_content: bool,
+    /// 裁剪时把包围盒向外取整，保证 `viewBox` 为整数
+    snap_viewbox_to_int: bool,
+    /// 输出画布尺寸：内容包围盒等比缩放后居中放入 `(宽, 高)`，`viewBox` 随之改为该尺寸
+    normalize_to: Option<(u32, u32)>,
+    /// 坐标分别除以画布的宽、高，`viewBox` 为 `0 0 1 1`
+    normalize_coords: bool,
+    /// 点数少于该值的路径不输出，0 表示不过滤
+    min_points_per_path: usize,
+    /// 后处理链：同色合并、简化与紧凑输出
+    postprocess: PostprocessOptions,
+    /// 平移后相同的路径只在 `<defs>` 中输出一次，以 `<use>` 引用
+    dedupe_shapes: bool,
+    /// 平移坐标，使内容包围盒的中心位于 (0, 0)
+    center_origin: bool,
+    /// 把图像视为上下、左右首尾相接的环面追踪，输出可无缝平铺
+    tileable: bool,
+    /// 每个绘制元素写上 `id="snapsvg-layer-N"` (N 为拟合结果中的下标)，供清单与元素对应
+    layer_ids: bool,
+    /// 拟合时统计细分平滑是否在 `max_iterations` 次内收敛，`None` 表示不统计
+    convergence: Option<Arc<convergence::ConvergenceStats>>,
+    /// 疑似文字的深色小色块按精细参数单独追踪
+    text_aware: bool,
+    /// 平均宽度低于该值 (像素) 的细长路径外扩到该宽度，0 表示不处理
+    min_stroke_width: f64,
+    /// 追踪前按重建误差的拐点自动选择 `color_count`
+    auto_color_count: bool,
+    /// 拟合时统计各色块的颜色起伏，判定为纹理的色块改用图案填充
+    detect_texture: bool,
+    /// 按路径下标排列的纹理，输出时写为 `<pattern>`；`None` 表示不使用图案
+    textures: Option<Arc<Vec<Option<texture::Texture>>>>,
+    /// 描边揭示动画的总时长 (毫秒)，`None` 表示不输出动画
+    animate_draw: Option<f64>,
+    /// 输出前合并连续重复的坐标、删去零长度的线段与曲线段
+    clean_commands: bool,
+}
+
+/// 由 `color_count` (截断到 2~64) 推导 `layer_difference`：颜色越多，分层阈值越低
+fn layer_difference_for(color_count: u8) -> i32 {
+    let color_count = color_count.clamp(2, 64) as i32;
+    let layer_diff = if color_count <= 8 {
+        16 - (color_count - 2)
+    } else if color_count <= 24 {
+        10 - (color_count - 9) / 3
+    } else {
+        4
+    };
+    layer_diff.clamp(4, 16)
 }
 
 fn build_config(
@@ -64,26 +294,767 @@ fn build_config(
     corner_threshold: u8,
     filter_speckle: u32,
 ) -> TracerConfig {
-    let color_count_clamped = color_count.max(2).min(64) as i32;
-    
-    let layer_diff = if color_count_clamped <= 8 {
-        16 - (color_count_clamped - 2)
-    } else if color_count_clamped <= 24 {
-        10 - (color_count_clamped - 9) / 3
-    } else {
-        4
-    };
+    let color_count_clamped = color_count.clamp(2, 64) as i32;
+    let layer_difference = layer_difference_for(color_count);
     
     TracerConfig {
         filter_speckle: filter_speckle as usize,
         color_precision: 6, // 降低精度到 6 (忽略低2位)，大幅减少聚类数，防止 OOM 和提高性能
-        layer_difference: layer_diff.max(4).min(16),
+        layer_difference,
         corner_threshold: corner_threshold as i32,
         length_threshold: 4.0,
         max_iterations: 10,
-        splice_threshold: (125 - (path_precision as i32 * 110 / 100)).max(10).min(135),
+        splice_threshold: (125 - (path_precision as i32 * 110 / 100)).clamp(10, 135),
         path_precision: Some(2),
         mode: PathSimplifyMode::Spline,
+        detect_primitives: false,
+        max_threads: None,
+        stroke_width: None,
+        non_scaling_stroke: false,
+        stroke_linejoin: None,
+        stroke_linecap: None,
+        color_space: ColorSpace::Rgb,
+        translate_separator: ',',
+        transform_precision: None,
+        alpha_mode: AlphaMode::Straight,
+        palette: None,
+        inline: false,
+        protect_dark: false,
+        mask: None,
+        hue_window: None,
+        // 透明像素的 RGB 没有意义，所有入口默认都不让它们形成色块
+        key_transparent: true,
+        transparent_as_color: false,
+        max_paths: None,
+        unsharp_amount: 0.0,
+        auto_background: false,
+        edge_layers: false,
+        physical_unit: None,
+        overlap: 0.0,
+        trap_width: 0.0,
+        batch_size: 25600,
+        cluster_scale: None,
+        color_count: color_count_clamped as u8,
+        refine_passes: 1,
+        line_extraction: None,
+        auto_speckle: false,
+        // 沿用 vtracer 的推导规则；layer_difference 至少为 4，因此默认总是关闭
+        diagonal: layer_difference == 0,
+        title: None,
+        desc: None,
+        root_attributes: Vec::new(),
+        fail_on_empty: false,
+        post_merge_tolerance: 0.0,
+        hierarchical: HIERARCHICAL_MAX,
+        crop_to_content: false,
+        snap_viewbox_to_int: false,
+        normalize_to: None,
+        normalize_coords: false,
+        min_points_per_path: 0,
+        postprocess: PostprocessOptions::default(),
+        dedupe_shapes: false,
+        center_origin: false,
+        tileable: false,
+        layer_ids: false,
+        convergence: None,
+        text_aware: false,
+        min_stroke_width: 0.0,
+        auto_color_count: false,
+        detect_texture: false,
+        textures: None,
+        animate_draw: None,
+        // 旧的按位置传参入口保持原有输出，只有 `TraceOptions` 默认开启
+        clean_commands: false,
+    }
+}
+
+/// `allow_many_colors` 下超过 64 色时的 `layer_difference`：按每 64 色递减 1，最低为 1
+/// 
+/// 与 `build_config` 的推导衔接：25~64 色为 4，65~128 色为 3，129~192 色为 2，193~255 色为 1。
+/// 同时提高 `color_precision` (见 `TraceOptions::to_config`)。
+fn many_colors_layer_difference(color_count: u8) -> i32 {
+    (4 - (color_count as i32 - 1) / 64).max(1)
+}
+
+/// 把顶点内角 (度) 换算为 `corner_threshold` 使用的转向角 (度)
+fn corner_threshold_from_angle(angle: f64) -> i32 {
+    (180.0 - angle.clamp(0.0, 180.0)).round() as i32
+}
+
+/// 给 JS 对象设置属性 (对普通对象设置属性不会失败，结果直接忽略)
+fn set_js_property(target: &js_sys::Object, key: &str, value: &JsValue) {
+    let _ = js_sys::Reflect::set(target, &JsValue::from_str(key), value);
+}
+
+/// 返回 `build_config` 根据四个基础参数推导出的完整配置，不执行追踪
+/// 
+/// 用于记录/复现追踪参数，以及排查 `layer_difference`、`splice_threshold`
+/// 等派生值的来源。返回的 JS 对象字段与内部 `TracerConfig` 一一对应：
+/// `filter_speckle`、`color_precision`、`layer_difference`、`corner_threshold`、
+/// `length_threshold`、`max_iterations`、`splice_threshold`、`path_precision`、`mode`。
+#[wasm_bindgen]
+pub fn describe_config(
+    color_count: u8,
+    path_precision: u8,
+    corner_threshold: u8,
+    filter_speckle: u32,
+) -> JsValue {
+    let cfg = build_config(color_count, path_precision, corner_threshold, filter_speckle);
+    
+    let mode = match cfg.mode {
+        PathSimplifyMode::Polygon => "polygon",
+        PathSimplifyMode::Spline => "spline",
+        _ => "none",
+    };
+    
+    let obj = js_sys::Object::new();
+    set_js_property(&obj, "filter_speckle", &(cfg.filter_speckle as u32).into());
+    set_js_property(&obj, "color_precision", &cfg.color_precision.into());
+    set_js_property(&obj, "layer_difference", &cfg.layer_difference.into());
+    set_js_property(&obj, "corner_threshold", &cfg.corner_threshold.into());
+    set_js_property(&obj, "length_threshold", &cfg.length_threshold.into());
+    set_js_property(&obj, "max_iterations", &(cfg.max_iterations as u32).into());
+    set_js_property(&obj, "splice_threshold", &cfg.splice_threshold.into());
+    set_js_property(&obj, "path_precision", &cfg.path_precision.map_or(JsValue::NULL, JsValue::from));
+    set_js_property(&obj, "mode", &mode.into());
+    obj.into()
+}
+
+/// 追踪选项
+/// 
+/// 新功能以字段形式加入，避免继续扩充导出函数的位置参数。
+/// JS 端用法：`const opts = new TraceOptions(); opts.color_count = 8;`
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct TraceOptions {
+    pub color_count: u8,
+    pub path_precision: u8,
+    pub corner_threshold: u8,
+    pub filter_speckle: u32,
+    /// 允许 `color_count` 超过 64 (最多 255，默认关闭)
+    /// 
+    /// 默认上限 64 是有意为之：超过 64 色后分层阈值降到 1~3、颜色量化精度提高到 7~8 位，相近颜色不再合并，
+    /// 色块数量、追踪耗时与输出体积都会成倍增长 (照片类图像常见数倍的路径数与文件大小)，
+    /// 还可能超出 WASM 的内存上限。只在细节丰富、确实需要上百个色层的插画上开启，
+    /// 并配合 `filter_speckle`、`max_paths` 控制输出规模。
+    pub allow_many_colors: bool,
+    /// 识别接近矩形/圆/椭圆的路径并输出为对应图元（默认关闭）
+    pub detect_primitives: bool,
+    /// 本次调用最多使用的 Rayon 线程数（默认不限制，使用全局线程池）
+    /// 
+    /// 原生环境下会为本次调用创建独立的线程池；WASM 中无法临时创建 Worker 线程，
+    /// 建池失败时回退到 `initThreadPool` 创建的全局线程池，此时该选项不生效。
+    pub max_threads: Option<usize>,
+    /// 描边模式：每个色块只输出轮廓线 (`fill="none"` + `stroke`)，适合线稿
+    pub stroke_mode: bool,
+    /// 描边模式下的线宽 (用户坐标单位，即像素)
+    pub stroke_width: f64,
+    /// 描边模式下输出 `vector-effect="non-scaling-stroke"`，缩放 SVG 时线宽保持不变
+    /// 
+    /// Chrome/Firefox/Safari/Edge 均支持；IE 及部分旧版设计软件 (如 Inkscape 1.0 之前)
+    /// 会忽略该属性，此时线宽照常随缩放变化。
+    pub non_scaling_stroke: bool,
+    /// 描边模式下每条路径的 `stroke-linejoin`：`""`（默认，不输出，即 SVG 默认的 `miter`）、
+    /// `"miter"`、`"round"` 或 `"bevel"`
+    /// 
+    /// 追踪出的线稿拐角很多，`miter` 在锐角处会形成尖刺，手绘线稿用 `"round"` 效果更好。
+    /// `line_extraction` 模式下默认为 `"round"`。
+    pub stroke_linejoin: String,
+    /// 描边模式下每条路径的 `stroke-linecap`：`""`（默认，不输出，即 SVG 默认的 `butt`）、
+    /// `"butt"`、`"round"` 或 `"square"`；`line_extraction` 模式下默认为 `"round"`
+    pub stroke_linecap: String,
+    /// 颜色距离所用的颜色空间：`"rgb"`（默认）或 `"lab"`
+    /// 
+    /// `"lab"` 在感知均匀的 CIE LAB 空间中聚类/匹配，照片类图像的调色板更贴近人眼观感。
+    /// 代价是聚类前需要逐像素做一次 sRGB→LAB 转换 (含 pow/cbrt)，
+    /// 原生环境 (release) 下每百万像素约增加 130ms，WASM 中更慢；固定调色板映射的耗时也会相应增加。
+    pub color_space: String,
+    /// 输出 `translate(x y)`（空格分隔）而不是 `translate(x,y)`
+    /// 
+    /// 两种写法都合法；部分下游解析器会把逗号误认为小数点，空格分隔更稳妥。
+    /// 数字本身始终使用 `.` 作为小数点，不受运行环境的区域设置影响。
+    pub space_separated_translate: bool,
+    /// 输入 RGBA 的 alpha 语义：`"straight"`（默认）或 `"premultiplied"`
+    /// 
+    /// 部分来源 (如某些 canvas/WebGL 读回) 给出的是预乘数据，直接聚类会让半透明边缘发暗，
+    /// 形成深色光晕。设为 `"premultiplied"` 时先把 RGB 除以 alpha 还原；
+    /// alpha 为 0 的像素不做除法，保持原值。
+    pub alpha_mode: String,
+    /// 输出坐标保留的小数位数（默认 2），同时作用于 `d` 数据与 `translate` 偏移，可由 `precision` 分项覆盖
+    /// 
+    /// 注意与 `path_precision` 区分：后者控制曲线拟合的细节程度，而不是数字格式。
+    /// 设为 0 时整个元素都输出为整数坐标。
+    pub coordinate_precision: u8,
+    /// 内联模式：省略根元素的 `xmlns` 声明（默认关闭）
+    /// 
+    /// 直接插入 HTML 文档的 `<svg>` 由 HTML 解析器自动归入 SVG 命名空间，声明是多余的；
+    /// 页面中内联大量图标时可以省下这部分体积。作为独立 `.svg` 文件或通过 `<img>` 引用时必须保持关闭。
+    pub inline: bool,
+    /// 斑点过滤时保留深色小色块（默认关闭）
+    /// 
+    /// `filter_speckle` 会把小于阈值的色块全部并入邻居，文字追踪中 i 上的点、标点等也会丢失。
+    /// 开启后亮度低于阈值的小色块不受过滤影响，浅色噪点仍照常清除。
+    /// 实现上需要额外做一次不过滤斑点的聚类，聚类耗时约翻倍。
+    pub protect_dark: bool,
+    /// 非致命警告回调（默认不设置），每个事件调用一次，参数为 `"<类别>: <说明>"` 字符串
+    /// 
+    /// 类别：`clamp` 参数被截断、`option` 无法识别的选项取值、`dropped` 聚类拟合后为空或坐标无效被跳过、
+    /// `fallback` 无法创建独立线程池、`downscale` 超出内存预算而缩小输入、`max_paths` 路径数量预算的保留/合并统计、`empty` 没有生成任何路径。只在调用线程上触发，回调抛出的异常会被忽略。
+    pub on_warning: Option<js_sys::Function>,
+    /// 输出 `<path>` 元素数量的硬上限（默认不限制）
+    /// 
+    /// 拟合完成后按面积保留最大的 `max_paths` 条路径，其余路径并入颜色最接近的保留路径，
+    /// 适合绘制调用次数受限的渲染引擎。作用于最终路径而非聚类，元素数量精确可控；
+    /// 保留与合并的数量通过 `on_warning` 以 `max_paths` 类别报告。
+    pub max_paths: Option<usize>,
+    /// 聚类前的锐化强度（默认 0，关闭）
+    /// 
+    /// 对模糊或放大过的低分辨率图像先做一次半径 1 像素的反锐化掩模，边缘变陡后
+    /// 聚类边界更干净，拐角不再被追成圆团。0.5~1.5 为常用范围；过大会在边缘两侧
+    /// 产生明暗光晕，反而多出细碎色块。
+    pub unsharp_amount: f64,
+    /// 自动检测并移除纯色背景（默认关闭）
+    /// 
+    /// 采样图像四角，颜色一致时视为“主体 + 纯色背景”，从边缘泛洪移除与背景色相近的连通区域，
+    /// 输出中这些区域保持透明；四角颜色不一致 (纹理背景等) 时不做任何移除。
+    /// 检测到的背景色可通过 `trace_rgba_to_result` 返回的 `background` 查看。
+    pub auto_background: bool,
+    /// 保留抗锯齿边缘（默认关闭）
+    /// 
+    /// 默认的层次聚类会把细窄的色块 (宽度约 1~2 像素的过渡带) 当作噪点并入邻居，
+    /// 抗锯齿信息因此丢失，边缘呈阶梯状。开启后，位于高对比度边界上的色块不做细窄判定，
+    /// 且以一半的 `layer_difference` 作为分层阈值，软边缘会变成几层渐变的细条。
+    /// 代价是每条边缘多出 1~3 条路径，边缘较多的图像输出体积可能翻倍。
+    pub edge_layers: bool,
+    /// 根元素 `width`/`height` 的单位：`""`（默认，不输出尺寸）、`"px"`、`"pt"` 或 `"mm"`
+    /// 
+    /// `viewBox` 始终以像素为用户单位，只有 `width`/`height` 换算为物理尺寸，
+    /// 供 Illustrator/Inkscape 等按正确的印刷尺寸导入。例如 600px 在 300dpi 下为 `width="50.8mm"`。
+    pub units: String,
+    /// 像素到物理单位的换算分辨率（默认 96，即 CSS 像素）
+    pub dpi: f64,
+    /// 路径外扩距离 (像素，默认 0)
+    /// 
+    /// 每条路径独立描出自身边界，渲染器对相邻色块各自做抗锯齿时，交界处会透出 1px 的细缝。
+    /// 设为 0.3~0.5 左右时每个色块略微外扩、与邻居轻微重叠，重叠部分由叠放顺序遮住，细缝随之消失。
+    /// 仅作用于填充模式；过大的值会让细小色块明显变胖。
+    pub overlap: f64,
+    /// 印刷陷印宽度 (像素，默认 0 即不处理)
+    /// 
+    /// 分色印刷时各色版难免有套准误差，相邻色块之间会露出纸色。设置后，除最上层外的每条路径
+    /// 都向外扩张该距离，伸到压在它上面、与它相邻的色块下方；上层色块保持原状，按叠放顺序
+    /// 遮住扩出的部分，画面外观基本不变，只在下层色块相互接壤处多出该宽度的重叠。
+    /// 与 `overlap` 的区别在于用途：`overlap` 只需零点几像素来消除屏幕抗锯齿造成的细缝，
+    /// 对全部路径生效；陷印按印刷机的套准精度设定 (常见为 0.1~0.3mm，按 `dpi` 换算成像素)，
+    /// 最上层路径不扩张以免其轮廓变胖。两者可同时使用，效果叠加。仅作用于填充模式。
+    pub trap_width: f64,
+    /// 聚类时每轮处理的像素/聚类数 (默认 25600)
+    /// 
+    /// 聚类按批次推进，批次大小只影响工作的切分粒度，不影响结果：任意取值的输出完全一致。
+    /// 聚类占用的内存主要由像素数与聚类数决定，调小批次对内存峰值的帮助有限，
+    /// 但能缩短单轮循环的时长；取值过小 (几百以下) 时循环开销会使聚类变慢。0 按 1 处理。
+    pub batch_size: usize,
+    /// 聚类阶段的缩放比例 (默认 1，即按原分辨率聚类)
+    /// 
+    /// 聚类是追踪中最耗时的单线程阶段，耗时大致与像素数成正比。设为 0.5 等小于 1 的值时，
+    /// 先按面积平均把图像缩小到该比例再聚类，得到各颜色层后把色块标签放大回原图：
+    /// 原图的每个像素在缩小图上相邻的几个色块中选颜色最接近的一个，之后照常按原分辨率拟合曲线。
+    /// 0.5 时聚类的像素数约为四分之一，聚类耗时通常可减少一半以上。
+    /// 
+    /// 代价：色块边界按颜色重新定位到原图像素，但逐像素的判定会留下零星的单像素凹凸，
+    /// 曲线因此分段更多，输出体积可能反而略有增加；窄于约 `1 / cluster_scale` 像素的细线、
+    /// 小点在缩小时已与周围混合，可能整体丢失或变为混合色；`filter_speckle` 按缩小后的面积折算
+    /// (乘以比例的平方)。大于等于 1、非正数或非有限值时按原分辨率聚类，小于 0.05 时按 0.05 处理。
+    /// 可平铺模式 (`tileable`) 下不生效。
+    pub cluster_scale: f64,
+    /// 追踪遍数 (默认 1，即单遍聚类)
+    /// 
+    /// 大于 1 时先做一遍粗聚类，取面积最大的 `color_count` 种颜色作为初始中心，
+    /// 再做 `refine_passes - 1` 轮 k-means 迭代修正这些颜色，最后以修正后的调色板重新聚类。
+    /// 单遍聚类的颜色是相邻色块混合后的平均色，修正后的颜色更贴近原图，照片中尤其明显。
+    /// 
+    /// 代价：额外一次完整聚类，外加每轮一次耗时约为 像素数 × 颜色数 的最近色分配；
+    /// 2~3 遍即可收敛，中心不再移动时会提前结束。输出只包含调色板中的颜色。
+    /// 与 `trace_rgba_with_palette` 同时使用时以给定的调色板为准，不做修正。
+    pub refine_passes: u8,
+    /// 线稿模式 (默认关闭)：只提取梯度明显的轮廓线，输出为黑色描边
+    /// 
+    /// 适合把照片转成"线描"风格。流程为 Sobel 梯度幅值 → 按 `edge_threshold` 二值化 →
+    /// Zhang-Suen 细化为单像素骨架 → 沿骨架连成折线并简化。线宽取 `stroke_width`
+    /// (无需开启 `stroke_mode`)，短于 `filter_speckle` 像素的线段被丢弃；
+    /// 颜色数量等色块相关的选项不起作用。
+    /// 
+    /// 仅 `trace_rgba_with_options` 与 `trace_image_data` 支持，其余入口忽略此选项。
+    pub line_extraction: bool,
+    /// 线稿模式的梯度阈值 (默认 48)
+    /// 
+    /// 梯度幅值已归一化，纯黑与纯白之间的锐利边缘约为 255。调低会拾取更多细节与噪点，
+    /// 调高只保留对比最强的轮廓。
+    pub edge_threshold: f64,
+    /// 栅格输出 (RGBA 缓冲区) 是否使用预乘 alpha（默认 `false`，即非预乘）
+    /// 
+    /// `CanvasRenderingContext2D.putImageData` 要求非预乘 RGBA；WebGL 以默认的
+    /// `premultipliedAlpha: true` 上下文合成、且上传纹理时未开启
+    /// `UNPACK_PREMULTIPLY_ALPHA_WEBGL` 时需要预乘数据。开启后由 WASM 直接输出预乘结果，
+    /// 省去 JS 端逐像素换算。作用于 `render_svg_preview_rgba` (需要 `raster` 特性)；PNG 按规范始终为非预乘，不受影响。
+    pub premultiply_output: bool,
+    /// 自动确定斑点过滤阈值（默认关闭），开启后忽略 `filter_speckle`
+    /// 
+    /// 先不过滤斑点聚类一次，在色块面积分布上找噪点与内容之间的拐点作为阈值。
+    /// 额外多一次聚类的耗时；`trace_rgba_to_result` 的 `filter_speckle` 字段返回实际使用的值。
+    pub auto_speckle: bool,
+    /// 斜向连通（默认关闭）
+    /// 
+    /// 默认只有上下左右相邻的同色像素才归入同一色块，1 像素宽的斜线会断成许多小块，
+    /// 进而被斑点过滤吞掉。开启后斜向相邻的像素也视为连通，细斜线保持为一条完整路径；
+    /// 代价是仅以一个角相接的两块同色区域也会被合并。
+    pub diagonal: bool,
+    /// 以顶点内角指定拐角判定（默认不设置），设置后覆盖 `corner_threshold`
+    /// 
+    /// `corner_threshold` 是路径在顶点处的转向角 (度)：转向超过该值的顶点保留为尖角，
+    /// 其余被平滑为曲线。本选项改用更直观的内角表示，二者的换算为
+    /// `corner_threshold = 180 - corner_angle_degrees`：内角小于该值的顶点保留为尖角。
+    /// 例如 90 表示"比直角更尖的角保持锐利" (对应 `corner_threshold = 90`)；
+    /// 120 对应默认的 `corner_threshold = 60`，直角也会保持锐利；150 对应 30，几乎所有拐角都保留。
+    /// 取值截断到 0~180 并四舍五入到整数度。
+    pub corner_angle_degrees: Option<f64>,
+    /// 无障碍标题（默认不设置），输出为根元素的首个子元素 `<title>`
+    /// 
+    /// 设置了 `title` 或 `desc` 时，根元素同时带上 `role="img"` 与指向它们的 `aria-labelledby`，
+    /// 屏幕阅读器会朗读其内容。文本中的 `<`、`&` 等字符会被转义。元素 id 固定为
+    /// `snapsvg-title`/`snapsvg-desc`，同一页面内联多份 SVG 时 id 会重复，需自行改写。
+    pub title: Option<String>,
+    /// 无障碍描述（默认不设置），紧随 `<title>` 输出为 `<desc>`
+    pub desc: Option<String>,
+    /// 附加到根 `<svg>` 元素的自定义属性（默认不设置），为 `{ 属性名: 值 }` 形式的对象
+    /// 
+    /// 例如 `{ class: "logo", role: "presentation", focusable: "false", "data-id": 42 }`。
+    /// 值可以是字符串、数字或布尔值，输出前做 XML 转义，不会破坏文档结构。
+    /// 属性名须以 ASCII 字母或 `_` 开头，只含字母、数字、`-`、`_`、`.`，命名空间前缀只接受
+    /// `xml:`/`xmlns:`；`on` 开头的事件处理属性、以 `javascript:` 开头的值、`xmlns`/`viewBox`
+    /// 一律拒绝。与本库生成的属性 (`role`、`aria-labelledby`、`width`、`height`) 同名时以生成的为准。
+    /// 被跳过的属性通过 `on_warning` 以 `option` 类别报告。
+    pub root_attributes: Option<js_sys::Object>,
+    /// 把输出规范化到固定尺寸的画布 (宽，需与 `normalize_height` 同时设置，默认不设置)
+    /// 
+    /// 取全部路径的包围盒，保持宽高比缩放到恰好放入目标画布，并在较长的方向上居中 (上下或左右留白)，
+    /// 输出的 `viewBox` 为 `0 0 宽 高`。用于图标网格等需要统一尺寸的场景，非正方形输入放入正方形画布时
+    /// 只在一个方向留白。描边模式下线宽同比缩放。
+    /// 
+    /// 只对输出单个 SVG 文档的入口 (`trace_rgba_with_options`、`trace_image_data` 等) 生效；
+    /// 线稿提取、分色图层等其它输出忽略此选项。
+    pub normalize_width: Option<u32>,
+    /// 规范化画布的高，见 `normalize_width`
+    pub normalize_height: Option<u32>,
+    /// 输出归一化坐标 (默认关闭)：x 除以画布宽、y 除以画布高，全部坐标落在 0~1 内
+    /// 
+    /// 根元素为 `viewBox="0 0 1 1" preserveAspectRatio="none"`，在 JS 中只需一次
+    /// `scale(宽, 高)` 即可绘制到任意尺寸的画布，也便于直接作为着色器的输入。作为图片显示时
+    /// 内容会拉伸填满元素的框，需要按原图宽高比设置元素尺寸 (或配合 `units` 输出 `width`/`height`，
+    /// 其值仍为原图的物理尺寸)。坐标的小数位数在 `coordinate_precision` 的基础上按画布的长边
+    /// 自动增加 (例如长边 1000 像素时多 3 位)，保持与像素坐标相同的绝对精度。
+    /// 
+    /// 横纵按不同比例缩放，描边模式下的线宽按宽、高的几何平均折算，非正方形画布中横竖线条的
+    /// 粗细会略有差别。`crop_to_content`、`center_origin` 不生效；设置了 `normalize_width`/
+    /// `normalize_height` 时按规范化后的画布归一化。纹理图案 (`detect_texture`) 与描边揭示动画
+    /// (`animate_draw`) 以像素为单位，在此模式下不输出，纹理色块改用纯色填充。
+    pub normalize_coords: bool,
+    /// 在 `trace_rgba_to_result` 的结果中附带输出内容的哈希 (默认关闭)，可直接作为缓存键
+    pub content_hash: bool,
+    /// 没有生成任何路径时返回错误 (默认关闭)
+    /// 
+    /// 全透明或被 `filter_speckle` 全部过滤的图像不会产生路径。默认仍返回只有根元素的空 SVG
+    /// (合法文档)，并通过 `on_warning` 以 `empty` 类别报告；开启后改为返回 "没有生成任何路径" 错误，
+    /// 便于批处理尽早发现空白输出。适用于输出单个文档的选项入口 (`trace_rgba_with_options`、
+    /// `trace_image_data`、`trace_rgba_masked`、`trace_rgba_to_binary` 等)，线稿提取不检查。
+    pub fail_on_empty: bool,
+    /// 拟合后合并颜色相近的路径 (默认 0，不合并)
+    /// 
+    /// `color_precision` 的量化边界可能把肉眼相同的颜色 (如 `#ff0000` 与 `#fe0000`) 分到不同图层。
+    /// 颜色距离不超过该值的路径合并为一条，颜色取按面积加权的平均色。距离在 `color_space`
+    /// 指定的空间中计算：`rgb` 为 0~255 通道上的欧氏距离，`lab` 为 CIE76 色差 (约 2.3 为刚可察觉)。
+    /// 合并后的路径位于其中最早绘制的那一层，中间绘制的路径可能遮挡并入的部分，容差宜小。
+    pub post_merge_tolerance: f64,
+    /// 把输出裁剪到内容的包围盒 (默认关闭)
+    /// 
+    /// 取全部路径的包围盒，路径平移到以包围盒左上角为原点，`viewBox` 为 `0 0 宽 高`，
+    /// 去掉四周没有路径的空白 (如透明背景或掩码之外的区域)。包围盒按曲线采样点计算，
+    /// 宽高通常带小数；需要整数时同时开启 `snap_viewbox_to_int`。
+    /// 与 `normalize_width`/`normalize_height` 同时设置时以规范化为准。
+    pub crop_to_content: bool,
+    /// 裁剪时把包围盒向外取整 (最小值向下、最大值向上)，`viewBox` 为整数，路径整体只平移整数像素 (默认关闭)
+    /// 
+    /// 部分严格的解析器不接受带小数的 `viewBox`；向外取整保证内容不会被裁掉。
+    pub snap_viewbox_to_int: bool,
+    /// 改用平铺聚类，并以该面积 (像素) 作为合并上限 (默认不设置，即叠放分层)
+    /// 
+    /// visioncortex 的层次参数并不是嵌套深度，而是面积阈值：面积不超过该值的色块继续并入颜色最接近的邻居，
+    /// 超过后停止主动合并。设置后同时关闭叠放分层 (`layer_difference` 决定的 "加深" 输出只在不设置时生效)，
+    /// 每个像素只属于一条路径，输出为互不重叠的平铺色块，没有大色块垫在小色块之下的嵌套结构。
+    /// 
+    /// 取值越大，合并越彻底：路径越少、越平，颜色细节丢失越多 (接近图像面积时整幅图只剩一两块)；
+    /// 取值越小，保留的色块越多。繁杂的照片上适中的取值 (如图像面积的 1%) 通常能把路径数降到叠放模式的
+    /// 几分之一。此时 `color_count` 不再决定输出层数，`layer_difference` 也不再起作用。
+    /// 平铺色块相邻处没有重叠，部分渲染器中可能看到细缝，可配合 `overlap` 使用。
+    pub hierarchy_max_area: Option<u32>,
+    /// 丢弃点数少于该值的路径（默认 0，不过滤）
+    /// 
+    /// 点数按各子路径存储的点累加：折线为顶点数，样条为 `1 + 3n` (含控制点)。
+    /// 只有两三个点的路径多半是渲染不可见的细条，却同样占用输出体积；
+    /// 按点数过滤比按面积的 `filter_speckle` 更直接地针对这类退化几何，且不需要计算面积。
+    /// 丢弃的数量通过 `on_warning` 以 `dropped` 类别报告。
+    pub min_points_per_path: usize,
+    /// 后处理链 (默认全部关闭)：同色合并 → 简化 → 相对命令/紧凑输出，详见 `PostprocessOptions`
+    /// 
+    /// 各步骤在内存中的路径数据上完成，与先输出 SVG 再交给其它工具逐步处理相比，
+    /// 省去了每一步的解析与序列化。
+    pub postprocess: PostprocessOptions,
+    /// 分项输出精度 (默认全部沿用 `coordinate_precision`)，详见 `PrecisionOptions`
+    pub precision: PrecisionOptions,
+    /// 重复形状去重（默认关闭）：平移后相同的路径在 `<defs>` 中只输出一次，各处以 `<use>` 引用
+    /// 
+    /// 匹配容差即输出精度：各路径的数据以自身首个点为原点，按 `coordinate_precision` 取整后逐字相同
+    /// 即视为同一形状 (默认 2 位小数，即坐标相差不超过 0.005 像素)；只支持平移，旋转、缩放或镜像后
+    /// 相同的形状不会匹配。颜色不参与匹配，`fill`/`stroke` 写在各个 `<use>` 上。
+    /// 图案重复的输入 (图标阵列、抖动纹理等) 体积可大幅减小；路径数据很短的形状以及识别为图元的
+    /// 路径不共享。引用使用 SVG 2 的 `href` 属性，只认 `xlink:href` 的旧渲染器无法显示这些形状。
+    pub dedupe_shapes: bool,
+    /// 坐标原点：`"top-left"`（默认，画布左上角为 (0, 0)）或 `"center"`
+    /// 
+    /// `"center"` 把全部坐标平移到内容包围盒的中心位于 (0, 0)，`viewBox` 的最小值随之变为负数，
+    /// 例如物理引擎的刚体通常要求以质心附近为原点。`viewBox` 仍覆盖整张画布；
+    /// 同时开启 `crop_to_content` 时为居中后的内容包围盒 `-w/2 -h/2 w h`。
+    /// 开启 `snap_viewbox_to_int` 时中心取整到最近的整数像素，`viewBox` 保持整数，
+    /// 内容中心与原点可能相差半个像素。
+    pub origin: String,
+    /// 无缝平铺：把图像视为左右、上下边缘相接的环面，跨越边缘的色块按一个整体追踪 (默认关闭)
+    /// 
+    /// 追踪前把画布四周按对边的像素环绕扩展一圈，跨越边缘的色块在扩展后的画布上是连通的，
+    /// 因而拟合为一条完整的路径，超出画布的部分由 `viewBox` 裁掉；画布另一侧同时输出它环绕过来
+    /// 的那一份，两份由相同的像素拟合，拼接处轮廓一致，平铺时看不到接缝。
+    /// 完全落在扩展区内的路径不输出。扩展宽度为 16 像素，
+    /// 比扩展宽度更大的色块在扩展区外缘被截断，截断处不在画布内，不影响显示。
+    /// 适用于 `trace_rgba_with_options` 等经过完整聚类流程的函数；`line_extraction` 不支持环绕。
+    pub tileable: bool,
+    /// 色相窗口的起点 (度)，需与 `hue_range_end` 同时设置 (默认不设置)
+    /// 
+    /// 设置后只追踪色相落在 `hue_range_start` ~ `hue_range_end` 之间的像素，其余像素视为透明，
+    /// 例如从标志中单独提取红色元素。窗口沿色相增大的方向计算：终点小于起点时跨过 360°/0°，
+    /// `(330, 30)` 覆盖红色两侧，`(30, 330)` 则是除红色以外的部分；两者相差 360° 或以上时覆盖全部色相。
+    /// 色相按 HSV 计算，在 `alpha_mode`、`mask` 等预处理之后、调色板映射之前应用。
+    pub hue_range_start: Option<f64>,
+    /// 色相窗口的终点 (度)，见 `hue_range_start`
+    pub hue_range_end: Option<f64>,
+    /// 色相窗口的 HSV 饱和度下限 (0~1，默认 0.15)
+    /// 
+    /// 灰色、白色与接近灰色的像素色相不稳定，低于下限的像素不论色相都视为窗口之外。
+    pub hue_min_saturation: f64,
+    /// 色相窗口的 HSV 明度下限 (0~1，默认 0.15)，过暗的像素色相同样不稳定
+    pub hue_min_value: f64,
+    /// 统计曲线拟合前的平滑是否在 `max_iterations` 次内收敛，结果见 `TraceResult` (默认关闭)
+    /// 
+    /// 只在 `trace_rgba_to_result` 中生效。统计需要对每条轮廓多平滑两遍，拟合阶段耗时明显增加，
+    /// 只建议在调参时开启。
+    pub collect_convergence: bool,
+    /// 文字感知：疑似文字的深色小色块按精细参数单独追踪，避免小字糊成色块 (默认关闭)
+    /// 
+    /// 先用较小的斑点阈值 (不超过 2) 与更细的拐角阈值 (不超过 30°) 对原图聚类一次，按单个色块的
+    /// 简单规则挑出字形：颜色足够暗 (与 `protect_dark` 相同的亮度门限)、包围盒高 4~64 像素、
+    /// 宽度不超过高度的 12 倍 (允许粘连成行)、色块面积占包围盒的 8%~70% (笔画而非实心块)。
+    /// 字形按精细参数拟合后叠加在最上层，原位置的像素先抹成周围的背景色，其余部分仍按常规参数追踪。
+    /// 
+    /// 局限：只识别深色文字，浅色或彩色文字、反白文字 (深底浅字) 不会被识别；规则不看上下文，
+    /// 尺寸相近的深色线条图标、细框线等也会被当作文字，字号超过 64 像素的标题不会；
+    /// 多出的一次聚类使耗时大约增加一倍。它不是 OCR，只保证文字轮廓更清晰。
+    pub text_aware: bool,
+    /// 细线的最小宽度 (像素，默认 0 即不处理)
+    /// 
+    /// 1 像素宽的细线拟合后常常只剩接近零面积的细条，不同渲染器的抗锯齿下时隐时现。
+    /// 设置后，平均宽度 (`2 * 面积 / 周长`，对长条形状约等于条带宽度) 低于该值的路径向外扩张，
+    /// 使平均宽度大致达到该值 (斜线等锯齿轮廓外扩后周长变长，结果略低于该值)，与 `overlap` 相同地外扩轮廓、收缩孔洞。判定针对整条路径，
+    /// 粗大色块上的细长突出部分不受影响；很小的点状色块的平均宽度同样很小，也会被扩大。
+    /// 只能保住拟合阶段还存在的细线，聚类阶段已经并入邻居的线条 (见 `filter_speckle`) 无法找回。
+    /// 仅作用于填充模式，在 `overlap` 之前应用。
+    pub min_stroke_width: f64,
+    /// 自动选择颜色数 (默认关闭)，开启后忽略 `color_count`
+    /// 
+    /// 依次以 2、3、4、5、6、8、12、16、24 色聚类，把每个色块填回其输出颜色，计算与原图的均方误差。
+    /// 停止条件：某一档的误差已足够小 (每通道均方误差低于 4) 时直接取该档；否则在全部候选中取
+    /// 误差降幅已达到总降幅 90% 的最小颜色数，即再增加颜色收益递减的拐点。搜索上限为 24 色，
+    /// 不受 `allow_many_colors` 影响。评估在长边缩小到 256 像素以内的图像上进行，只聚类不拟合，
+    /// 但最多要聚类 9 次，耗时与图像内容有关，通常相当于一到两次完整追踪；`trace_rgba_to_result` 的 `color_count` 字段返回实际选择的值。
+    pub auto_color_count: bool,
+    /// 完全透明 (alpha 为 0) 的像素是否作为一种颜色参与聚类 (默认 `false`)
+    /// 
+    /// 透明像素的 RGB 没有意义，常见的导出工具会留下黑色或杂乱的残值，按原样聚类会多出
+    /// 莫名的深色图层。默认 (`false`) 在聚类时丢弃这些像素，输出中对应区域保持透明；
+    /// 设为 `true` 时先把它们统一为透明黑，合为一个黑色色块参与追踪 (会与相邻的黑色像素连成一片)。
+    /// `mask`、色相窗口等把像素置为透明的功能总是丢弃透明像素，不受此选项影响。
+    /// `trace_rgba_parallel`、`ClusterHandle`、`trace_tiled` 等不接受选项的入口同样丢弃透明像素。
+    pub transparent_as_color: bool,
+    /// 纹理检测 (实验性，默认关闭)：颜色起伏明显的色块改用 `<pattern>` 图案填充
+    /// 
+    /// 噪点、排线等纹理区域按纯色填充会丢掉质感。开启后统计每个色块内相邻像素的平均色差，
+    /// 超过阈值的色块 (至少 64 像素) 按亮度分成两组，在多数一组的平均色上铺一层 4 像素为单元的
+    /// 圆点 (无方向性) 或排线 (一个方向的起伏明显更大)，颜色为少数一组的平均色，点或线的面积
+    /// 与少数一组所占的比例相当。图案只是粗略的近似，不还原原图的纹理细节；判定是启发式的，
+    /// 色块合并较多的区域 (`filter_speckle` 较大时) 也可能被当作纹理。
+    /// 只在 `trace_rgba_with_options`、`trace_rgba_to_result` 中生效，描边模式下不使用图案。
+    pub detect_texture: bool,
+    /// 描边揭示动画 (默认关闭)：加载时各路径按绘制顺序逐条画出轮廓，再淡入填充
+    /// 
+    /// 每条路径计算自身长度，设置等长的 `stroke-dasharray`/`stroke-dashoffset`，
+    /// 由根元素内的 `<style>` 中的 CSS 动画把偏移过渡到 0。填充模式下临时加上与图层同色的
+    /// 1 像素描边，动画结束后保留，轮廓会略微变粗；描边模式直接使用原有描边。
+    /// 时间安排见 `animate_duration`。
+    /// 
+    /// 浏览器支持：Chrome/Edge、Firefox、Safari 均支持 SVG 中的 CSS 动画，直接内联或通过
+    /// `<img>`、CSS 背景引用都会播放；系统开启 "减少动态效果" 时不播放。会剥离 `<style>` 的
+    /// SVG 净化工具、以及 Inkscape、Illustrator、resvg 等静态渲染器不执行动画，显示的是
+    /// 起始属性下的画面：填充完整可见而描边尚未画出，即与关闭此选项时相同的外观。
+    pub animate_draw: bool,
+    /// 描边揭示动画的时长 (毫秒，默认 2000)，非正数或非有限值时按默认值
+    /// 
+    /// 每条路径的描边用一半时长画完，各路径的起点在前一半时长内按绘制顺序均匀错开，
+    /// 最后一条恰好在该时长结束时画完；每条路径画完后再用四分之一时长淡入填充，
+    /// 因此整个动画持续约 1.25 倍的时长。
+    pub animate_duration: f64,
+    /// 输出前清理路径命令 (默认开启)：合并连续重复的坐标，删去零长度的线段与曲线段
+    /// 
+    /// 坐标按输出精度取整后比较，因此 `coordinate_precision` 较低时取整后重合的点也会被合并，
+    /// 缩小输出 (如 `normalize_width`) 时效果明显。
+    /// 只删去不改变形状的命令，关闭后输出与旧版本逐字节一致。`trace_rgba_parallel` 等不接受
+    /// 选项的入口不做清理，输出保持不变。
+    pub clean_commands: bool,
+}
+
+#[wasm_bindgen]
+impl TraceOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TraceOptions {
+        TraceOptions::default()
+    }
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        TraceOptions {
+            color_count: 16,
+            allow_many_colors: false,
+            path_precision: 50,
+            corner_threshold: 60,
+            filter_speckle: 4,
+            detect_primitives: false,
+            max_threads: None,
+            stroke_mode: false,
+            stroke_width: 1.0,
+            non_scaling_stroke: false,
+            stroke_linejoin: String::new(),
+            stroke_linecap: String::new(),
+            color_space: "rgb".to_owned(),
+            space_separated_translate: false,
+            alpha_mode: "straight".to_owned(),
+            coordinate_precision: 2,
+            inline: false,
+            protect_dark: false,
+            on_warning: None,
+            max_paths: None,
+            unsharp_amount: 0.0,
+            auto_background: false,
+            edge_layers: false,
+            units: String::new(),
+            dpi: 96.0,
+            overlap: 0.0,
+            trap_width: 0.0,
+            batch_size: 25600,
+            cluster_scale: 1.0,
+            refine_passes: 1,
+            line_extraction: false,
+            edge_threshold: 48.0,
+            premultiply_output: false,
+            auto_speckle: false,
+            diagonal: false,
+            corner_angle_degrees: None,
+            title: None,
+            desc: None,
+            root_attributes: None,
+            normalize_width: None,
+            normalize_height: None,
+            normalize_coords: false,
+            content_hash: false,
+            fail_on_empty: false,
+            post_merge_tolerance: 0.0,
+            crop_to_content: false,
+            snap_viewbox_to_int: false,
+            hierarchy_max_area: None,
+            min_points_per_path: 0,
+            postprocess: PostprocessOptions::default(),
+            precision: PrecisionOptions::default(),
+            dedupe_shapes: false,
+            origin: "top-left".to_owned(),
+            tileable: false,
+            hue_range_start: None,
+            hue_range_end: None,
+            hue_min_saturation: 0.15,
+            hue_min_value: 0.15,
+            collect_convergence: false,
+            text_aware: false,
+            min_stroke_width: 0.0,
+            auto_color_count: false,
+            transparent_as_color: false,
+            detect_texture: false,
+            animate_draw: false,
+            animate_duration: 2000.0,
+            clean_commands: true,
+        }
+    }
+}
+
+impl TraceOptions {
+    fn to_config(&self) -> TracerConfig {
+        self.warn_clamped();
+        
+        let mut cfg = build_config(
+            self.color_count,
+            self.path_precision,
+            self.corner_threshold,
+            self.filter_speckle,
+        );
+        if self.allow_many_colors && self.color_count > 64 {
+            cfg.color_count = self.color_count;
+            cfg.layer_difference = many_colors_layer_difference(self.color_count);
+            // 默认精度忽略低 2 位，相差几个色阶的颜色会先被量化到一起，分层阈值再低也分不开
+            cfg.color_precision = if self.color_count > 128 { 8 } else { 7 };
+        }
+        cfg.detect_primitives = self.detect_primitives;
+        cfg.max_threads = self.max_threads;
+        cfg.stroke_width = self.stroke_mode.then_some(self.stroke_width);
+        cfg.non_scaling_stroke = self.non_scaling_stroke;
+        cfg.stroke_linejoin = match self.stroke_linejoin.as_str() {
+            "miter" => Some("miter"),
+            "round" => Some("round"),
+            "bevel" => Some("bevel"),
+            _ => None,
+        };
+        cfg.stroke_linecap = match self.stroke_linecap.as_str() {
+            "butt" => Some("butt"),
+            "round" => Some("round"),
+            "square" => Some("square"),
+            _ => None,
+        };
+        cfg.color_space = ColorSpace::parse(&self.color_space);
+        if self.space_separated_translate {
+            cfg.translate_separator = ' ';
+        }
+        cfg.alpha_mode = AlphaMode::parse(&self.alpha_mode);
+        cfg.path_precision = Some(self.precision.coord_precision.unwrap_or(self.coordinate_precision) as u32);
+        cfg.transform_precision = self.precision.transform_precision.map(u32::from);
+        cfg.inline = self.inline;
+        cfg.protect_dark = self.protect_dark;
+        cfg.max_paths = self.max_paths;
+        cfg.unsharp_amount = self.unsharp_amount.max(0.0);
+        cfg.auto_background = self.auto_background;
+        cfg.edge_layers = self.edge_layers;
+        cfg.refine_passes = self.refine_passes.max(1);
+        cfg.auto_speckle = self.auto_speckle;
+        cfg.diagonal = self.diagonal;
+        cfg.title = self.title.clone().filter(|t| !t.is_empty());
+        cfg.root_attributes = self.root_attributes.as_ref().map(root_attrs::parse).unwrap_or_default();
+        cfg.desc = self.desc.clone().filter(|d| !d.is_empty());
+        cfg.fail_on_empty = self.fail_on_empty;
+        cfg.crop_to_content = self.crop_to_content;
+        cfg.hierarchical = self.hierarchy_max_area.map_or(HIERARCHICAL_MAX, |area| area.min(HIERARCHICAL_MAX - 1));
+        cfg.snap_viewbox_to_int = self.snap_viewbox_to_int;
+        cfg.min_points_per_path = self.min_points_per_path;
+        cfg.postprocess = self.postprocess;
+        cfg.dedupe_shapes = self.dedupe_shapes;
+        cfg.center_origin = self.origin == "center";
+        cfg.tileable = self.tileable;
+        cfg.text_aware = self.text_aware;
+        cfg.auto_color_count = self.auto_color_count;
+        cfg.min_stroke_width = if self.min_stroke_width.is_finite() { self.min_stroke_width.max(0.0) } else { 0.0 };
+        let unit = |v: f64| if v.is_finite() { v.clamp(0.0, 1.0) } else { 0.0 };
+        cfg.hue_window = match (self.hue_range_start, self.hue_range_end) {
+            (Some(start), Some(end)) if start.is_finite() && end.is_finite() => {
+                Some(HueWindow::new(start, end, unit(self.hue_min_saturation), unit(self.hue_min_value)))
+            }
+            _ => None,
+        };
+        // 窗口外的像素已置为透明，需要在聚类时丢弃
+        cfg.key_transparent = cfg.hue_window.is_some() || !self.transparent_as_color;
+        cfg.transparent_as_color = self.transparent_as_color;
+        cfg.detect_texture = self.detect_texture;
+        cfg.clean_commands = self.clean_commands;
+        cfg.animate_draw = self.animate_draw.then_some(
+            if self.animate_duration.is_finite() && self.animate_duration > 0.0 { self.animate_duration } else { 2000.0 },
+        );
+        let tolerance = cfg.postprocess.simplify_tolerance;
+        cfg.postprocess.simplify_tolerance = if tolerance.is_finite() { tolerance.max(0.0) } else { 0.0 };
+        cfg.post_merge_tolerance = if self.post_merge_tolerance.is_finite() { self.post_merge_tolerance.max(0.0) } else { 0.0 };
+        cfg.normalize_to = match (self.normalize_width, self.normalize_height) {
+            (Some(w), Some(h)) if w > 0 && h > 0 => Some((w, h)),
+            _ => None,
+        };
+        cfg.normalize_coords = self.normalize_coords;
+        if let Some(angle) = self.corner_angle_degrees.filter(|a| a.is_finite()) {
+            cfg.corner_threshold = corner_threshold_from_angle(angle);
+        }
+        if self.line_extraction {
+            cfg.line_extraction = Some(if self.edge_threshold.is_finite() { self.edge_threshold } else { 48.0 });
+            cfg.stroke_width = Some(self.stroke_width);
+            cfg.stroke_linejoin.get_or_insert("round");
+            cfg.stroke_linecap.get_or_insert("round");
+        }
+        cfg.batch_size = self.batch_size.clamp(1, i32::MAX as usize);
+        cfg.cluster_scale = (self.cluster_scale > 0.0 && self.cluster_scale < 1.0).then(|| self.cluster_scale.max(0.05));
+        cfg.overlap = if self.overlap.is_finite() { self.overlap.max(0.0) } else { 0.0 };
+        cfg.trap_width = if self.trap_width.is_finite() { self.trap_width.max(0.0) } else { 0.0 };
+        let dpi = if self.dpi > 0.0 { self.dpi } else { 96.0 };
+        cfg.physical_unit = match self.units.as_str() {
+            "px" => Some(("px", 1.0)),
+            "pt" => Some(("pt", 72.0 / dpi)),
+            "mm" => Some(("mm", 25.4 / dpi)),
+            _ => None,
+        };
+        cfg
+    }
+    
+    /// 对会被 `build_config` 截断或回退的取值发出警告
+    fn warn_clamped(&self) {
+        if self.color_count < 2 || (self.color_count > 64 && !self.allow_many_colors) {
+            warnings::emit("clamp", &format!(
+                "color_count={} 超出 2~64，已截断为 {}",
+                self.color_count, self.color_count.clamp(2, 64)
+            ));
+        }
+        let splice = 125 - (self.path_precision as i32 * 110 / 100);
+        if !(10..=135).contains(&splice) {
+            warnings::emit("clamp", &format!(
+                "path_precision={} 过大，拼接阈值已截断为 10",
+                self.path_precision
+            ));
+        }
+        if !matches!(self.color_space.as_str(), "rgb" | "lab") {
+            warnings::emit("option", &format!("无法识别的 color_space \"{}\"，已使用 \"rgb\"", self.color_space));
+        }
+        if !matches!(self.units.as_str(), "" | "px" | "pt" | "mm") {
+            warnings::emit("option", &format!("无法识别的 units \"{}\"，不输出 width/height", self.units));
+        }
+        if !matches!(self.alpha_mode.as_str(), "straight" | "premultiplied") {
+            warnings::emit("option", &format!("无法识别的 alpha_mode \"{}\"，已使用 \"straight\"", self.alpha_mode));
+        }
+        if !matches!(self.origin.as_str(), "top-left" | "center") {
+            warnings::emit("option", &format!("无法识别的 origin \"{}\"，已使用 \"top-left\"", self.origin));
+        }
+        if self.hue_range_start.is_some() != self.hue_range_end.is_some() {
+            warnings::emit("option", "hue_range_start 与 hue_range_end 需要同时设置，已忽略色相窗口");
+        }
+        if !matches!(self.stroke_linejoin.as_str(), "" | "miter" | "round" | "bevel") {
+            warnings::emit("option", &format!("无法识别的 stroke_linejoin \"{}\"，不输出该属性", self.stroke_linejoin));
+        }
+        if !matches!(self.stroke_linecap.as_str(), "" | "butt" | "round" | "square") {
+            warnings::emit("option", &format!("无法识别的 stroke_linecap \"{}\"，不输出该属性", self.stroke_linecap));
+        }
     }
 }
 
@@ -91,6 +1062,59 @@ fn build_config(
 // 原始版本：使用 vtracer::convert (单线程)
 // ============================================================================
 
+/// 追踪结果：SVG 字符串及其对应的图像尺寸
+#[wasm_bindgen(getter_with_clone)]
+pub struct TraceResult {
+    #[wasm_bindgen(readonly)]
+    pub svg: String,
+    /// 解码后图像的宽度 (像素)，与 `viewBox` 宽度一致
+    #[wasm_bindgen(readonly)]
+    pub width: u32,
+    /// 解码后图像的高度 (像素)，与 `viewBox` 高度一致
+    #[wasm_bindgen(readonly)]
+    pub height: u32,
+    /// `auto_background` 检测到并移除的背景色 (`#rrggbb`)，未检测到或未开启时为 `undefined`
+    #[wasm_bindgen(readonly)]
+    pub background: Option<String>,
+    /// 实际使用的斑点过滤阈值：开启 `auto_speckle` 时为自动估计的值，否则等于 `filter_speckle`
+    #[wasm_bindgen(readonly)]
+    pub filter_speckle: u32,
+    /// 实际使用的颜色数：开启 `auto_color_count` 时为自动选择的值，否则等于 `color_count`
+    #[wasm_bindgen(readonly)]
+    pub color_count: u32,
+    /// 输出 SVG 的 64 位 FNV-1a 哈希 (16 位小写十六进制)，仅在开启 `content_hash` 时提供
+    /// 
+    /// 哈希直接对 `svg` 的 UTF-8 字节计算。追踪是确定性的，相同的输入与选项总是得到相同的输出，
+    /// 因此哈希在多次运行之间稳定；升级版本后输出可能变化，缓存键应同时包含版本号。
+    #[wasm_bindgen(readonly)]
+    pub hash: Option<String>,
+    /// 输出是否可能有未被路径覆盖的透明区域，用于决定合成时是否需要垫背景色
+    /// 
+    /// 精确计算覆盖范围代价较高，这里是保守的估计：`true` 表示可能透明，`false` 则可以确定完全覆盖。
+    /// 以下任一情况为 `true`：移除了背景 (`auto_background`)；描边/线稿模式；规范化尺寸 (可能留边)；没有任何路径；全部路径的面积之和小于 `viewBox` 的面积。
+    /// 叠放模式下底层色块铺满整张画布，不透明的输入通常为 `false`；平铺模式 (`hierarchy_max_area`)
+    /// 的色块边缘经曲线拟合后面积之和略小于画布，通常为 `true`。
+    #[wasm_bindgen(readonly)]
+    pub has_transparency: bool,
+    /// 是否有轮廓的平滑在达到 `max_iterations` 时仍未收敛，仅在开启 `collect_convergence` 时提供
+    /// 
+    /// 配置中的 `max_iterations` (默认 10) 限制的是曲线拟合前细分平滑的轮数，而不是聚类：
+    /// visioncortex 的聚类是单遍的层次合并，没有迭代，也就不存在收敛问题。平滑在某一轮不再细分
+    /// 任何线段时提前结束；达到上限时被截断，曲线会比收敛时略粗糙。统计方法是对每条轮廓多平滑
+    /// 一轮，点数仍增加即为未收敛。多边形 (`"polygon"`、`"none"`) 模式不做平滑，恒为 `false`；
+    /// `line_extraction` 不经过这一步，为 `undefined`。
+    #[wasm_bindgen(readonly)]
+    pub iteration_cap_reached: Option<bool>,
+    /// 未收敛的轮廓数 (含孔洞)，与 `iteration_cap_reached` 同时提供
+    #[wasm_bindgen(readonly)]
+    pub capped_paths: Option<u32>,
+    /// 末轮变化量：多平滑一轮时点数相对增加的比例，取全部轮廓中的最大值，已收敛时为 0
+    /// 
+    /// 数值越大，提高 `max_iterations` 的收益越明显；每多一轮平滑，长线段大约被再细分一次。
+    #[wasm_bindgen(readonly)]
+    pub final_change: Option<f64>,
+}
+
 /// 将图片字节数组转换为 SVG 字符串（单线程版本）
 #[wasm_bindgen]
 pub fn trace_image_to_svg(
@@ -101,15 +1125,36 @@ pub fn trace_image_to_svg(
     filter_speckle: u32,
     color_mode: &str,
 ) -> Result<String, JsValue> {
-    fastrand::seed(1);
+    trace_image_to_svg_with_size(
+        image_bytes,
+        color_count,
+        path_precision,
+        corner_threshold,
+        filter_speckle,
+        color_mode,
+    ).map(|result| result.svg)
+}
+
+/// 与 `trace_image_to_svg` 相同，但同时返回解码得到的图像宽高，
+/// 调用方无需再解码或从 SVG 中解析尺寸即可布局
+#[wasm_bindgen]
+pub fn trace_image_to_svg_with_size(
+    image_bytes: &[u8],
+    color_count: u8,
+    path_precision: u8,
+    corner_threshold: u8,
+    filter_speckle: u32,
+    color_mode: &str,
+) -> Result<TraceResult, JsValue> {
+    reset_rng();
     
-    let img = image::load_from_memory(image_bytes)
-        .map_err(|e| JsValue::from_str(&format!("图片解析失败: {}", e)))?;
+    let rgba = decode::load_rgba(image_bytes)?;
     
-    let width = img.width() as usize;
-    let height = img.height() as usize;
-    let rgba = img.to_rgba8();
+    let width = rgba.width() as usize;
+    let height = rgba.height() as usize;
     let pixels: Vec<u8> = rgba.into_raw();
+    // vtracer 会抠除透明像素，不透明的输入由叠放的底层色块铺满
+    let has_transparency = pixels.chunks_exact(4).any(|p| p[3] < 255);
     
     let color_image = ColorImage { pixels, width, height };
     
@@ -149,10 +1194,25 @@ pub fn trace_image_to_svg(
         width, height, paths_str
     );
     
-    Ok(svg_string)
+    Ok(TraceResult {
+        svg: svg_string,
+        width: width as u32,
+        height: height as u32,
+        background: None,
+        filter_speckle: cfg.filter_speckle as u32,
+        color_count: cfg.color_count as u32,
+        hash: None,
+        has_transparency,
+        iteration_cap_reached: None,
+        capped_paths: None,
+        final_change: None,
+    })
 }
 
 /// 高性能版本：直接接收 RGBA 像素数据（单线程）
+/// 
+/// 全透明或被斑点过滤清空的图像返回只有根元素的空 SVG (合法文档)，不报错；
+/// 需要检测空输出时改用 `trace_rgba_with_options` 并开启 `fail_on_empty`。
 #[wasm_bindgen]
 pub fn trace_rgba_to_svg(
     rgba_data: &[u8],
@@ -164,7 +1224,7 @@ pub fn trace_rgba_to_svg(
     filter_speckle: u32,
     color_mode: &str,
 ) -> Result<String, JsValue> {
-    fastrand::seed(1);
+    reset_rng();
     let w = width as usize;
     let h = height as usize;
     
@@ -225,107 +1285,1869 @@ pub fn trace_rgba_to_svg(
 // 并行版本：使用 visioncortex 底层 API + Rayon
 // ============================================================================
 
-/// 并行矢量化：使用 Rayon 在曲线拟合阶段并行处理
-/// 
-/// 注意：此函数需要线程池已初始化 (initThreadPool)
-#[wasm_bindgen]
-pub fn trace_rgba_parallel(
-    rgba_data: &[u8],
-    width: u32,
-    height: u32,
-    color_count: u8,
-    path_precision: u8,
-    corner_threshold: u8,
-    filter_speckle: u32,
-) -> Result<String, JsValue> {
-    fastrand::seed(1);
-    let w = width as usize;
-    let h = height as usize;
-    
+/// 校验 RGBA 数据长度是否与宽高一致
+fn check_rgba_len(rgba_data: &[u8], w: usize, h: usize) -> Result<(), JsValue> {
+    validate_rgba_len(rgba_data, w, h).map_err(|e| JsValue::from_str(&e))
+}
+
+/// `check_rgba_len` 的字符串错误版本，供不能构造 `JsValue` 的原生入口共用
+fn validate_rgba_len(rgba_data: &[u8], w: usize, h: usize) -> Result<(), String> {
     let expected_len = w * h * 4;
     if rgba_data.len() != expected_len {
-        return Err(JsValue::from_str(&format!(
-            "RGBA 数据长度不匹配: 期望 {} 字节, 实际 {} 字节",
-            expected_len, rgba_data.len()
-        )));
+        return Err(format!("RGBA 数据长度不匹配: 期望 {} 字节, 实际 {} 字节", expected_len, rgba_data.len()));
     }
+    Ok(())
+}
+
+/// 没有生成任何路径时按 `fail_on_empty` 返回错误，或发出 `empty` 警告后继续输出空的 SVG
+fn check_empty(path_results: &[(CompoundPath, Color)], cfg: &TracerConfig) -> Result<(), JsValue> {
+    validate_nonempty(path_results, cfg).map_err(|e| JsValue::from_str(&e))
+}
+
+/// `check_empty` 的字符串错误版本
+fn validate_nonempty(path_results: &[(CompoundPath, Color)], cfg: &TracerConfig) -> Result<(), String> {
+    if !path_results.is_empty() {
+        return Ok(());
+    }
+    if cfg.fail_on_empty {
+        return Err("没有生成任何路径 (图像全透明或全部被斑点过滤)".to_owned());
+    }
+    warnings::emit("empty", "没有生成任何路径，输出为空的 SVG");
+    Ok(())
+}
+
+/// 第一阶段：层次聚类 (单线程，这部分难以并行化)
+/// 
+/// LAB 模式下像素会先编码为 LAB 字节，聚类颜色需经 `cluster_color` 解码
+fn cluster_image(
+    mut color_image: visioncortex::ColorImage,
+    cfg: &TracerConfig,
+) -> Clusters {
+    prepare_pixels(&mut color_image.pixels, color_image.width, cfg);
+    run_clusters(color_image, cfg)
+}
+
+/// 聚类前的像素处理：预处理与颜色空间编码
+fn prepare_pixels(pixels: &mut [u8], width: usize, cfg: &TracerConfig) {
+    preprocess::apply(pixels, width, cfg);
+    if cfg.color_space == ColorSpace::Lab {
+        color_space::encode_lab_pixels(pixels);
+    }
+}
+
+/// 对已经过 `prepare_pixels` 处理的图像做键控与聚类
+fn run_clusters(mut color_image: visioncortex::ColorImage, cfg: &TracerConfig) -> Clusters {
+    let w = color_image.width;
+    let h = color_image.height;
     
-    let cfg = build_config(color_count, path_precision, corner_threshold, filter_speckle);
-    
-    // 创建 ColorImage (visioncortex 版本)
-    let color_image = visioncortex::ColorImage {
-        pixels: rgba_data.to_vec(),
-        width: w,
-        height: h,
+    let key_color = if cfg.key_transparent || cfg.auto_background {
+        preprocess::key_transparent(&mut color_image.pixels)
+    } else {
+        None
     };
-    
-    // 第一阶段：层次聚类 (单线程，这部分难以并行化)
+
     let runner = Runner::new(
         RunnerConfig {
-            diagonal: cfg.layer_difference == 0,
-            hierarchical: HIERARCHICAL_MAX,
-            batch_size: 25600,
+            diagonal: cfg.diagonal,
+            hierarchical: cfg.hierarchical,
+            batch_size: cfg.batch_size as i32,
             good_min_area: cfg.filter_speckle,
             good_max_area: w * h,
             // 修正：is_same_color_a 是要忽略的位数，而 color_precision 是要保留的位数
-            is_same_color_a: (8 - cfg.color_precision) as i32,
+            is_same_color_a: 8 - cfg.color_precision,
             is_same_color_b: 1,
             deepen_diff: cfg.layer_difference,
             hollow_neighbours: 1,
-            key_color: Color::default(),
+            key_color: key_color.unwrap_or_default(),
             keying_action: KeyingAction::Discard,
         },
         color_image,
     );
     
-    let clusters = runner.run();
-    let view = clusters.view();
-    
+    if cfg.edge_layers {
+        runner
+            .builder()
+            .deepen(edge_aware_deepen(cfg.filter_speckle, w * h, cfg.layer_difference, w))
+            .run()
+    } else {
+        runner.run()
+    }
+}
+
+/// `edge_layers` 判定为边缘色块所需的最强邻居色差 (RGB 各通道差之和)
+const EDGE_MIN_CONTRAST: i32 = 96;
+
+/// `edge_layers` 的分层判定：在 visioncortex 默认规则的基础上放宽边缘色块
+/// 
+/// 默认规则 (`RunnerConfig` 内部的 `patch_good`) 要求面积在范围内、周长小于面积
+/// (排除细线) 且与最近邻居的色差大于 `deepen_diff`。这里对最强邻居色差足够大的色块
+/// (即夹在两个对比强烈区域之间的过渡带) 跳过细线判定，并把色差阈值减半。
+fn edge_aware_deepen(
+    good_min_area: usize,
+    good_max_area: usize,
+    deepen_diff: i32,
+    width: usize,
+) -> impl Fn(&BuilderImpl, &Cluster, &[NeighbourInfo]) -> bool {
+    move |_internal, patch, neighbours| {
+        let area = patch.area();
+        if area <= good_min_area || area >= good_max_area {
+            return false;
+        }
+        
+        let is_edge = neighbours.len() >= 2
+            && neighbours[neighbours.len() - 1].diff >= EDGE_MIN_CONTRAST;
+        if is_edge {
+            return neighbours[0].diff > deepen_diff / 2;
+        }
+        
+        let thread_like = good_min_area != 0
+            && Shape::image_boundary_list(&patch.to_image_with_hole(width as u32, true)).len() >= area;
+        !thread_like && neighbours[0].diff > deepen_diff
+    }
+}
+
+/// 取聚类的输出颜色 (已解码回 sRGB)
+fn cluster_color(cluster: &visioncortex::color_clusters::Cluster, cfg: &TracerConfig) -> Color {
+    let color = cluster.residue_color();
+    match cfg.color_space {
+        ColorSpace::Rgb => color,
+        ColorSpace::Lab => color_space::decode_lab_color(color),
+    }
+}
+
+/// 第二阶段：曲线拟合 (并行处理！)，返回按绘制顺序排列的 (路径, 颜色) 列表
+fn fit_clusters(view: &ClustersView, cfg: &TracerConfig) -> (Vec<(CompoundPath, Color)>, Vec<Source>) {
     // 收集所有需要处理的 cluster 索引
     let cluster_indices: Vec<_> = view.clusters_output.iter().rev().copied().collect();
-    
-    // 第二阶段：曲线拟合 (并行处理！)
+    (fit_cluster_indices(view, &cluster_indices, cfg), cluster_sources(view, &cluster_indices, cfg))
+}
+
+/// 拟合结果的来源：聚类的像素包围盒 (右、下边界不含) 与纹理统计
+#[derive(Clone, Copy, Debug, Default)]
+struct Source {
+    rect: BoundingRect,
+    /// 开启 `detect_texture` 且判定为纹理时的图案近似
+    texture: Option<texture::Texture>,
+}
+
+/// 各聚类的来源信息，与 `fit_cluster_indices` 的结果一一对应
+fn cluster_sources(view: &ClustersView, cluster_indices: &[ClusterIndex], cfg: &TracerConfig) -> Vec<Source> {
+    let owners = if cfg.detect_texture { texture::visible_owners(view) } else { Vec::new() };
+    cluster_indices
+        .par_iter()
+        .map(|&index| Source {
+            rect: view.get_cluster(index).rect,
+            texture: cfg.detect_texture.then(|| texture::detect(view, index, &owners, cfg)).flatten(),
+        })
+        .collect()
+}
+
+/// 把单个聚类拟合为复合路径
+/// 
+/// 与 `Cluster::to_compound_path` 相同，先把聚类按连通性拆成若干块再逐块描边；
+/// 区别是连通性跟随 `cfg.diagonal`，斜向连通的聚类 (如 1px 斜线) 不会被拆成单个像素。
+fn cluster_compound_path(cluster: &Cluster, view: &ClustersView, cfg: &TracerConfig) -> CompoundPath {
+    let mut paths = CompoundPath::new();
+    // hole = false：叠放模式下不挖空洞
+    for part in cluster.to_image_with_hole(view.width, false).to_clusters(cfg.diagonal).iter() {
+        let offset = visioncortex::PointI32 {
+            x: cluster.rect.left + part.rect.left,
+            y: cluster.rect.top + part.rect.top,
+        };
+        let image = part.to_binary_image();
+        if let Some(stats) = &cfg.convergence {
+            stats.measure(&image, cfg);
+        }
+        paths.append(visioncortex::clusters::Cluster::image_to_compound_path(
+            &offset,
+            &image,
+            cfg.mode,
+            // 配置中的角度阈值以度为单位 (与 vtracer 一致)，visioncortex 要求弧度
+            (cfg.corner_threshold as f64).to_radians(),
+            cfg.length_threshold,
+            cfg.max_iterations,
+            (cfg.splice_threshold as f64).to_radians(),
+        ));
+    }
+    paths
+}
+
+/// 对指定的聚类做曲线拟合，结果顺序与 `cluster_indices` 一致
+fn fit_cluster_indices(
+    view: &ClustersView,
+    cluster_indices: &[ClusterIndex],
+    cfg: &TracerConfig,
+) -> Vec<(CompoundPath, Color)> {
     // 每个 cluster 可以独立处理
-    let path_results: Vec<_> = cluster_indices
+    let mut path_results: Vec<_> = cluster_indices
         .par_iter()  // 🚀 使用 Rayon 并行迭代
-        .filter_map(|&cluster_index| {
+        .map(|&cluster_index| {
             let cluster = view.get_cluster(cluster_index);
-            let paths = cluster.to_compound_path(
-                &view,
-                false,  // hole (Stacked mode = false)
-                cfg.mode,
-                cfg.corner_threshold as f64, // visioncortex 使用角度(Degrees)
-                cfg.length_threshold,
-                cfg.max_iterations,
-                cfg.splice_threshold as f64, // visioncortex 使用角度(Degrees)
-            );
+            let paths = cluster_compound_path(cluster, view, cfg);
             
-            let color = cluster.residue_color();
-            Some((paths, color))
+            let color = cluster_color(cluster, cfg);
+            (paths, color)
         })
         .collect();
     
-    // 构建 SVG 字符串 (使用 CompoundPath 的 to_svg_string 方法)
-    let mut svg = String::with_capacity(1024 * 64);
-    writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}">"#, w, h).ok();
-    
-    for (compound_path, color) in path_results {
-        let color_str = format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
-        let (path_str, offset) = compound_path.to_svg_string(
-            true,  // close path
-            visioncortex::PointF64::default(),
-            cfg.path_precision,
-        );
+    if let Some(palette) = &cfg.palette {
+        palette::snap_colors(&mut path_results, palette, cfg.color_space);
+    }
+    
+    path_results
+}
+
+/// `protect_dark` 判定为深色的亮度上限 (Rec.601 加权，0~255)
+const DARK_LUMA_THRESHOLD: u32 = 96;
+
+fn luma(color: Color) -> u32 {
+    (299 * color.r as u32 + 587 * color.g as u32 + 114 * color.b as u32) / 1000
+}
+
+/// 找回被斑点过滤掉的深色小色块
+/// 
+/// 斑点过滤发生在 visioncortex 的层次聚类内部 (面积不足的色块并入邻居)，无法逐块干预，
+/// 因此对原图再做一次不过滤斑点的聚类，从中挑出面积低于阈值且亮度足够低的色块，
+/// 拟合后叠加在常规结果之上。
+fn fit_dark_speckles(
+    color_image: visioncortex::ColorImage,
+    cfg: &TracerConfig,
+) -> (Vec<(CompoundPath, Color)>, Vec<Source>) {
+    let mut unfiltered_cfg = cfg.clone();
+    unfiltered_cfg.filter_speckle = 0;
+    
+    let clusters = cluster_image(color_image, &unfiltered_cfg);
+    let view = clusters.view();
+    
+    let dark_indices: Vec<_> = view.clusters_output
+        .iter()
+        .rev()
+        .copied()
+        .filter(|&index| {
+            let cluster = view.get_cluster(index);
+            cluster.area() <= cfg.filter_speckle
+                && luma(cluster_color(cluster, cfg)) < DARK_LUMA_THRESHOLD
+        })
+        .collect();
+    
+    (fit_cluster_indices(&view, &dark_indices, cfg), cluster_sources(&view, &dark_indices, cfg))
+}
+
+/// 聚类 + 并行曲线拟合
+fn cluster_and_fit(
+    color_image: visioncortex::ColorImage,
+    cfg: &TracerConfig,
+) -> Vec<(CompoundPath, Color)> {
+    cluster_and_fit_timed(color_image, cfg).0
+}
+
+/// 与 `cluster_and_fit` 相同，同时返回聚类与拟合两个阶段各自的耗时
+/// 
+/// 聚类阶段包括参数预估 (`auto_speckle`、`refine_passes`)、`text_aware` 的字形提取与主聚类；
+/// 其余部分 (含 `protect_dark` 的补充聚类与拟合后的处理) 计入拟合阶段。
+fn cluster_and_fit_timed(
+    color_image: visioncortex::ColorImage,
+    cfg: &TracerConfig,
+) -> (Vec<(CompoundPath, Color)>, bench::PhaseTimes) {
+    let (path_results, _, times) = cluster_and_fit_sourced(color_image, cfg);
+    (path_results, times)
+}
+
+/// 按 `keep` 中对应的标记保留元素，用于让路径与其来源包围盒同步过滤
+fn retain_by_mask<T>(items: &mut Vec<T>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    items.retain(|_| keep.next().copied().unwrap_or(false));
+}
+
+/// 合并后的来源信息：`targets[i]` 为第 i 条路径并入的结果下标
+/// 
+/// 并入同一结果的包围盒取并集，纹理沿用其中最先绘制的一条。
+fn merge_sources(sources: &[Source], targets: &[usize]) -> Vec<Source> {
+    let mut merged: Vec<Option<Source>> = vec![None; targets.iter().map(|&t| t + 1).max().unwrap_or(0)];
+    for (&source, &target) in sources.iter().zip(targets) {
+        match &mut merged[target] {
+            Some(existing) => existing.rect.merge(source.rect),
+            slot => *slot = Some(source),
+        }
+    }
+    merged.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// 需要先看一遍图像才能确定的参数 (自动斑点阈值、自动颜色数、两遍追踪的调色板)，都不需要时为 `None`
+fn resolve_config(color_image: &visioncortex::ColorImage, cfg: &TracerConfig) -> Option<TracerConfig> {
+    let mut resolved: Option<TracerConfig> = None;
+    if cfg.auto_speckle {
+        let mut c = cfg.clone();
+        c.filter_speckle = analysis::estimate_speckle(color_image, cfg);
+        c.auto_speckle = false;
+        resolved = Some(c);
+    }
+    if cfg.auto_color_count {
+        let mut c = resolved.take().unwrap_or_else(|| cfg.clone());
+        c.color_count = analysis::estimate_color_count(color_image, &c);
+        c.layer_difference = layer_difference_for(c.color_count);
+        c.auto_color_count = false;
+        resolved = Some(c);
+    }
+    // 两遍追踪：先修正调色板，之后按固定调色板处理
+    if cfg.refine_passes > 1 && cfg.palette.is_none() {
+        let mut c = resolved.take().unwrap_or_else(|| cfg.clone());
+        c.palette = Some(palette::refine_palette(color_image, &c));
+        resolved = Some(c);
+    }
+    resolved
+}
+
+/// 与 `cluster_and_fit_timed` 相同，另外返回每条路径的来源信息 (见 `Source`)
+/// 
+/// 包围盒按输入图像的像素坐标；来源与路径一一对应，路径在后处理中合并时见 `merge_sources`。
+fn cluster_and_fit_sourced(
+    color_image: visioncortex::ColorImage,
+    cfg: &TracerConfig,
+) -> (Vec<(CompoundPath, Color)>, Vec<Source>, bench::PhaseTimes) {
+    if cfg.tileable {
+        return tileable::cluster_and_fit_wrapped(color_image, cfg);
+    }
+    let start = bench::now_ms();
+    let resolved = resolve_config(&color_image, cfg);
+    let cfg = resolved.as_ref().unwrap_or(cfg);
+    
+    // 保护深色小色块需要对原图再聚类一次，先留一份副本
+    let protect_source = (cfg.protect_dark && cfg.filter_speckle > 0).then(|| color_image.clone());
+    
+    let run = || {
+        let mut color_image = color_image;
+        let (text_paths, text_sources) =
+            if cfg.text_aware { text::extract(&mut color_image, cfg) } else { (Vec::new(), Vec::new()) };
+        let (mut path_results, mut sources, clustered) = match cfg.cluster_scale {
+            Some(scale) => {
+                let clusters = cluster_scale::cluster(color_image, scale, cfg);
+                let clustered = bench::now_ms();
+                let (paths, sources) = fit_clusters(&clusters.view(), cfg);
+                (paths, sources, clustered)
+            }
+            None => {
+                let clusters = cluster_image(color_image, cfg);
+                let clustered = bench::now_ms();
+                let (paths, sources) = fit_clusters(&clusters.view(), cfg);
+                (paths, sources, clustered)
+            }
+        };
+        if let Some(source) = protect_source {
+            let (dark_paths, dark_sources) = fit_dark_speckles(source, cfg);
+            path_results.extend(dark_paths);
+            sources.extend(dark_sources);
+        }
+        path_results.extend(text_paths);
+        sources.extend(text_sources);
+        (path_results, sources, clustered)
+    };
+    
+    // 指定了线程上限时在独立线程池中拟合，建池失败 (如 WASM) 则回退到全局线程池
+    let scoped_pool = cfg.max_threads.and_then(|n| {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(n.max(1)).build().ok();
+        if pool.is_none() {
+            warnings::emit("fallback", &format!("无法创建 {} 线程的独立线程池，改用全局线程池", n));
+        }
+        pool
+    });
+    let (path_results, sources, clustered) = match scoped_pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    };
+    
+    let (path_results, sources) = finish_paths(path_results, sources, cfg);
+    
+    let times = bench::PhaseTimes {
+        cluster_ms: clustered - start,
+        fit_ms: bench::now_ms() - clustered,
+    };
+    (path_results, sources, times)
+}
+
+/// 拟合之后的后处理：丢弃无效路径、合并、简化、数量预算与外扩，`sources` 随路径同步调整
+fn finish_paths(
+    mut path_results: Vec<(CompoundPath, Color)>,
+    mut sources: Vec<Source>,
+    cfg: &TracerConfig,
+) -> (Vec<(CompoundPath, Color)>, Vec<Source>) {
+    // 含 NaN/无穷坐标的路径会让部分渲染器拒绝整个文档，直接丢弃
+    let before = path_results.len();
+    let keep: Vec<bool> = path_results.iter().map(|(compound_path, _)| geometry::is_finite(compound_path)).collect();
+    retain_by_mask(&mut path_results, &keep);
+    retain_by_mask(&mut sources, &keep);
+    if path_results.len() < before {
+        warnings::emit("dropped", &format!("{} 条路径含有无效坐标 (NaN/无穷)，已跳过", before - path_results.len()));
+    }
+    
+    if cfg.min_points_per_path > 0 {
+        let before = path_results.len();
+        let keep: Vec<bool> = path_results
+            .iter()
+            .map(|(compound_path, _)| geometry::point_count(compound_path) >= cfg.min_points_per_path)
+            .collect();
+        retain_by_mask(&mut path_results, &keep);
+        retain_by_mask(&mut sources, &keep);
+        if path_results.len() < before {
+            warnings::emit("dropped", &format!(
+                "{} 条路径点数少于 {}，已跳过",
+                before - path_results.len(), cfg.min_points_per_path
+            ));
+        }
+    }
+    
+    if cfg.post_merge_tolerance > 0.0 || cfg.postprocess.merge_same_color {
+        let targets = palette::merge_similar(&mut path_results, cfg.post_merge_tolerance, cfg.color_space);
+        sources = merge_sources(&sources, &targets);
+    }
+    
+    if cfg.postprocess.simplify_tolerance > 0.0 {
+        for (compound_path, _) in &mut path_results {
+            postprocess::simplify_path(compound_path, cfg.postprocess.simplify_tolerance);
+        }
+    }
+    
+    if let Some(max_paths) = cfg.max_paths {
+        let targets = budget::apply(&mut path_results, max_paths);
+        sources = merge_sources(&sources, &targets);
+    }
+    
+    if cfg.min_stroke_width > 0.0 && cfg.stroke_width.is_none() {
+        for (compound_path, _) in &mut path_results {
+            let width = geometry::mean_width(compound_path);
+            if width < cfg.min_stroke_width {
+                geometry::dilate(compound_path, (cfg.min_stroke_width - width) / 2.0);
+            }
+        }
+    }
+    
+    if cfg.overlap > 0.0 && cfg.stroke_width.is_none() {
+        for (compound_path, _) in &mut path_results {
+            geometry::dilate(compound_path, cfg.overlap);
+        }
+    }
+    
+    if cfg.trap_width > 0.0 && cfg.stroke_width.is_none() {
+        // 最后绘制的路径在最上层，保持原状
+        let top = path_results.len().saturating_sub(1);
+        for (compound_path, _) in &mut path_results[..top] {
+            geometry::dilate(compound_path, cfg.trap_width);
+        }
+    }
+    
+    (path_results, sources)
+}
+
+/// 生成元素的着色属性：填充模式为 `fill`，描边模式为 `fill="none"` + `stroke`
+fn paint_attributes(color: &Color, cfg: &TracerConfig) -> String {
+    let color_str = format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+    match cfg.stroke_width {
+        None => format!(r#"fill="{}""#, color_str),
+        Some(width) => {
+            let mut attrs = format!(
+                r#"fill="none" stroke="{}" stroke-width="{}""#,
+                color_str, format_number(width, cfg.path_precision)
+            );
+            if let Some(join) = cfg.stroke_linejoin {
+                write!(attrs, r#" stroke-linejoin="{}""#, join).ok();
+            }
+            if let Some(cap) = cfg.stroke_linecap {
+                write!(attrs, r#" stroke-linecap="{}""#, cap).ok();
+            }
+            if cfg.non_scaling_stroke {
+                attrs.push_str(r#" vector-effect="non-scaling-stroke""#);
+            }
+            attrs
+        }
+    }
+}
+
+/// 将拟合结果逐条写为 `<path>` 元素，`origin` 为整体坐标偏移
+/// 
+/// 所有数字都经由 `format!` 输出，Rust 的格式化与区域设置无关，小数点恒为 `.`
+fn write_paths(
+    svg: &mut String,
+    path_results: &[(CompoundPath, Color)],
+    cfg: &TracerConfig,
+    origin: PointF64,
+) {
+    let mut dropped = 0;
+    let mut shared = Vec::new();
+    let textures = cfg.textures.as_deref().map_or(&[][..], Vec::as_slice);
+    let mut defs = texture::pattern_defs(textures, cfg);
+    if cfg.dedupe_shapes {
+        let shapes;
+        (shapes, shared) = dedupe::shared_shapes(path_results, cfg, origin);
+        defs.push_str(&shapes);
+    }
+    if !defs.is_empty() {
+        writeln!(svg, "<defs>\n{}</defs>", defs).ok();
+    }
+    for (i, (compound_path, color)) in path_results.iter().enumerate() {
+        // 清理时按原路径确定写出坐标的平移量，清理后的路径沿用它：首条子路径被删去时
+        // 偏移不随之改变，判定重合所用的取整与实际写出的数字一致
+        let absolute = cfg.postprocess.relative_commands || cfg.postprocess.minify;
+        let shift = if absolute {
+            Some(origin)
+        } else if let Some(transform_precision) = cfg.transform_precision {
+            precision::rounded_offset(compound_path, origin, transform_precision).map(|(_, shift)| shift)
+        } else {
+            geometry::first_point(compound_path).map(|p| PointF64::new(-p.x, -p.y))
+        };
+        let cleaned = match shift {
+            Some(shift) if cfg.clean_commands => Some((postprocess::clean_commands(compound_path, shift, cfg.path_precision), shift)),
+            _ => None,
+        };
+        let compound_path = cleaned.as_ref().map_or(compound_path, |(path, _)| path);
+        let mut paint = match textures.get(i) {
+            Some(Some(_)) => texture::fill_attribute(i),
+            _ => paint_attributes(color, cfg),
+        };
+        if cfg.layer_ids {
+            paint = format!(r#"id="snapsvg-layer-{}" {}"#, i, paint);
+        }
+        if let Some(duration) = cfg.animate_draw {
+            let animation = reveal::path_attributes(compound_path, color, i, path_results.len(), duration, cfg);
+            paint = format!("{} {}", paint, animation);
+        }
+        
+        if let Some(&Some((id, offset))) = shared.get(i) {
+            writeln!(
+                svg,
+                r##"<use href="#snapsvg-shape-{}" x="{}" y="{}" {}/>"##,
+                id,
+                format_number(offset.x, cfg.path_precision),
+                format_number(offset.y, cfg.path_precision),
+                paint,
+            ).ok();
+            continue;
+        }
+        
+        if cfg.detect_primitives {
+            if let Some(primitive) = primitives::detect(compound_path) {
+                primitive.write_svg(svg, &paint, origin, cfg.path_precision);
+                continue;
+            }
+        }
+        
+        let PostprocessOptions { relative_commands, minify, .. } = cfg.postprocess;
+        if relative_commands || minify {
+            let path_str = postprocess::path_data(compound_path, origin, cfg.path_precision, relative_commands, minify);
+            if path_str.is_empty() {
+                dropped += 1;
+            } else {
+                write!(svg, r#"<path d="{}" {}/>"#, svg_tools::close_subpaths(&path_str), paint).ok();
+                if !minify {
+                    svg.push('\n');
+                }
+            }
+            continue;
+        }
+        
+        let (path_str, offset) = match (&cleaned, cfg.transform_precision) {
+            (Some((_, shift)), _) => (
+                precision::path_data_with_shift(compound_path, *shift, cfg.path_precision),
+                PointF64::new(origin.x - shift.x, origin.y - shift.y),
+            ),
+            (None, Some(transform_precision)) => {
+                precision::path_data_with_rounded_offset(compound_path, origin, cfg.path_precision, transform_precision)
+            }
+            (None, None) => compound_path.to_svg_string(
+                true,  // close path
+                origin,
+                cfg.path_precision,
+            ),
+        };
+        let transform_precision = cfg.transform_precision.or(cfg.path_precision);
         if !path_str.is_empty() {
             writeln!(
                 svg, 
-                r#"<path d="{}" fill="{}" transform="translate({:.2},{:.2})"/>"#,
-                path_str, color_str, offset.x, offset.y
+                r#"<path d="{}" {} transform="translate({}{}{})"/>"#,
+                svg_tools::close_subpaths(&path_str),
+                paint,
+                format_fixed(offset.x, transform_precision),
+                cfg.translate_separator,
+                format_fixed(offset.y, transform_precision),
             ).ok();
+        } else {
+            dropped += 1;
         }
     }
-    
-    writeln!(svg, "</svg>").ok();
-    
-    Ok(svg)
+    if dropped > 0 {
+        warnings::emit("dropped", &format!("{} 个聚类拟合后没有路径，已跳过", dropped));
+    }
+}
+
+/// 按固定小数位数格式化数字 (不去掉末尾的 0)，`None` 表示不取整
+fn format_fixed(num: f64, precision: Option<u32>) -> String {
+    match precision {
+        None => format!("{}", num),
+        Some(p) => format!("{:.*}", p as usize, num),
+    }
+}
+
+/// 64 位 FNV-1a 哈希，格式化为 16 位小写十六进制
+fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// 转义 XML 文本与属性值中的特殊字符
+/// 
+/// 标题、描述等调用方提供的文本写入输出前都必须经过这里；颜色与坐标由数值格式化得到，
+/// 不含特殊字符，无需转义。XML 1.0 不允许出现的控制字符 (制表、换行、回车以外的
+/// `U+0000`~`U+001F`) 以及 `U+FFFE`/`U+FFFF` 无法用实体表示，直接丢弃，否则整份文档无法解析。
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 根元素 `viewBox` 之前的属性 (含前导空格)：命名空间 (内联模式下省略)、无障碍属性与物理尺寸
+/// 
+/// `width`/`height` 为像素尺寸，按 `physical_unit` 换算后输出。
+fn svg_root_attrs(cfg: &TracerConfig, width: f64, height: f64) -> String {
+    let mut attrs = String::new();
+    if !cfg.inline {
+        attrs.push_str(r#" xmlns="http://www.w3.org/2000/svg""#);
+    }
+    let labels: Vec<&str> = [(cfg.title.is_some(), "snapsvg-title"), (cfg.desc.is_some(), "snapsvg-desc")]
+        .iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, id)| *id)
+        .collect();
+    if !labels.is_empty() {
+        write!(attrs, r#" role="img" aria-labelledby="{}""#, labels.join(" ")).ok();
+    }
+    if let Some((unit, per_px)) = cfg.physical_unit {
+        write!(
+            attrs,
+            r#" width="{}{}" height="{}{}""#,
+            format_number(width * per_px, Some(3)), unit,
+            format_number(height * per_px, Some(3)), unit,
+        ).ok();
+    }
+    if cfg.normalize_coords {
+        attrs.push_str(r#" preserveAspectRatio="none""#);
+    }
+    for (name, value) in &cfg.root_attributes {
+        if attrs.contains(&format!(" {}=\"", name)) {
+            warnings::emit("option", &format!("root_attributes 中的 \"{}\" 与生成的属性重复，已跳过", name));
+            continue;
+        }
+        write!(attrs, r#" {}="{}""#, name, value).ok();
+    }
+    attrs
+}
+
+/// 根元素的首批子元素：`<title>` 与 `<desc>` (每个独占一行，未设置时为空)
+fn svg_root_children(cfg: &TracerConfig) -> String {
+    let mut children = String::new();
+    if let Some(title) = &cfg.title {
+        writeln!(children, r#"<title id="snapsvg-title">{}</title>"#, escape_xml(title)).ok();
+    }
+    if let Some(desc) = &cfg.desc {
+        writeln!(children, r#"<desc id="snapsvg-desc">{}</desc>"#, escape_xml(desc)).ok();
+    }
+    if let Some(duration) = cfg.animate_draw {
+        children.push_str(&reveal::style_element(duration));
+    }
+    children
+}
+
+/// 输出完整的 SVG 文档 (根元素 + 全部路径)
+fn build_svg(
+    width: usize,
+    height: usize,
+    path_results: &[(CompoundPath, Color)],
+    cfg: &TracerConfig,
+) -> String {
+    if let Some((target_width, target_height)) = cfg.normalize_to {
+        let (normalized, cfg) = normalize_paths(path_results, cfg, target_width as f64, target_height as f64);
+        let cfg = TracerConfig { normalize_to: None, ..cfg };
+        return build_svg(target_width as usize, target_height as usize, &normalized, &cfg);
+    }
+    if cfg.normalize_coords {
+        return write_normalized_document(width as f64, height as f64, path_results, cfg);
+    }
+    
+    let bounds = if cfg.crop_to_content || cfg.center_origin { content_bounds(path_results) } else { None };
+    if let Some((mut min, mut max)) = bounds {
+        if cfg.crop_to_content && cfg.snap_viewbox_to_int {
+            min = PointF64::new(min.x.floor(), min.y.floor());
+            max = PointF64::new(max.x.ceil(), max.y.ceil());
+        }
+        let mut center = PointF64::new((min.x + max.x) / 2.0, (min.y + max.y) / 2.0);
+        if cfg.snap_viewbox_to_int {
+            center = PointF64::new(center.x.round(), center.y.round());
+        }
+        let offset = if cfg.center_origin { PointF64::new(-center.x, -center.y) } else { PointF64::new(-min.x, -min.y) };
+        let moved: Vec<_> = path_results
+            .iter()
+            .map(|(path, color)| (transform_compound_path(path, 1.0, offset), *color))
+            .collect();
+        // 裁剪时 viewBox 为平移后的包围盒，否则为平移后的整张画布
+        let (view_min, size) = if cfg.crop_to_content {
+            (PointF64::new(min.x + offset.x, min.y + offset.y), PointF64::new(max.x - min.x, max.y - min.y))
+        } else {
+            (offset, PointF64::new(width as f64, height as f64))
+        };
+        return write_document(view_min, size.x, size.y, &moved, cfg);
+    }
+    
+    write_document(PointF64::default(), width as f64, height as f64, path_results, cfg)
+}
+
+/// 写出根元素为 `viewBox="min.x min.y width height"` 的完整文档
+fn write_document(min: PointF64, width: f64, height: f64, path_results: &[(CompoundPath, Color)], cfg: &TracerConfig) -> String {
+    let mut svg = String::with_capacity(1024 * 64);
+    writeln!(
+        svg,
+        r#"<svg{} viewBox="{} {} {} {}">"#,
+        svg_root_attrs(cfg, width, height),
+        format_number(min.x, cfg.path_precision),
+        format_number(min.y, cfg.path_precision),
+        format_number(width, cfg.path_precision),
+        format_number(height, cfg.path_precision),
+    ).ok();
+    svg.push_str(&svg_root_children(cfg));
+    write_paths(&mut svg, path_results, cfg, PointF64::default());
+    writeln!(svg, "</svg>").ok();
+    svg
+}
+
+/// 写出坐标归一化到 0~1 的完整文档 (见 `TraceOptions::normalize_coords`)
+fn write_normalized_document(width: f64, height: f64, path_results: &[(CompoundPath, Color)], cfg: &TracerConfig) -> String {
+    let (width, height) = (width.max(1.0), height.max(1.0));
+    let normalized: Vec<_> = path_results
+        .iter()
+        .map(|(path, color)| (map_compound_path(path, |p| PointF64::new(p.x / width, p.y / height)), *color))
+        .collect();
+    // 长边每多一位数，小数多保留一位，绝对精度与像素坐标相同
+    let extra_digits = width.max(height).log10().ceil() as u32;
+    let cfg = TracerConfig {
+        path_precision: cfg.path_precision.map(|p| p + extra_digits),
+        transform_precision: cfg.transform_precision.map(|p| p + extra_digits),
+        stroke_width: cfg.stroke_width.map(|w| w / (width * height).sqrt()),
+        textures: None,
+        animate_draw: None,
+        ..cfg.clone()
+    };
+    
+    let mut svg = String::with_capacity(1024 * 64);
+    writeln!(svg, r#"<svg{} viewBox="0 0 1 1">"#, svg_root_attrs(&cfg, width, height)).ok();
+    svg.push_str(&svg_root_children(&cfg));
+    write_paths(&mut svg, &normalized, &cfg, PointF64::default());
+    writeln!(svg, "</svg>").ok();
+    svg
+}
+
+/// 并行矢量化：使用 Rayon 在曲线拟合阶段并行处理
+/// 
+/// 注意：此函数需要线程池已初始化 (initThreadPool)
+/// 
+/// 确定性：相同输入与参数总是得到逐字节相同的 SVG，与线程数和调度顺序无关。
+/// 聚类阶段单线程执行且不使用随机数；拟合阶段各聚类互相独立，
+/// `par_iter().collect()` 按输入顺序收集结果，输出顺序固定为聚类的绘制顺序。
+/// 今后若并行化聚类阶段，必须保持这一约定。
+#[wasm_bindgen]
+pub fn trace_rgba_parallel(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    color_count: u8,
+    path_precision: u8,
+    corner_threshold: u8,
+    filter_speckle: u32,
+) -> Result<String, JsValue> {
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    
+    let cfg = build_config(color_count, path_precision, corner_threshold, filter_speckle);
+    
+    // 创建 ColorImage (visioncortex 版本)
+    let color_image = visioncortex::ColorImage {
+        pixels: rgba_data.to_vec(),
+        width: w,
+        height: h,
+    };
+    
+    let path_results = cluster_and_fit(color_image, &cfg);
+    
+    // 构建 SVG 字符串 (使用 CompoundPath 的 to_svg_string 方法)
+    Ok(build_svg(w, h, &path_results, &cfg))
+}
+
+/// 并行矢量化（选项版本）：与 `trace_rgba_parallel` 相同的流程，参数通过 `TraceOptions` 传入
+#[wasm_bindgen]
+pub fn trace_rgba_with_options(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    trace_rgba_inner(rgba_data, width, height, options.to_config(), false).map(|(svg, ..)| svg)
+}
+
+/// `trace_rgba_with_options` 的实现，警告通道由调用方安装
+/// 
+/// `cfg` 由调用方从选项转换一次后传入，选项校验产生的警告只报告一次。
+/// `report` 为 `true` 时同时估计输出是否可能有透明区域，并返回 `cfg.convergence` 统计的平滑收敛情况。
+fn trace_rgba_inner(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    cfg: TracerConfig,
+    report: bool,
+) -> Result<(String, bool, Option<convergence::Summary>), JsValue> {
+    trace_rgba_core(rgba_data, width, height, cfg, report).map_err(|e| JsValue::from_str(&e))
+}
+
+/// `trace_rgba_inner` 的主体，错误以字符串返回，原生入口 `trace_core` 同样调用它
+fn trace_rgba_core(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    mut cfg: TracerConfig,
+    report: bool,
+) -> Result<(String, bool, Option<convergence::Summary>), String> {
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    validate_rgba_len(rgba_data, w, h)?;
+    
+    if let Some(threshold) = cfg.line_extraction {
+        return Ok((lines::trace_lines(rgba_data, w, h, threshold, &cfg), true, None));
+    }
+    let color_image = visioncortex::ColorImage {
+        pixels: rgba_data.to_vec(),
+        width: w,
+        height: h,
+    };
+    
+    let (path_results, sources, _) = cluster_and_fit_sourced(color_image, &cfg);
+    validate_nonempty(&path_results, &cfg)?;
+    if cfg.detect_texture && cfg.stroke_width.is_none() {
+        cfg.textures = Some(Arc::new(sources.iter().map(|source| source.texture).collect()));
+    }
+    
+    let uncovered = report && may_have_transparency(&path_results, &cfg, w, h);
+    let convergence = cfg.convergence.as_ref().map(|stats| stats.summary());
+    Ok((build_svg(w, h, &path_results, &cfg), uncovered, convergence))
+}
+
+/// 保守估计输出是否有未被路径覆盖的区域，`false` 表示确定完全覆盖 (见 `TraceResult::has_transparency`)
+fn may_have_transparency(
+    path_results: &[(CompoundPath, Color)],
+    cfg: &TracerConfig,
+    width: usize,
+    height: usize,
+) -> bool {
+    if path_results.is_empty() || cfg.stroke_width.is_some() || cfg.normalize_to.is_some() {
+        return true;
+    }
+    // 裁剪后 viewBox 为内容的包围盒
+    let view_area = match content_bounds(path_results).filter(|_| cfg.crop_to_content) {
+        Some((min, max)) => (max.x - min.x) * (max.y - min.y),
+        None => (width * height) as f64,
+    };
+    let covered: f64 = path_results.iter().map(|(path, _)| geometry::compound_path_area(path)).sum();
+    // 留出浮点误差的余量
+    covered < view_area * (1.0 - 1e-6)
+}
+
+/// 直接矢量化 Canvas 的 `ImageData` 对象 (`ctx.getImageData(...)` 的返回值)
+///
+/// 从 `data`、`width`、`height` 三个属性读取图像：像素从 `Uint8ClampedArray` 一次复制进
+/// WASM 内存即可使用，比先转成普通数组再传入 `trace_rgba_with_options` 少一次复制。
+/// 也接受结构相同的普通对象，`data` 可以是 `Uint8ClampedArray` 或 `Uint8Array`。
+///
+/// 跨域限制由浏览器在 `getImageData` 处执行：绘制过未经 CORS 授权的跨域图片的 Canvas
+/// (含 `OffscreenCanvas`) 会被标记为"污染"，读取像素时直接抛出 `SecurityError`，
+/// 调用本函数之前就会失败。`ImageBitmap` 无法直接读取像素，需先绘制到 Canvas 上再取 `ImageData`。
+#[wasm_bindgen]
+pub fn trace_image_data(image_data: &JsValue, options: &TraceOptions) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+
+    let get = |key: &str| js_sys::Reflect::get(image_data, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED);
+    let dimension = |key: &str| {
+        get(key)
+            .as_f64()
+            .filter(|v| v.fract() == 0.0 && *v >= 0.0 && *v <= u32::MAX as f64)
+            .map(|v| v as usize)
+            .ok_or_else(|| JsValue::from_str(&format!("ImageData 缺少有效的 {} 属性", key)))
+    };
+    let w = dimension("width")?;
+    let h = dimension("height")?;
+
+    let data = get("data");
+    let pixels = if let Some(array) = data.dyn_ref::<js_sys::Uint8ClampedArray>() {
+        array.to_vec()
+    } else if let Some(array) = data.dyn_ref::<js_sys::Uint8Array>() {
+        array.to_vec()
+    } else {
+        return Err(JsValue::from_str("ImageData 的 data 属性必须是 Uint8ClampedArray 或 Uint8Array"));
+    };
+    check_rgba_len(&pixels, w, h)?;
+
+    let cfg = options.to_config();
+    if let Some(threshold) = cfg.line_extraction {
+        return Ok(lines::trace_lines(&pixels, w, h, threshold, &cfg));
+    }
+    let color_image = visioncortex::ColorImage { pixels, width: w, height: h };
+    let path_results = cluster_and_fit(color_image, &cfg);
+    check_empty(&path_results, &cfg)?;
+
+    Ok(build_svg(w, h, &path_results, &cfg))
+}
+
+/// 只在掩码区域内矢量化：`mask` 为单通道缓冲区 (每像素 1 字节)
+/// 
+/// 掩码值 >= 128 的像素参与追踪，其余像素视为透明，不会形成任何色块，
+/// 输出中这些区域保持空白。适合画图应用中按选区局部矢量化。
+#[wasm_bindgen]
+pub fn trace_rgba_masked(
+    rgba_data: &[u8],
+    mask: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    if mask.len() != w * h {
+        return Err(JsValue::from_str(&format!(
+            "掩码长度不匹配: 期望 {} 字节 ({}x{}x1), 实际 {} 字节",
+            w * h, w, h, mask.len()
+        )));
+    }
+    
+    let mut cfg = options.to_config();
+    cfg.mask = Some(mask.to_vec());
+    cfg.key_transparent = true;
+    
+    let color_image = visioncortex::ColorImage {
+        pixels: rgba_data.to_vec(),
+        width: w,
+        height: h,
+    };
+    
+    let path_results = cluster_and_fit(color_image, &cfg);
+    check_empty(&path_results, &cfg)?;
+    
+    Ok(build_svg(w, h, &path_results, &cfg))
+}
+
+/// 并行矢量化（结果版本）：与 `trace_rgba_with_options` 相同，额外返回尺寸与检测到的背景色
+#[wasm_bindgen]
+pub fn trace_rgba_to_result(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<TraceResult, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    check_rgba_len(rgba_data, width as usize, height as usize)?;
+    let background = if options.auto_background {
+        preprocess::detect_background(rgba_data, width as usize, height as usize)
+    } else {
+        None
+    };
+    
+    // 选项只转换一次；自动斑点阈值与颜色数在这里先估计出来，既用于追踪也用于返回
+    let mut cfg = options.to_config();
+    if cfg.auto_speckle || cfg.auto_color_count {
+        let color_image = visioncortex::ColorImage {
+            pixels: rgba_data.to_vec(),
+            width: width as usize,
+            height: height as usize,
+        };
+        if let Some(resolved) = resolve_config(&color_image, &cfg) {
+            cfg = resolved;
+        }
+    }
+    let filter_speckle = if options.auto_speckle { cfg.filter_speckle as u32 } else { options.filter_speckle };
+    let color_count = if options.auto_color_count { cfg.color_count } else { options.color_count };
+    if options.collect_convergence {
+        cfg.convergence = Some(Arc::default());
+    }
+    
+    let (svg, uncovered, convergence) = trace_rgba_inner(rgba_data, width, height, cfg, true)?;
+    
+    Ok(TraceResult {
+        has_transparency: background.is_some() || uncovered,
+        width,
+        height,
+        background: background.map(|c| format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)),
+        filter_speckle,
+        color_count: color_count as u32,
+        hash: options.content_hash.then(|| content_hash(svg.as_bytes())),
+        iteration_cap_reached: convergence.map(|c| c.capped > 0),
+        capped_paths: convergence.map(|c| c.capped as u32),
+        final_change: convergence.map(|c| c.max_change),
+        svg,
+    })
+}
+
+// ============================================================================
+// 缓存版本：保留拟合结果，按需以不同尺寸重新输出
+// ============================================================================
+
+/// 按 visioncortex 的规则格式化数字：保留 `precision` 位小数并去掉末尾的 0
+fn format_number(num: f64, precision: Option<u32>) -> String {
+    match precision {
+        None => format!("{}", num),
+        Some(0) => format!("{:.0}", num),
+        Some(p) => format!("{:.*}", p as usize, num)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned(),
+    }
+}
+
+/// 将路径中的所有坐标乘以 `scale`（整数路径会转为浮点路径）
+fn scale_compound_path(path: &CompoundPath, scale: f64) -> CompoundPath {
+    transform_compound_path(path, scale, PointF64::default())
+}
+
+/// 将路径中的所有坐标乘以 `scale` 后再平移 `offset`（整数路径会转为浮点路径）
+fn transform_compound_path(path: &CompoundPath, scale: f64, offset: PointF64) -> CompoundPath {
+    map_compound_path(path, |p| PointF64::new(p.x * scale + offset.x, p.y * scale + offset.y))
+}
+
+/// 对路径中的每个坐标 (含样条控制点) 应用 `f`（整数路径会转为浮点路径）
+fn map_compound_path(path: &CompoundPath, f: impl Fn(PointF64) -> PointF64) -> CompoundPath {
+    let scale_points = |points: &[PointF64]| -> Vec<PointF64> { points.iter().map(|&p| f(p)).collect() };
+    
+    let mut scaled = CompoundPath::new();
+    for element in path.iter() {
+        match element {
+            CompoundPathElement::PathI32(p) => {
+                scaled.add_path_f64(PathF64::from_points(scale_points(&p.to_path_f64().path)));
+            }
+            CompoundPathElement::PathF64(p) => {
+                scaled.add_path_f64(PathF64::from_points(scale_points(&p.path)));
+            }
+            CompoundPathElement::Spline(p) => {
+                scaled.add_spline(Spline { points: scale_points(&p.points) });
+            }
+        }
+    }
+    scaled
+}
+
+/// 全部路径的包围盒 (左上角, 右下角)，没有路径时为 `None`
+/// 
+/// 样条按采样点计算，比直接取控制点更贴合曲线。
+fn content_bounds(path_results: &[(CompoundPath, Color)]) -> Option<(PointF64, PointF64)> {
+    let (mut min, mut max) = (PointF64::new(f64::MAX, f64::MAX), PointF64::new(f64::MIN, f64::MIN));
+    for (compound_path, _) in path_results {
+        for element in compound_path.iter() {
+            for p in geometry::flatten_element(element, 8) {
+                min = PointF64::new(min.x.min(p.x), min.y.min(p.y));
+                max = PointF64::new(max.x.max(p.x), max.y.max(p.y));
+            }
+        }
+    }
+    (min.x <= max.x).then_some((min, max))
+}
+
+/// 把全部路径的包围盒等比缩放、居中放入 `target_width` x `target_height` 的画布
+/// 
+/// 返回变换后的路径与线宽同比缩放后的配置。没有路径或包围盒退化为零面积时不缩放，只把内容中心移到画布中心。
+fn normalize_paths(
+    path_results: &[(CompoundPath, Color)],
+    cfg: &TracerConfig,
+    target_width: f64,
+    target_height: f64,
+) -> (Vec<(CompoundPath, Color)>, TracerConfig) {
+    let Some((min, max)) = content_bounds(path_results) else {
+        return (Vec::new(), cfg.clone());
+    };
+    
+    let (box_width, box_height) = (max.x - min.x, max.y - min.y);
+    let scale = if box_width > 0.0 && box_height > 0.0 {
+        (target_width / box_width).min(target_height / box_height)
+    } else {
+        1.0
+    };
+    let offset = PointF64::new(
+        (target_width - box_width * scale) / 2.0 - min.x * scale,
+        (target_height - box_height * scale) / 2.0 - min.y * scale,
+    );
+    
+    let normalized = path_results
+        .iter()
+        .map(|(path, color)| (transform_compound_path(path, scale, offset), *color))
+        .collect();
+    let mut cfg = cfg.clone();
+    cfg.stroke_width = cfg.stroke_width.map(|w| w * scale);
+    (normalized, cfg)
+}
+
+/// 缓存一次并行追踪的拟合结果
+/// 
+/// 聚类与曲线拟合只在构造时执行一次，之后可以反复调用 `render` / `render_scaled`
+/// 以不同尺寸输出，无需重新追踪。
+#[wasm_bindgen]
+pub struct ClusterHandle {
+    width: usize,
+    height: usize,
+    cfg: TracerConfig,
+    paths: Vec<(CompoundPath, Color)>,
+    /// 聚类层次，供 `with_color_count` 剪枝
+    hierarchy: prune::Hierarchy,
+}
+
+#[wasm_bindgen]
+impl ClusterHandle {
+    /// 执行聚类与并行曲线拟合，参数含义与 `trace_rgba_parallel` 相同
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        rgba_data: &[u8],
+        width: u32,
+        height: u32,
+        color_count: u8,
+        path_precision: u8,
+        corner_threshold: u8,
+        filter_speckle: u32,
+    ) -> Result<ClusterHandle, JsValue> {
+        reset_rng();
+        let w = width as usize;
+        let h = height as usize;
+        
+        check_rgba_len(rgba_data, w, h)?;
+        
+        let cfg = build_config(color_count, path_precision, corner_threshold, filter_speckle);
+        let color_image = visioncortex::ColorImage {
+            pixels: rgba_data.to_vec(),
+            width: w,
+            height: h,
+        };
+        // 与 `cluster_and_fit` 相同的聚类与拟合，另外留下层次信息
+        let clusters = cluster_image(color_image, &cfg);
+        let view = clusters.view();
+        let (mut paths, _) = fit_clusters(&view, &cfg);
+        let mut hierarchy = prune::Hierarchy::new(&view);
+        let keep: Vec<bool> = paths.iter().map(|(compound_path, _)| geometry::is_finite(compound_path)).collect();
+        retain_by_mask(&mut paths, &keep);
+        hierarchy.retain(&keep);
+        
+        Ok(ClusterHandle { width: w, height: h, cfg, paths, hierarchy })
+    }
+    
+    /// 在已有的聚类层次上剪枝，得到较少颜色数的结果，不重新聚类也不重新拟合
+    /// 
+    /// 颜色数决定分层阈值 `layer_difference` (见 `color_count`)：与直接包含它的下层色块颜色差
+    /// 不超过新阈值的色块并入下层色块，下层色块改用合并后的平均色。叠放模式下下层色块的路径
+    /// 本来就覆盖其上的子色块，删去子色块即可，其余路径原样保留，因此几乎是瞬时的，适合拖动滑块。
+    /// 
+    /// `color_count` 的有效范围为 2 到构造时的颜色数 (截断到 2~64)：更多的颜色需要重新聚类，
+    /// 返回错误。分层阈值只在 2~8 色之间逐色变化，8~24 色每 3 色变化一次，24 色以上不再变化，
+    /// 相邻的颜色数可能得到相同的结果；阈值与构造时相同 (包括颜色数相同) 时直接返回原结果。判定依据的是最终的色块颜色，
+    /// 而聚类时比较的是合并过程中的邻居颜色，结果与直接以该颜色数追踪相近但不完全一致。
+    /// 返回新的句柄，原句柄不受影响，可以继续以其它颜色数剪枝。
+    pub fn with_color_count(&self, color_count: u8) -> Result<ClusterHandle, JsValue> {
+        let color_count = color_count.clamp(2, 64);
+        if color_count > self.cfg.color_count {
+            return Err(JsValue::from_str(&format!(
+                "color_count 不能超过构造时的 {}，更多颜色需要重新追踪",
+                self.cfg.color_count
+            )));
+        }
+        let mut cfg = self.cfg.clone();
+        cfg.color_count = color_count;
+        cfg.layer_difference = layer_difference_for(color_count);
+        let (paths, hierarchy) = if cfg.layer_difference == self.cfg.layer_difference {
+            (self.paths.clone(), self.hierarchy.clone())
+        } else {
+            self.hierarchy.prune(&self.paths, cfg.layer_difference, cfg.color_space)
+        };
+        Ok(ClusterHandle { width: self.width, height: self.height, cfg, paths, hierarchy })
+    }
+    
+    /// 以原始尺寸输出 SVG，结果与 `trace_rgba_parallel` 一致
+    pub fn render(&self) -> String {
+        build_svg(self.width, self.height, &self.paths, &self.cfg)
+    }
+    
+    /// 将所有坐标与 `viewBox` 乘以 `scale` 后输出（例如 2.0 生成 @2x 版本）
+    /// 
+    /// 直接缩放几何数据，比让浏览器缩放固定尺寸的 SVG 更清晰；
+    /// 坐标仍按 `path_precision` 取整，因此高倍率下精度不会丢失。
+    pub fn render_scaled(&self, scale: f64) -> Result<String, JsValue> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(JsValue::from_str(&format!("scale 必须为正数: {}", scale)));
+        }
+        
+        let scaled_paths: Vec<_> = self.paths
+            .iter()
+            .map(|(path, color)| (scale_compound_path(path, scale), *color))
+            .collect();
+        
+        // 描边线宽与坐标同比缩放，保持线条与形状的比例不变
+        let mut cfg = self.cfg.clone();
+        cfg.stroke_width = cfg.stroke_width.map(|w| w * scale);
+        
+        let mut svg = String::with_capacity(1024 * 64);
+        writeln!(
+            svg,
+            r#"<svg{} viewBox="0 0 {} {}">"#,
+            svg_root_attrs(&cfg, self.width as f64 * scale, self.height as f64 * scale),
+            format_number(self.width as f64 * scale, cfg.path_precision),
+            format_number(self.height as f64 * scale, cfg.path_precision),
+        ).ok();
+        svg.push_str(&svg_root_children(&cfg));
+        write_paths(&mut svg, &scaled_paths, &cfg, PointF64::default());
+        writeln!(svg, "</svg>").ok();
+        
+        Ok(svg)
+    }
+}
+
+// ============================================================================
+// 分块版本：逐块拉取像素，突破 WASM 内存上限
+// ============================================================================
+
+/// 相邻分块之间的重叠像素数，让块边缘的聚类能看到邻居上下文
+const TILE_OVERLAP: u32 = 8;
+
+/// 分块矢量化：通过回调按需拉取像素块，逐块追踪后拼接为一个 SVG
+/// 
+/// `get_tile(x, y, w, h)` 需返回该矩形区域的 RGBA 数据 (`Uint8Array`，长度 `w * h * 4`)。
+/// 每块会向四周多取 `TILE_OVERLAP` 像素一起聚类，输出时再用 `clipPath`
+/// 裁回本块的核心区域，因此任意时刻只有一个分块的像素驻留在内存中。
+/// 
+/// 已知限制：跨块的色块不会被合并，拼缝处可能出现淡淡的细线。
+#[wasm_bindgen]
+pub fn trace_tiled(
+    get_tile: &js_sys::Function,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    color_count: u8,
+    path_precision: u8,
+    corner_threshold: u8,
+    filter_speckle: u32,
+) -> Result<String, JsValue> {
+    if tile_size == 0 {
+        return Err(JsValue::from_str("tile_size 必须大于 0"));
+    }
+    
+    let cfg = build_config(color_count, path_precision, corner_threshold, filter_speckle);
+    
+    let mut svg = String::with_capacity(1024 * 64);
+    writeln!(svg, r#"<svg{} viewBox="0 0 {} {}">"#, svg_root_attrs(&cfg, width as f64, height as f64), width, height).ok();
+    svg.push_str(&svg_root_children(&cfg));
+    
+    let mut tile_index = 0;
+    for y0 in (0..height).step_by(tile_size as usize) {
+        for x0 in (0..width).step_by(tile_size as usize) {
+            // 本块核心区域
+            let core_w = tile_size.min(width - x0);
+            let core_h = tile_size.min(height - y0);
+            
+            // 带重叠的拉取区域 (裁剪到图像范围内)
+            let ex0 = x0.saturating_sub(TILE_OVERLAP);
+            let ey0 = y0.saturating_sub(TILE_OVERLAP);
+            let ex1 = (x0 + core_w + TILE_OVERLAP).min(width);
+            let ey1 = (y0 + core_h + TILE_OVERLAP).min(height);
+            let (ew, eh) = (ex1 - ex0, ey1 - ey0);
+            
+            let tile = get_tile
+                .call4(
+                    &JsValue::NULL,
+                    &JsValue::from(ex0),
+                    &JsValue::from(ey0),
+                    &JsValue::from(ew),
+                    &JsValue::from(eh),
+                )?
+                .dyn_into::<js_sys::Uint8Array>()
+                .map_err(|_| JsValue::from_str("get_tile 必须返回 Uint8Array"))?
+                .to_vec();
+            check_rgba_len(&tile, ew as usize, eh as usize)?;
+            
+            // 每块使用相同的种子，保证分块结果与调用顺序无关
+            reset_rng();
+            let color_image = visioncortex::ColorImage {
+                pixels: tile,
+                width: ew as usize,
+                height: eh as usize,
+            };
+            let path_results = cluster_and_fit(color_image, &cfg);
+            
+            writeln!(
+                svg,
+                r#"<clipPath id="snapsvg-tile-{}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+                tile_index, x0, y0, core_w, core_h
+            ).ok();
+            writeln!(svg, r#"<g clip-path="url(#snapsvg-tile-{})">"#, tile_index).ok();
+            write_paths(
+                &mut svg,
+                &path_results,
+                &cfg,
+                PointF64 { x: ex0 as f64, y: ey0 as f64 },
+            );
+            writeln!(svg, "</g>").ok();
+            
+            tile_index += 1;
+        }
+    }
+    
+    writeln!(svg, "</svg>").ok();
+    
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commands, coverage, fill_rect, fills, numbers, path_data, rasterize, rings, shapes, solid, transforms};
+
+    #[test]
+    fn corner_angle_maps_to_turning_threshold() {
+        assert_eq!(corner_threshold_from_angle(90.0), 90);
+        assert_eq!(corner_threshold_from_angle(120.0), 60);
+        assert_eq!(corner_threshold_from_angle(150.0), 30);
+
+        let mut options = TraceOptions::new();
+        options.corner_threshold = 10;
+        options.corner_angle_degrees = Some(120.0);
+        assert_eq!(options.to_config().corner_threshold, 60);
+    }
+
+    #[test]
+    fn transparent_pixels_form_no_layer_on_legacy_entry_points() {
+        // 左半透明，残留的 RGB 为黑色 (常见导出工具的做法)；右半为不透明的红色
+        let (w, h) = (40, 20);
+        let mut pixels = solid(w, h, [0, 0, 0, 0]);
+        fill_rect(&mut pixels, w, (w / 2, 0), (w, h), [255, 0, 0, 255]);
+
+        let svg = trace_rgba_parallel(&pixels, w as u32, h as u32, 8, 50, 60, 4).unwrap();
+        let colors = fills(&svg);
+        assert!(!colors.is_empty());
+        assert!(colors.iter().all(|&c| c == "#ff0000"), "{:?}", colors);
+
+        let cfg = build_config(8, 50, 60, 4);
+        let path_results = cluster_and_fit(ColorImage { pixels, width: w, height: h }, &cfg);
+        let (min, _) = content_bounds(&path_results).unwrap();
+        assert!(min.x >= (w / 2) as f64 - 1e-6, "{:?}", min.x);
+    }
+
+    /// 路径数据中零长度的段数：与当前点重合的 `L` 终点，或端点、控制点全部与当前点重合的 `C`
+    fn zero_length_segments(d: &str) -> usize {
+        let mut count = 0;
+        let mut current = (f64::NAN, f64::NAN);
+        for (command, args) in commands(d) {
+            match command {
+                'M' | 'L' => {
+                    for (k, p) in args.chunks_exact(2).enumerate() {
+                        count += (command == 'L' || k > 0) as usize * ((p[0], p[1]) == current) as usize;
+                        current = (p[0], p[1]);
+                    }
+                }
+                'C' => {
+                    for p in args.chunks_exact(6) {
+                        count += p.chunks_exact(2).all(|q| (q[0], q[1]) == current) as usize;
+                        current = (p[4], p[5]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn clean_commands_removes_zero_length_segments_without_changing_the_raster() {
+        // 起伏的彩色图案缩小 10 倍输出，取整到整数后会出现大量重合的坐标
+        let (w, h) = (160, 120);
+        let mut pixels = Vec::with_capacity(w * h * 4);
+        for y in 0..h {
+            for x in 0..w {
+                let (fx, fy) = (x as f64 - 80.0, y as f64 - 60.0);
+                pixels.extend([
+                    ((fx * 0.07).sin() * 120.0 + 128.0) as u8,
+                    ((fy * 0.05 + fx * 0.02).cos() * 120.0 + 128.0) as u8,
+                    ((fx * fy * 0.001).sin() * 120.0 + 128.0) as u8,
+                    255,
+                ]);
+            }
+        }
+        let trace = |clean: bool| {
+            let mut options = TraceOptions::new();
+            options.coordinate_precision = 0;
+            options.normalize_width = Some(16);
+            options.normalize_height = Some(12);
+            options.clean_commands = clean;
+            trace_rgba_with_options(&pixels, w as u32, h as u32, &options).unwrap()
+        };
+        let (raw, cleaned) = (trace(false), trace(true));
+
+        let count = |svg: &str| path_data(svg).iter().map(|d| zero_length_segments(d)).sum::<usize>();
+        assert!(count(&raw) > 0, "夹具应当产生零长度的段");
+        assert_eq!(count(&cleaned), 0);
+        assert!(cleaned.len() < raw.len());
+        assert_eq!(rasterize(&raw, 16, 12), rasterize(&cleaned, 16, 12));
+        assert!(!build_config(8, 50, 60, 4).clean_commands);
+    }
+
+    #[test]
+    fn parallel_trace_is_byte_identical_across_runs() {
+        let pixels = shapes(80, 60);
+        let first = trace_rgba_parallel(&pixels, 80, 60, 8, 8, 60, 4).unwrap();
+        assert!(!path_data(&first).is_empty());
+        for _ in 0..20 {
+            assert_eq!(trace_rgba_parallel(&pixels, 80, 60, 8, 8, 60, 4).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn back_to_back_traces_match_standalone_runs() {
+        // 带透明区域，走 vtracer 随机选取键控色的路径
+        let mut first = shapes(60, 40);
+        fill_rect(&mut first, 60, (0, 0), (10, 40), [0, 0, 0, 0]);
+        let mut second = solid(50, 50, [250, 240, 200, 255]);
+        fill_rect(&mut second, 50, (5, 5), (30, 45), [0, 0, 0, 0]);
+        fill_rect(&mut second, 50, (20, 10), (45, 25), [10, 130, 60, 255]);
+
+        let trace = |pixels: &[u8], w: u32, h: u32| trace_rgba_with_options(pixels, w, h, &TraceOptions::new()).unwrap();
+        // 在新线程上单独追踪，不受本线程先前状态影响
+        let standalone = |pixels: Vec<u8>, w: u32, h: u32| std::thread::spawn(move || trace(&pixels, w, h)).join().unwrap();
+        let expected_first = standalone(first.clone(), 60, 40);
+        let expected_second = standalone(second.clone(), 50, 50);
+
+        assert_eq!(trace(&first, 60, 40), expected_first);
+        assert_eq!(trace(&second, 50, 50), expected_second);
+        assert_eq!(trace(&first, 60, 40), expected_first);
+    }
+
+    /// 由点序列构成的单段样条子路径
+    fn spline_path(points: &[(f64, f64)]) -> CompoundPath {
+        let mut spline = visioncortex::Spline::new(PointF64::new(points[0].0, points[0].1));
+        for segment in points[1..].chunks_exact(3) {
+            let [a, b, c] = [segment[0], segment[1], segment[2]].map(|(x, y)| PointF64::new(x, y));
+            spline.add(a, b, c);
+        }
+        let mut path = CompoundPath::new();
+        path.add_spline(spline);
+        path
+    }
+
+    #[test]
+    fn non_finite_coordinates_never_reach_the_output() {
+        let square = spline_path(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0)]);
+        let degenerate = spline_path(&[(1.0, 1.0), (f64::NAN, 2.0), (3.0, f64::INFINITY), (1.0, 1.0)]);
+        let paths = vec![(square, Color::new(200, 0, 0)), (degenerate, Color::new(0, 0, 200))];
+        let sources = (0..2).map(|_| Source { rect: BoundingRect::default(), texture: None }).collect();
+        let cfg = build_config(8, 8, 60, 4);
+        let (paths, _) = finish_paths(paths, sources, &cfg);
+        let svg = build_svg(8, 8, &paths, &cfg);
+        assert_eq!(fills(&svg), ["#c80000"]);
+
+        // 退化的输入图像：单像素、单行、单列
+        let mut documents = vec![svg];
+        for (w, h) in [(1, 1), (40, 1), (1, 40)] {
+            let pixels = solid(w, h, [90, 30, 160, 255]);
+            documents.push(trace_rgba_with_options(&pixels, w as u32, h as u32, &TraceOptions::new()).unwrap());
+        }
+        for svg in &documents {
+            assert!(!svg.contains("NaN") && !svg.contains("inf"), "{}", svg);
+            assert!(validate_svg(svg).is_ok(), "{}", svg);
+        }
+    }
+
+    #[test]
+    fn title_and_desc_are_escaped() {
+        assert_eq!(escape_xml("a\u{1}b\u{ffff}\tc"), "ab\tc");
+
+        let mut options = TraceOptions::new();
+        options.title = Some("<script>alert(1)</script> & \"x\"".to_owned());
+        options.desc = Some("Tom & Jerry\u{0}\u{8}<b>".to_owned());
+        let svg = trace_rgba_with_options(&shapes(30, 20), 30, 20, &options).unwrap();
+
+        assert!(svg.contains("<title id=\"snapsvg-title\">&lt;script&gt;alert(1)&lt;/script&gt; &amp; &quot;x&quot;</title>"), "{}", svg);
+        assert!(svg.contains("<desc id=\"snapsvg-desc\">Tom &amp; Jerry&lt;b&gt;</desc>"), "{}", svg);
+        assert!(!svg.contains("<script") && !svg.contains('\u{0}'));
+        assert!(validate_svg(&svg).is_ok(), "{}", svg);
+    }
+
+    #[test]
+    fn numbers_use_a_decimal_point_regardless_of_locale() {
+        // 不分组、不使用小数逗号
+        assert_eq!(format_number(-1234.5, Some(2)), "-1234.5");
+        assert_eq!(format_fixed(1234.5, Some(2)), "1234.50");
+
+        let pixels = shapes(60, 40);
+        let mut options = TraceOptions::new();
+        for space_separated in [false, true] {
+            options.space_separated_translate = space_separated;
+            let svg = trace_rgba_with_options(&pixels, 60, 40, &options).unwrap();
+            for d in path_data(&svg) {
+                assert!(!d.contains(','), "{}", d);
+            }
+            let translates = transforms(&svg);
+            assert!(!translates.is_empty());
+            for translate in translates {
+                let args = translate.strip_prefix("translate(").and_then(|t| t.strip_suffix(')')).unwrap();
+                let separator = if space_separated { ' ' } else { ',' };
+                let parts: Vec<&str> = args.split(separator).collect();
+                assert_eq!(parts.len(), 2, "{}", translate);
+                assert!(parts.iter().all(|p| p.parse::<f64>().is_ok()), "{}", translate);
+            }
+        }
+    }
+
+    #[test]
+    fn protect_dark_keeps_the_dot_over_an_i_but_not_light_noise() {
+        // 文档片段：字母 i (竖画与 3×3 的点)、一个粗竖画、一个 3×3 的浅灰噪点
+        let mut page = solid(80, 40, [255, 255, 255, 255]);
+        fill_rect(&mut page, 80, (10, 14), (13, 30), [20, 20, 20, 255]);
+        fill_rect(&mut page, 80, (10, 8), (13, 11), [20, 20, 20, 255]);
+        fill_rect(&mut page, 80, (20, 14), (32, 30), [20, 20, 20, 255]);
+        fill_rect(&mut page, 80, (50, 10), (53, 13), [200, 200, 200, 255]);
+        let mut options = TraceOptions::new();
+        options.filter_speckle = 12;
+
+        let svg = trace_rgba_with_options(&page, 80, 40, &options).unwrap();
+        assert_eq!(fills(&svg), ["#fefefe", "#141414", "#141414"]);
+
+        options.protect_dark = true;
+        let svg = trace_rgba_with_options(&page, 80, 40, &options).unwrap();
+        assert_eq!(fills(&svg), ["#fefefe", "#141414", "#141414", "#141414"]);
+        assert!(transforms(&svg).contains(&"translate(10.00,8.00)"), "{}", svg);
+    }
+
+    #[test]
+    fn physical_units_follow_dpi() {
+        let pixels = solid(600, 300, [10, 200, 90, 255]);
+        let root = |units: &str, dpi: f64| {
+            let mut options = TraceOptions::new();
+            options.units = units.to_owned();
+            options.dpi = dpi;
+            let svg = trace_rgba_with_options(&pixels, 600, 300, &options).unwrap();
+            svg.lines().next().unwrap().to_owned()
+        };
+        assert_eq!(root("mm", 300.0), r#"<svg xmlns="http://www.w3.org/2000/svg" width="50.8mm" height="25.4mm" viewBox="0 0 600 300">"#);
+        assert!(root("pt", 300.0).contains(r#" width="144pt" height="72pt" "#));
+        // 未设置 dpi 时按 96
+        assert!(root("pt", 0.0).contains(r#" width="450pt" height="225pt" "#));
+        assert!(root("px", 300.0).contains(r#" width="600px" height="300px" "#));
+        assert!(!root("", 300.0).contains(" width="));
+    }
+
+    #[test]
+    fn overlap_closes_antialiasing_seams_between_neighbours() {
+        // 白底上两块浅色互为兄弟色块，沿阶梯状斜线相接
+        let mut pixels = solid(40, 40, [255, 255, 255, 255]);
+        fill_rect(&mut pixels, 40, (4, 4), (36, 36), [250, 180, 180, 255]);
+        for y in 4..36 {
+            fill_rect(&mut pixels, 40, ((60 - y) / 2 + (y % 2), y), (36, y + 1), [180, 250, 250, 255]);
+        }
+        // 渲染器逐条按覆盖率混合时，底层白色从交界处透出的最大比例
+        let seam = |overlap: f64| {
+            let mut options = TraceOptions::new();
+            options.overlap = overlap;
+            let svg = trace_rgba_with_options(&pixels, 40, 40, &options).unwrap();
+            assert_eq!(fills(&svg), ["#ffffff", "#fab4b4", "#b4fafa"]);
+            let layers = coverage(&svg, 40, 40, 4);
+            (6..34)
+                .flat_map(|y| (6..34).map(move |x| y * 40 + x))
+                .map(|i| layers[1..].iter().map(|layer| 1.0 - layer[i]).product::<f64>())
+                .fold(0.0, f64::max)
+        };
+        assert!(seam(0.0) > 0.1);
+        assert_eq!(seam(0.5), 0.0);
+    }
+
+    #[test]
+    fn batch_size_does_not_change_the_output() {
+        let pixels = shapes(80, 60);
+        let trace = |batch_size: usize| {
+            let mut options = TraceOptions::new();
+            options.batch_size = batch_size;
+            trace_rgba_with_options(&pixels, 80, 60, &options).unwrap()
+        };
+        let expected = trace(25600);
+        for batch_size in [1, 7, 256, 4800, 1 << 20] {
+            assert_eq!(trace(batch_size), expected, "batch_size = {}", batch_size);
+        }
+    }
+
+    #[test]
+    fn diagonal_line_stays_one_path_with_diagonal_connectivity() {
+        let mut pixels = solid(24, 24, [255, 255, 255, 255]);
+        for i in 2..22 {
+            fill_rect(&mut pixels, 24, (i, i), (i + 1, i + 1), [0, 0, 0, 255]);
+        }
+        let mut options = TraceOptions::new();
+        options.filter_speckle = 0;
+
+        // 四连通时斜线断成孤立的单个像素，拟合后都被丢弃
+        let svg = trace_rgba_with_options(&pixels, 24, 24, &options).unwrap();
+        assert_eq!(fills(&svg), ["#ffffff"]);
+
+        options.diagonal = true;
+        let svg = trace_rgba_with_options(&pixels, 24, 24, &options).unwrap();
+        assert_eq!(fills(&svg), ["#ffffff", "#000000"]);
+        // 一条路径贯穿整条斜线
+        let (d, offset) = (path_data(&svg)[1], numbers(transforms(&svg)[1]));
+        let points = rings(d, (offset[0], offset[1])).concat();
+        let extent = |f: fn(&(f64, f64)) -> f64| points.iter().map(f).fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        assert_eq!((extent(|p| p.0), extent(|p| p.1)), ((2.0, 22.0), (2.0, 22.0)));
+    }
+
+    /// 透明底上的椭圆，拟合后的包围盒带小数
+    fn ellipse_on_transparent(width: usize, height: usize) -> Vec<u8> {
+        let mut pixels = solid(width, height, [0, 0, 0, 0]);
+        let (rx, ry) = (width as f64 * 0.31, height as f64 * 0.27);
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = ((x as f64 + 0.5 - width as f64 * 0.47) / rx, (y as f64 + 0.5 - height as f64 * 0.53) / ry);
+                if dx * dx + dy * dy < 1.0 {
+                    pixels[(y * width + x) * 4..(y * width + x) * 4 + 4].copy_from_slice(&[0, 150, 90, 255]);
+                }
+            }
+        }
+        pixels
+    }
+
+    fn view_box(svg: &str) -> Vec<f64> {
+        numbers(svg.split("viewBox=\"").nth(1).and_then(|rest| rest.split('"').next()).unwrap())
+    }
+
+    #[test]
+    fn snapped_crop_has_an_integral_view_box_containing_all_content() {
+        let pixels = ellipse_on_transparent(50, 40);
+        let mut options = TraceOptions::new();
+        options.crop_to_content = true;
+        let svg = trace_rgba_with_options(&pixels, 50, 40, &options).unwrap();
+        assert!(view_box(&svg).iter().any(|v| v.fract() != 0.0), "{}", svg);
+
+        options.snap_viewbox_to_int = true;
+        let svg = trace_rgba_with_options(&pixels, 50, 40, &options).unwrap();
+        let view = view_box(&svg);
+        assert!(view.iter().all(|v| v.fract() == 0.0), "{:?}", view);
+        for (d, transform) in path_data(&svg).into_iter().zip(transforms(&svg)) {
+            let offset = numbers(transform);
+            for (x, y) in rings(d, (offset[0], offset[1])).concat() {
+                let inside = |v: f64, min: f64, size: f64| v >= min - 1e-9 && v <= min + size + 1e-9;
+                assert!(inside(x, view[0], view[2]) && inside(y, view[1], view[3]), "({}, {}) outside {:?}", x, y, view);
+            }
+        }
+    }
+
+    #[test]
+    fn hierarchy_max_area_flattens_a_gradient_into_fewer_paths() {
+        let (w, h) = (64, 48);
+        let pixels: Vec<u8> = (0..w * h).flat_map(|i| {
+            let (x, y) = (i % w, i / w);
+            [(x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8, 255]
+        }).collect();
+        let path_count = |max_area: Option<u32>| {
+            let mut options = TraceOptions::new();
+            options.hierarchy_max_area = max_area;
+            fills(&trace_rgba_with_options(&pixels, w as u32, h as u32, &options).unwrap()).len()
+        };
+
+        let stacked = path_count(None);
+        let counts: Vec<usize> = [30, 300, 3000].map(|area| path_count(Some(area))).to_vec();
+        assert!(counts[0] <= stacked, "{} {:?}", stacked, counts);
+        assert!(counts[1] < counts[0] && counts[2] < counts[1], "{:?}", counts);
+        assert_eq!(counts[2], 1);
+    }
+
+    #[test]
+    fn min_points_per_path_drops_degenerate_paths() {
+        let polyline = |points: &[(f64, f64)]| {
+            let mut path = CompoundPath::new();
+            path.add_path_f64(PathF64::from_points(points.iter().map(|&(x, y)| PointF64::new(x, y)).collect()));
+            path
+        };
+        let paths = vec![
+            (polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)]), Color::new(200, 0, 0)),
+            (polyline(&[(1.0, 1.0), (6.0, 1.0)]), Color::new(0, 200, 0)),
+            (polyline(&[(2.0, 2.0), (7.0, 2.0), (2.0, 2.0)]), Color::new(0, 0, 200)),
+            (spline_path(&[(0.0, 0.0), (3.0, 0.0), (3.0, 3.0), (0.0, 0.0)]), Color::new(90, 90, 90)),
+        ];
+        let sources = || (0..4).map(|_| Source { rect: BoundingRect::default(), texture: None }).collect();
+        let mut cfg = build_config(8, 8, 60, 4);
+
+        let (kept, _) = finish_paths(paths.clone(), sources(), &cfg);
+        assert_eq!(kept.len(), 4);
+
+        cfg.min_points_per_path = 4;
+        let ((kept, kept_sources), warnings) = crate::warnings::capture(|| finish_paths(paths, sources(), &cfg));
+        let colors: Vec<Color> = kept.iter().map(|(_, color)| *color).collect();
+        assert_eq!(colors, [Color::new(200, 0, 0), Color::new(90, 90, 90)]);
+        assert_eq!(kept_sources.len(), 2);
+        assert!(warnings.iter().any(|w| w.starts_with("dropped: 2 ")), "{:?}", warnings);
+    }
+
+    /// 所有路径 (平移后) 的点
+    fn absolute_points(svg: &str) -> Vec<(f64, f64)> {
+        path_data(svg)
+            .into_iter()
+            .zip(transforms(svg))
+            .flat_map(|(d, transform)| {
+                let offset = numbers(transform);
+                rings(d, (offset[0], offset[1])).concat()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn center_origin_puts_the_content_center_at_zero() {
+        let pixels = ellipse_on_transparent(50, 40);
+        let mut options = TraceOptions::new();
+        options.origin = "center".to_owned();
+        for crop_to_content in [false, true] {
+            options.crop_to_content = crop_to_content;
+            let svg = trace_rgba_with_options(&pixels, 50, 40, &options).unwrap();
+            let points = absolute_points(&svg);
+            let mid = |f: fn(&(f64, f64)) -> f64| {
+                let (lo, hi) = points.iter().map(f).fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+                (lo + hi) / 2.0
+            };
+            // 包围盒按展平后的曲线计算，与这里的展平精度略有差异
+            assert!(mid(|p| p.0).abs() < 0.05 && mid(|p| p.1).abs() < 0.05, "{} {}", mid(|p| p.0), mid(|p| p.1));
+            let view = view_box(&svg);
+            assert!(view[0] < 0.0 && view[1] < 0.0, "{:?}", view);
+            if crop_to_content {
+                assert!((view[0] + view[2] / 2.0).abs() < 0.01 && (view[1] + view[3] / 2.0).abs() < 0.01, "{:?}", view);
+            } else {
+                assert_eq!(&view[2..], [50.0, 40.0]);
+            }
+        }
+    }
+
+    #[test]
+    fn thin_lines_are_widened_to_min_stroke_width() {
+        // 1 像素宽的横线、竖线与斜线 (斜线需要斜向连通)
+        let mut pixels = solid(40, 30, [255, 255, 255, 255]);
+        fill_rect(&mut pixels, 40, (4, 5), (36, 6), [0, 0, 0, 255]);
+        fill_rect(&mut pixels, 40, (10, 10), (11, 26), [0, 0, 0, 255]);
+        for i in 0..14 {
+            fill_rect(&mut pixels, 40, (16 + i, 12 + i), (17 + i, 13 + i), [0, 0, 0, 255]);
+        }
+        let mut options = TraceOptions::new();
+        options.filter_speckle = 0;
+        options.diagonal = true;
+        // 每条深色路径的平均宽度 `2 * 面积 / 周长`
+        let widths = |options: &TraceOptions| -> Vec<f64> {
+            let svg = trace_rgba_with_options(&pixels, 40, 30, options).unwrap();
+            svg.lines()
+                .filter(|line| line.contains("fill=\"#000000\""))
+                .map(|line| {
+                    let d = line.split(" d=\"").nth(1).and_then(|rest| rest.split('"').next()).unwrap();
+                    let ring: Vec<PointF64> = rings(d, (0.0, 0.0)).concat().into_iter().map(|(x, y)| PointF64::new(x, y)).collect();
+                    let perimeter: f64 = (0..ring.len()).map(|i| ring[i].distance_to(ring[(i + 1) % ring.len()])).sum();
+                    2.0 * geometry::polygon_area(&ring).abs() / perimeter
+                })
+                .collect()
+        };
+
+        let thin = widths(&options);
+        assert_eq!(thin.len(), 3);
+        assert!(thin.iter().all(|&w| w < 1.5), "{:?}", thin);
+
+        options.min_stroke_width = 2.0;
+        let widened = widths(&options);
+        assert_eq!(widened.len(), 3);
+        // 外扩按平均宽度一次估算，斜线的锯齿轮廓外扩后周长也变长，略低于目标值
+        assert!(widened.iter().zip(&thin).all(|(&w, &t)| w > t && (1.6..2.2).contains(&w)), "{:?}", widened);
+    }
+
+    /// 指定颜色的路径在画布坐标中的包围盒 `(min_x, min_y, max_x, max_y)`
+    fn fill_bounds(svg: &str, fill: &str) -> (f64, f64, f64, f64) {
+        let line = svg.lines().find(|line| line.contains(&format!("fill=\"{}\"", fill))).unwrap();
+        let points = absolute_points(line);
+        points.iter().fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |(x0, y0, x1, y1), &(x, y)| {
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        })
+    }
+
+    #[test]
+    fn trap_width_spreads_lower_layers_under_their_neighbours() {
+        // 白底上左右相接的两块浅色，都是白色的子色块，粉色最后绘制
+        let mut pixels = solid(40, 40, [255, 255, 255, 255]);
+        fill_rect(&mut pixels, 40, (4, 4), (20, 36), [250, 180, 180, 255]);
+        fill_rect(&mut pixels, 40, (20, 4), (36, 36), [180, 250, 250, 255]);
+        let mut options = TraceOptions::new();
+        let flat = trace_rgba_with_options(&pixels, 40, 40, &options).unwrap();
+        options.trap_width = 1.5;
+        let trapped = trace_rgba_with_options(&pixels, 40, 40, &options).unwrap();
+        assert_eq!(fills(&trapped), ["#ffffff", "#b4fafa", "#fab4b4"]);
+
+        // 下层的青色伸到粉色下方 1.5 像素，最上层的粉色保持原状
+        let (cyan, pink) = (fill_bounds(&trapped, "#b4fafa"), fill_bounds(&trapped, "#fab4b4"));
+        assert_eq!(fill_bounds(&flat, "#b4fafa").0, 20.0);
+        assert!((pink.2 - cyan.0 - 1.5).abs() < 0.1, "{:?} {:?}", pink, cyan);
+        assert_eq!(pink, fill_bounds(&flat, "#fab4b4"));
+        // 伸到粉色下方的部分被遮住，粉色区域的外观不变
+        let (before, after) = (rasterize(&flat, 40, 40), rasterize(&trapped, 40, 40));
+        let pink_pixels: Vec<usize> = (0..1600).filter(|&i| before[i].as_deref() == Some("#fab4b4")).collect();
+        assert!(!pink_pixels.is_empty() && pink_pixels.iter().all(|&i| after[i] == before[i]));
+    }
+
+    #[test]
+    fn normalized_coordinates_stay_within_the_unit_view_box() {
+        let pixels = shapes(60, 40);
+        let mut options = TraceOptions::new();
+        options.normalize_coords = true;
+        let svg = trace_rgba_with_options(&pixels, 60, 40, &options).unwrap();
+        assert_eq!(view_box(&svg), [0.0, 0.0, 1.0, 1.0]);
+
+        let points = absolute_points(&svg);
+        assert!(!points.is_empty());
+        for &(x, y) in &points {
+            assert!((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y), "({}, {})", x, y);
+        }
+        // 白色背景铺满整张画布，横纵都按各自的边长归一化
+        let max_x = points.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+        let max_y = points.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+        assert!(max_x > 0.99 && max_y > 0.99, "{} {}", max_x, max_y);
+    }
+
+    #[test]
+    fn fewer_colors_never_add_layers() {
+        // 层层嵌套的方块，每层与外层只差红色通道，差值由外向内从 5 增大到 15
+        let mut pixels = solid(64, 64, [100, 100, 100, 255]);
+        let mut red = 100;
+        for (i, step) in [5u8, 7, 9, 11, 13, 15].into_iter().enumerate() {
+            red += step;
+            let inset = 4 + 5 * i;
+            fill_rect(&mut pixels, 64, (inset, inset), (64 - inset, 64 - inset), [red, 100, 100, 255]);
+        }
+        let handle = ClusterHandle::new(&pixels, 64, 64, 64, 2, 60, 4).unwrap();
+        let counts: Vec<usize> = (2..=64)
+            .rev()
+            .map(|color_count| handle.with_color_count(color_count).unwrap().render().matches("<path").count())
+            .collect();
+        assert!(counts.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", counts);
+        assert!(counts[0] >= 5 && counts[counts.len() - 1] <= 3, "{:?}", counts);
+    }
 }
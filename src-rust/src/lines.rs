@@ -0,0 +1,215 @@
+//! 线稿提取：只描出图像中梯度明显的轮廓线，以描边路径输出
+//!
+//! 算法分四步：
+//! 1. 把像素 (先与白色背景合成) 转为亮度，用 Sobel 算子求梯度幅值，归一化到 0~255 左右；
+//! 2. 幅值不低于阈值的像素构成边缘图；
+//! 3. Zhang-Suen 细化把边缘图收缩为 1 像素宽的骨架；
+//! 4. 沿骨架从端点/分叉点出发走出像素链 (剩余的闭环单独处理)，
+//!    用 Douglas-Peucker 简化后写为折线。
+//!
+//! 与 `binary` 色彩模式不同：后者按亮度二值化后描出色块轮廓，这里按亮度的变化率取线条，
+//! 亮度均匀的大色块无论深浅都不会产生线条。
+
+use std::fmt::Write;
+use visioncortex::{Color, PointF64};
+
+use crate::geometry::simplify;
+use crate::{format_number, paint_attributes, svg_root_attrs, svg_root_children, TracerConfig};
+
+/// 线条简化的最大允许偏差 (像素)
+const SIMPLIFY_TOLERANCE: f64 = 0.75;
+
+/// 8 邻域偏移，按顺时针排列 (从正上方开始)，Zhang-Suen 依赖这一顺序
+const NEIGHBOURS: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// 与白色背景合成后的亮度
+fn luma_map(pixels: &[u8]) -> Vec<f64> {
+    pixels
+        .chunks_exact(4)
+        .map(|px| {
+            let alpha = px[3] as f64 / 255.0;
+            let luma = 0.299 * px[0] as f64 + 0.587 * px[1] as f64 + 0.114 * px[2] as f64;
+            luma * alpha + 255.0 * (1.0 - alpha)
+        })
+        .collect()
+}
+
+/// Sobel 梯度幅值 (除以 4 归一化，纯黑/纯白的阶跃边缘约为 255)，超过阈值的像素记为边缘
+fn edge_map(luma: &[f64], width: usize, height: usize, threshold: f64) -> Vec<bool> {
+    let at = |x: usize, y: usize, dx: isize, dy: isize| {
+        let sx = (x as isize + dx).clamp(0, width as isize - 1) as usize;
+        let sy = (y as isize + dy).clamp(0, height as isize - 1) as usize;
+        luma[sy * width + sx]
+    };
+    let mut edges = vec![false; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let gx = at(x, y, 1, -1) + 2.0 * at(x, y, 1, 0) + at(x, y, 1, 1)
+                - at(x, y, -1, -1) - 2.0 * at(x, y, -1, 0) - at(x, y, -1, 1);
+            let gy = at(x, y, -1, 1) + 2.0 * at(x, y, 0, 1) + at(x, y, 1, 1)
+                - at(x, y, -1, -1) - 2.0 * at(x, y, 0, -1) - at(x, y, 1, -1);
+            edges[y * width + x] = (gx * gx + gy * gy).sqrt() / 4.0 >= threshold;
+        }
+    }
+    edges
+}
+
+/// 读取 `(x, y)` 的 8 个邻居 (越界视为背景)
+fn neighbours(map: &[bool], width: usize, height: usize, x: usize, y: usize) -> [bool; 8] {
+    NEIGHBOURS.map(|(dx, dy)| {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+        nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height && map[ny as usize * width + nx as usize]
+    })
+}
+
+/// Zhang-Suen 细化：交替执行两个子迭代剥离边界像素，直到没有像素可删
+fn thin(map: &mut [bool], width: usize, height: usize) {
+    let mut removal = Vec::new();
+    loop {
+        let mut changed = false;
+        for step in 0..2 {
+            removal.clear();
+            for y in 0..height {
+                for x in 0..width {
+                    if !map[y * width + x] {
+                        continue;
+                    }
+                    // p[0]..p[7] 依次为 P2..P9
+                    let p = neighbours(map, width, height, x, y);
+                    let count = p.iter().filter(|&&v| v).count();
+                    let transitions = (0..8).filter(|&i| !p[i] && p[(i + 1) % 8]).count();
+                    let (a, b) = if step == 0 {
+                        (p[0] && p[2] && p[4], p[2] && p[4] && p[6])
+                    } else {
+                        (p[0] && p[2] && p[6], p[0] && p[4] && p[6])
+                    };
+                    if (2..=6).contains(&count) && transitions == 1 && !a && !b {
+                        removal.push(y * width + x);
+                    }
+                }
+            }
+            changed |= !removal.is_empty();
+            for &i in &removal {
+                map[i] = false;
+            }
+        }
+        if !changed {
+            return;
+        }
+    }
+}
+
+/// 骨架中 `i` 的所有邻居下标
+///
+/// 采用混合邻接 (m-adjacency)：斜向邻居只有在两侧的横竖邻居都为空时才计入，
+/// 否则阶梯状的拐角会多出一个邻居，被误判为分叉点而把线条切碎。
+fn skeleton_neighbours(map: &[bool], width: usize, height: usize, i: usize) -> Vec<usize> {
+    let (x, y) = (i % width, i / width);
+    let p = neighbours(map, width, height, x, y);
+    (0..8)
+        .filter(|&k| p[k] && (k % 2 == 0 || (!p[k - 1] && !p[(k + 1) % 8])))
+        .map(|k| {
+            let (dx, dy) = NEIGHBOURS[k];
+            (y as isize + dy) as usize * width + (x as isize + dx) as usize
+        })
+        .collect()
+}
+
+/// 把骨架拆成像素链：链的两端为端点或分叉点，中间像素恰有两个邻居
+fn trace_chains(map: &[bool], width: usize, height: usize) -> Vec<Vec<usize>> {
+    let degree = |i: usize| skeleton_neighbours(map, width, height, i).len();
+    let is_node = |i: usize| degree(i) != 2;
+
+    let mut visited = vec![false; map.len()];
+    let mut chains = Vec::new();
+
+    let walk = |start: usize, first: usize, visited: &mut Vec<bool>| {
+        let mut chain = vec![start, first];
+        let (mut prev, mut cur) = (start, first);
+        while !is_node(cur) {
+            visited[cur] = true;
+            let next = skeleton_neighbours(map, width, height, cur)
+                .into_iter()
+                .find(|&n| n != prev && (is_node(n) || !visited[n] || n == start));
+            match next {
+                Some(next) => {
+                    chain.push(next);
+                    prev = cur;
+                    cur = next;
+                    if cur == start {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        chain
+    };
+
+    // 从端点与分叉点出发
+    for i in (0..map.len()).filter(|&i| map[i] && is_node(i)) {
+        for n in skeleton_neighbours(map, width, height, i) {
+            if is_node(n) {
+                // 两个相邻节点之间的短链只记录一次
+                if i < n {
+                    chains.push(vec![i, n]);
+                }
+            } else if !visited[n] {
+                chains.push(walk(i, n, &mut visited));
+            }
+        }
+    }
+
+    // 剩余未访问的像素都在不含节点的闭环上
+    for i in 0..map.len() {
+        if map[i] && !visited[i] {
+            visited[i] = true;
+            if let Some(&first) = skeleton_neighbours(map, width, height, i).first() {
+                chains.push(walk(i, first, &mut visited));
+            }
+        }
+    }
+
+    chains
+}
+
+/// 提取线稿并输出 SVG：所有线条合并为一条黑色描边路径
+///
+/// 短于 `cfg.filter_speckle` 像素的线段视为噪点丢弃。
+pub(crate) fn trace_lines(pixels: &[u8], width: usize, height: usize, threshold: f64, cfg: &TracerConfig) -> String {
+    let luma = luma_map(pixels);
+    let mut edges = edge_map(&luma, width, height, threshold);
+    thin(&mut edges, width, height);
+
+    let min_length = cfg.filter_speckle.max(2);
+    let mut d = String::new();
+    for chain in trace_chains(&edges, width, height) {
+        if chain.len() < min_length {
+            continue;
+        }
+        // 坐标取像素中心
+        let points: Vec<PointF64> = chain
+            .iter()
+            .map(|&i| PointF64::new((i % width) as f64 + 0.5, (i / width) as f64 + 0.5))
+            .collect();
+        for (k, p) in simplify(&points, SIMPLIFY_TOLERANCE).iter().enumerate() {
+            let command = if k == 0 { 'M' } else { 'L' };
+            write!(d, "{}{} {} ", command, format_number(p.x, cfg.path_precision), format_number(p.y, cfg.path_precision)).ok();
+        }
+    }
+
+    let mut svg = String::with_capacity(d.len() + 256);
+    writeln!(svg, r#"<svg{} viewBox="0 0 {} {}">"#, svg_root_attrs(cfg, width as f64, height as f64), width, height).ok();
+    svg.push_str(&svg_root_children(cfg));
+    if !d.is_empty() {
+        writeln!(
+            svg,
+            r#"<path d="{}" {}/>"#,
+            d.trim_end(),
+            paint_attributes(&Color::new(0, 0, 0), cfg)
+        ).ok();
+    }
+    writeln!(svg, "</svg>").ok();
+    svg
+}
@@ -0,0 +1,101 @@
+//! 图层清单：与 SVG 一同返回的结构化元数据，供设计工具导入时直接读取，无需解析 SVG
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Object};
+use visioncortex::ColorImage;
+
+use crate::{
+    build_svg, check_empty, check_rgba_len, cluster_and_fit_sourced, content_bounds, geometry, reset_rng,
+    set_js_property, warnings, TraceOptions,
+};
+
+/// 追踪结果与图层清单
+#[wasm_bindgen(getter_with_clone)]
+pub struct TraceManifest {
+    /// 每个绘制元素带有 `id="snapsvg-layer-N"` 的 SVG
+    #[wasm_bindgen(readonly)]
+    pub svg: String,
+    /// 图层清单，结构见 `trace_rgba_with_manifest`
+    #[wasm_bindgen(readonly)]
+    pub manifest: JsValue,
+}
+
+/// 矢量化并返回 SVG 与描述全部图层的清单
+///
+/// 清单为 `{ width, height, layers }`：`width`/`height` 为原图尺寸 (像素)；`layers` 按绘制顺序
+/// (自下而上) 排列，每一项为：
+/// - `id`：字符串，与 SVG 中对应元素的 `id` 属性相同 (`snapsvg-layer-N`)；
+/// - `color`：填充色 (描边模式为描边色)，`#rrggbb`；
+/// - `z_index`：从 0 开始的叠放次序，越大越靠上，等于该元素在 SVG 中的出现次序；
+/// - `bbox`：`{ x, y, width, height }`，路径 (含曲线) 的包围盒；
+/// - `source_bbox`：`{ x, y, width, height }`，拟合前聚类的像素包围盒，为整数；覆盖列
+///   `x` ~ `x + width - 1`、行 `y` ~ `y + height - 1` (即 `x + width`、`y + height` 不含在内)。
+///   多条路径在后处理 (`post_merge_tolerance`、`merge_same_color`、`max_paths`) 中合并时取并集，
+///   `tileable` 时裁剪到画布范围内；
+/// - `point_count`：路径存储的点数 (全部子路径之和，样条含控制点)；
+/// - `area`：填充面积 (平方像素)，孔洞已扣除。
+///
+/// 几何数据按原图的像素坐标计算 (本函数不缩小输入，`source_bbox` 同样是原图坐标)：开启 `crop_to_content`、`origin` 或 `normalize_to` 时
+/// SVG 的坐标系经过平移或缩放，与清单不一致。`N` 是拟合结果中的下标，拟合后没有路径数据的
+/// 聚类不输出元素也不出现在清单中，因此 `N` 可能不连续，排序应使用 `z_index`。
+/// 线稿提取没有逐图层的拟合结果，这里忽略 `line_extraction`，按色块追踪。
+#[wasm_bindgen]
+pub fn trace_rgba_with_manifest(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<TraceManifest, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let mut cfg = options.to_config();
+    cfg.layer_ids = true;
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let (path_results, sources, _) = cluster_and_fit_sourced(color_image, &cfg);
+    check_empty(&path_results, &cfg)?;
+
+    let layers = Array::new();
+    for (i, (path, color)) in path_results.iter().enumerate() {
+        let point_count = geometry::point_count(path);
+        let Some((min, max)) = content_bounds(&path_results[i..=i]).filter(|_| point_count > 0) else {
+            continue;
+        };
+        let bbox = Object::new();
+        set_js_property(&bbox, "x", &min.x.into());
+        set_js_property(&bbox, "y", &min.y.into());
+        set_js_property(&bbox, "width", &(max.x - min.x).into());
+        set_js_property(&bbox, "height", &(max.y - min.y).into());
+
+        let source = &sources[i].rect;
+        let source_bbox = Object::new();
+        set_js_property(&source_bbox, "x", &source.left.into());
+        set_js_property(&source_bbox, "y", &source.top.into());
+        set_js_property(&source_bbox, "width", &source.width().into());
+        set_js_property(&source_bbox, "height", &source.height().into());
+
+        let layer = Object::new();
+        set_js_property(&layer, "id", &JsValue::from_str(&format!("snapsvg-layer-{}", i)));
+        set_js_property(&layer, "color", &JsValue::from_str(&format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)));
+        set_js_property(&layer, "z_index", &layers.length().into());
+        set_js_property(&layer, "bbox", &bbox.into());
+        set_js_property(&layer, "source_bbox", &source_bbox.into());
+        set_js_property(&layer, "point_count", &(point_count as u32).into());
+        set_js_property(&layer, "area", &geometry::compound_path_area(path).into());
+        layers.push(&layer);
+    }
+
+    let manifest = Object::new();
+    set_js_property(&manifest, "width", &width.into());
+    set_js_property(&manifest, "height", &height.into());
+    set_js_property(&manifest, "layers", &layers.into());
+
+    Ok(TraceManifest {
+        svg: build_svg(w, h, &path_results, &cfg),
+        manifest: manifest.into(),
+    })
+}
@@ -0,0 +1,78 @@
+//! 蒙版加权追踪：按抠图蒙版 (alpha matte) 的置信度决定每个像素如何参与追踪
+
+use wasm_bindgen::prelude::*;
+
+use crate::{check_rgba_len, trace_rgba_masked, TraceOptions};
+
+/// 蒙版值不低于此值视为确定的前景，颜色保持原样
+const CONFIDENT_FOREGROUND: u8 = 224;
+
+/// 把不确定边缘 (128 ~ `CONFIDENT_FOREGROUND`) 的像素颜色替换为 3x3 邻域内已确定颜色的平均色
+///
+/// 边缘像素多半混入了背景色，直接追踪会在轮廓外形成一圈细碎的过渡色块。按蒙版值从高到低
+/// 逐个处理，每个像素取邻域内确定前景与已处理过的边缘像素的平均色，主体颜色由内向外延伸，
+/// 整条边缘并入相邻的主体色块。邻域内没有可参考像素的边缘像素保持原色。
+fn flatten_uncertain_edges(pixels: &mut [u8], matte: &[u8], width: usize, height: usize) {
+    let mut uncertain: Vec<usize> = (0..matte.len())
+        .filter(|&i| (128..CONFIDENT_FOREGROUND).contains(&matte[i]))
+        .collect();
+    uncertain.sort_by_key(|&i| std::cmp::Reverse(matte[i]));
+
+    let mut settled: Vec<bool> = matte.iter().map(|&m| m >= CONFIDENT_FOREGROUND).collect();
+    for i in uncertain {
+        let (x, y) = (i % width, i / width);
+        let mut sum = [0u32; 3];
+        let mut count = 0u32;
+        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                let n = ny * width + nx;
+                if settled[n] {
+                    for (s, &c) in sum.iter_mut().zip(&pixels[n * 4..n * 4 + 3]) {
+                        *s += c as u32;
+                    }
+                    count += 1;
+                }
+            }
+        }
+        if let Some(half) = (count > 0).then_some(count / 2) {
+            for (c, s) in pixels[i * 4..i * 4 + 3].iter_mut().zip(sum) {
+                *c = ((s + half) / count) as u8;
+            }
+            settled[i] = true;
+        }
+    }
+}
+
+/// 按抠图蒙版追踪：`matte` 为单通道缓冲区 (每像素 1 字节，255 为前景)
+///
+/// 蒙版值按置信度分三段处理：
+/// - 低于 128：视为背景，与 `trace_rgba_masked` 相同地当作透明，不产生路径；
+/// - 128 ~ 223：不确定的边缘，参与追踪，但颜色由相邻的确定前景向外延伸替换，
+///   轮廓沿蒙版 128 处的等值线走，不会在边缘留下混有背景色的过渡色块；
+/// - 224 及以上：确定的前景，按原色正常追踪。
+///
+/// 蒙版只决定哪些像素参与以及边缘像素的颜色，不改变聚类与拟合参数，
+/// 输出路径都是不透明的，半透明边缘不会保留为渐变。
+#[wasm_bindgen]
+pub fn trace_rgba_with_matte(
+    rgba_data: &[u8],
+    matte: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+    if matte.len() != w * h {
+        return Err(JsValue::from_str(&format!(
+            "蒙版长度不匹配: 期望 {} 字节 ({}x{}x1), 实际 {} 字节",
+            w * h, w, h, matte.len()
+        )));
+    }
+
+    let mut pixels = rgba_data.to_vec();
+    flatten_uncertain_edges(&mut pixels, matte, w, h);
+    trace_rgba_masked(&pixels, matte, width, height, options)
+}
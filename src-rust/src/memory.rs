@@ -0,0 +1,105 @@
+//! 内存预算：追踪前估算峰值内存，超出预算时缩小输入后再追踪
+//!
+//! WASM 中分配失败会直接 trap，整个实例随之不可用，无法在失败后恢复；
+//! 因此只能在开始追踪之前按估算值决定输入尺寸，而不是捕获失败后重试。
+
+use wasm_bindgen::prelude::*;
+use visioncortex::ColorImage;
+
+use crate::{build_svg, check_empty, check_rgba_len, cluster_and_fit, reset_rng, scale_compound_path, warnings, TraceOptions};
+
+/// 平坦图像 (大色块) 每像素的峰值内存，实测约 36 字节：输入副本、聚类索引与色块像素列表
+const BASE_BYTES_PER_PIXEL: f64 = 40.0;
+
+/// 逐像素都在变化的噪声图像额外增加的每像素内存，实测总量约 215 字节，主要是大量小色块与输出文本
+const DETAIL_BYTES_PER_PIXEL: f64 = 180.0;
+
+/// 缩小后的最短边下限，再小的结果已没有参考价值
+const MIN_SIDE: usize = 16;
+
+/// 估算追踪的峰值内存 (字节)
+///
+/// 以 "与右侧像素颜色不同的像素比例" 衡量细节程度，在平坦与噪声两种实测值之间插值。
+/// 只是粗略估计：`refine_passes`、`protect_dark` 等选项会额外各做一次聚类，但与主流程先后执行，
+/// 峰值增加有限，未计入。
+fn estimate_bytes(pixels: &[u8], width: usize, height: usize) -> f64 {
+    let total = (width * height).max(1);
+    let changes = pixels
+        .chunks_exact(width * 4)
+        .flat_map(|row| row.chunks_exact(4).zip(row.chunks_exact(4).skip(1)))
+        .filter(|(a, b)| a != b)
+        .count();
+    let detail = changes as f64 / total as f64;
+    total as f64 * (BASE_BYTES_PER_PIXEL + DETAIL_BYTES_PER_PIXEL * detail)
+}
+
+/// 宽高各缩小一半 (2x2 平均，奇数的最后一行/列直接丢弃)
+pub(crate) fn halve(pixels: &[u8], width: usize, height: usize) -> (Vec<u8>, usize, usize) {
+    let (w, h) = (width / 2, height / 2);
+    let mut out = vec![0u8; w * h * 4];
+    for y in 0..h {
+        for x in 0..w {
+            for c in 0..4 {
+                let at = |dx: usize, dy: usize| pixels[((y * 2 + dy) * width + x * 2 + dx) * 4 + c] as u32;
+                out[(y * w + x) * 4 + c] = ((at(0, 0) + at(1, 0) + at(0, 1) + at(1, 1) + 2) / 4) as u8;
+            }
+        }
+    }
+    (out, w, h)
+}
+
+/// 在内存预算内矢量化：估算值超出 `memory_budget` (字节) 时把输入逐次缩小一半，直到估算值落在预算内
+///
+/// 缩小后追踪得到的坐标会放大回原尺寸，输出的 `viewBox` 始终与输入一致，只是细节变少；
+/// 发生缩小时通过 `on_warning` 以 `downscale` 类别报告最终的追踪尺寸。
+/// 缩到最短边不足 16 像素仍超出预算时返回错误，不做追踪。
+///
+/// 不调整 `batch_size`：它只影响聚类循环的切分粒度，对峰值内存几乎没有作用。
+/// 估算值是基于实测的粗略近似，预算应留出余量。
+#[wasm_bindgen]
+pub fn trace_rgba_with_memory_budget(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    memory_budget: f64,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let mut pixels = rgba_data.to_vec();
+    let (mut cw, mut ch) = (w, h);
+    let mut scale = 1usize;
+    while w > 0 && h > 0 && estimate_bytes(&pixels, cw, ch) > memory_budget {
+        if cw / 2 < MIN_SIDE || ch / 2 < MIN_SIDE {
+            return Err(JsValue::from_str(&format!(
+                "内存预算 {} 字节不足：缩小到 {}x{} 仍需约 {:.0} 字节",
+                memory_budget, cw, ch, estimate_bytes(&pixels, cw, ch)
+            )));
+        }
+        (pixels, cw, ch) = halve(&pixels, cw, ch);
+        scale *= 2;
+    }
+    if scale > 1 {
+        warnings::emit(
+            "downscale",
+            &format!("估算内存超出预算，输入从 {}x{} 缩小到 {}x{} 后追踪", w, h, cw, ch),
+        );
+    }
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels, width: cw, height: ch };
+    let mut path_results = cluster_and_fit(color_image, &cfg);
+    check_empty(&path_results, &cfg)?;
+    if scale > 1 {
+        for (path, _) in &mut path_results {
+            *path = scale_compound_path(path, scale as f64);
+        }
+    }
+
+    Ok(build_svg(w, h, &path_results, &cfg))
+}
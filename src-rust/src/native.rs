@@ -0,0 +1,55 @@
+//! 原生入口：在指定线程数的独立线程池中追踪，供原生测试与基准测试使用 (WASM 构建中不存在)
+
+use crate::{trace_rgba_core, TraceOptions};
+
+/// 与 `trace_rgba_with_options` 相同的流程，但整个追踪在 `num_threads` 个线程 (至少 1 个) 的
+/// 独立 Rayon 线程池中运行
+///
+/// 只在原生环境提供：WASM 中无法临时创建 Worker 线程，线程数由 `initThreadPool` 决定。
+/// 每次调用新建线程池，不占用也不受全局线程池影响，基准测试可以逐个线程数测量扩展性，
+/// 并行的测试之间也互不干扰。`options.max_threads` 在此被忽略。
+/// 错误以字符串返回 (原生环境不能构造 `JsValue`)，`on_warning` 回调同样不可用，警告被丢弃。
+pub fn trace_core(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    num_threads: usize,
+) -> Result<String, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads.max(1))
+        .build()
+        .map_err(|e| format!("无法创建 {} 线程的线程池: {}", num_threads.max(1), e))?;
+
+    // `TraceOptions` 含 JS 对象，不能跨线程共享，先在调用线程转换为内部配置
+    let mut cfg = options.to_config();
+    cfg.max_threads = None;
+    pool.install(move || trace_rgba_core(rgba_data, width, height, cfg, false).map(|(svg, ..)| svg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::shapes;
+    use crate::trace_rgba_with_options;
+
+    #[test]
+    fn matches_the_wasm_entry_for_any_thread_count() {
+        let pixels = shapes(60, 40);
+        let mut detailed = TraceOptions::new();
+        detailed.color_count = 24;
+        detailed.coordinate_precision = 1;
+        for options in [TraceOptions::new(), detailed] {
+            let expected = trace_rgba_with_options(&pixels, 60, 40, &options).unwrap();
+            for num_threads in [1, 2, 4] {
+                assert_eq!(trace_core(&pixels, 60, 40, &options, num_threads).unwrap(), expected, "{} 个线程", num_threads);
+            }
+        }
+    }
+
+    #[test]
+    fn mismatched_length_is_an_error() {
+        let pixels = shapes(60, 40);
+        assert!(trace_core(&pixels[4..], 60, 40, &TraceOptions::new(), 1).is_err());
+    }
+}
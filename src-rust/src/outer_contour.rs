@@ -0,0 +1,107 @@
+//! 外轮廓：只追踪前景的整体外形，输出单条闭合路径，用作裁剪路径或碰撞边界
+
+use wasm_bindgen::prelude::*;
+use visioncortex::clusters::Cluster;
+use visioncortex::{BinaryImage, Color, PointI32};
+
+use crate::preprocess::{detect_background, within_tolerance};
+use crate::{build_svg, check_rgba_len, reset_rng, warnings, TraceOptions};
+
+/// 存在半透明像素时，alpha 低于此值的像素视为背景
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// 与图像边缘连通的背景像素，其余像素 (含被前景包围的背景) 都算作前景
+fn outside_mask(rgba_data: &[u8], w: usize, h: usize) -> Vec<bool> {
+    if w == 0 || h == 0 {
+        return Vec::new();
+    }
+    // `None` 表示按透明度判定
+    let background = if rgba_data.chunks_exact(4).any(|px| px[3] < 255) {
+        None
+    } else {
+        match detect_background(rgba_data, w, h) {
+            Some(color) => Some(color),
+            None => return vec![false; w * h],
+        }
+    };
+    let is_background = |px: &[u8]| match background {
+        None => px[3] < ALPHA_THRESHOLD,
+        Some(color) => within_tolerance(px, color),
+    };
+
+    let mut outside = vec![false; w * h];
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for x in 0..w {
+        stack.extend([(x, 0), (x, h - 1)]);
+    }
+    for y in 0..h {
+        stack.extend([(0, y), (w - 1, y)]);
+    }
+    while let Some((x, y)) = stack.pop() {
+        let i = y * w + x;
+        if outside[i] || !is_background(&rgba_data[i * 4..i * 4 + 4]) {
+            continue;
+        }
+        outside[i] = true;
+        if x > 0 { stack.push((x - 1, y)); }
+        if x + 1 < w { stack.push((x + 1, y)); }
+        if y > 0 { stack.push((x, y - 1)); }
+        if y + 1 < h { stack.push((x, y + 1)); }
+    }
+    outside
+}
+
+/// 只追踪前景的外轮廓，返回仅含一条闭合 `<path>` 的 SVG (填充为黑色)
+///
+/// 背景的判定：
+/// - 图像含有不完全不透明的像素时，按透明度判定：alpha 低于 128 的像素为背景；
+/// - 否则与 `auto_background` 相同地采样四角，颜色一致时与该颜色相差在容差内的像素为背景；
+/// - 两者都不成立时整张图像都是前景，结果为画布矩形。
+///
+/// 只有与图像边缘 (4 邻域) 连通的背景才算外部，被前景包围的背景 (孔洞) 一律填实，
+/// 前景内部的颜色变化完全忽略。前景分成多块时只取面积最大 (4 邻域连通) 的一块，
+/// 其余部分被丢弃并通过 `on_warning` 以 `dropped` 类别报告。
+///
+/// 整个过程只做一次泛洪与一次轮廓拟合，不做颜色聚类，比完整追踪快得多。拟合使用
+/// `options` 中的 `mode`、`corner_threshold`、`length_threshold`、`max_iterations`、
+/// `splice_threshold`，输出格式沿用 `coordinate_precision` 等选项；颜色相关的选项不生效。
+/// 没有前景像素时返回错误。
+#[wasm_bindgen]
+pub fn trace_rgba_outer_contour(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let outside = outside_mask(rgba_data, w, h);
+    let mut image = BinaryImage::new_w_h(w, h);
+    for (i, _) in outside.iter().enumerate().filter(|(_, &o)| !o) {
+        image.set_pixel(i % w, i / w, true);
+    }
+    let parts = image.to_clusters(false);
+    let Some(largest) = parts.iter().max_by_key(|part| part.size()) else {
+        return Err(JsValue::from_str("没有找到前景像素 (整张图像都被判定为背景)"));
+    };
+    if parts.len() > 1 {
+        warnings::emit("dropped", &format!("前景分为 {} 块，只保留面积最大的一块", parts.len()));
+    }
+
+    let path = Cluster::image_to_compound_path(
+        &PointI32::new(largest.rect.left, largest.rect.top),
+        &largest.to_binary_image(),
+        cfg.mode,
+        (cfg.corner_threshold as f64).to_radians(),
+        cfg.length_threshold,
+        cfg.max_iterations,
+        (cfg.splice_threshold as f64).to_radians(),
+    );
+    Ok(build_svg(w, h, &[(path, Color::new(0, 0, 0))], &cfg))
+}
@@ -0,0 +1,339 @@
+//! 固定调色板追踪：先确定调色板，再把像素映射到最近的调色板颜色后追踪
+//! 
+//! 用于批量处理视频帧等场景：所有帧共用同一调色板，颜色在帧间保持稳定不闪烁
+
+use wasm_bindgen::prelude::*;
+use visioncortex::{Color, ColorImage, CompoundPath};
+
+use std::collections::HashMap;
+
+use crate::color_space::{lab_distance_sq, rgb_to_lab, ColorSpace};
+use crate::{build_svg, check_rgba_len, cluster_and_fit, cluster_color, cluster_image, geometry, preprocess, reset_rng, warnings, TraceOptions, TracerConfig};
+
+/// RGB 空间的平方距离
+pub(crate) fn rgb_distance_sq(a: Color, b: Color) -> i32 {
+    let dr = a.r as i32 - b.r as i32;
+    let dg = a.g as i32 - b.g as i32;
+    let db = a.b as i32 - b.b as i32;
+    dr * dr + dg * dg + db * db
+}
+
+/// 最近色匹配器：LAB 模式下预先计算调色板的 LAB 坐标，并缓存已匹配过的颜色
+struct NearestColor<'a> {
+    palette: &'a [Color],
+    palette_lab: Vec<[f64; 3]>,
+    space: ColorSpace,
+    cache: HashMap<(u8, u8, u8), usize>,
+}
+
+impl<'a> NearestColor<'a> {
+    fn new(palette: &'a [Color], space: ColorSpace) -> Self {
+        let palette_lab = match space {
+            ColorSpace::Rgb => Vec::new(),
+            ColorSpace::Lab => palette.iter().map(|&c| rgb_to_lab(c)).collect(),
+        };
+        NearestColor { palette, palette_lab, space, cache: HashMap::new() }
+    }
+    
+    /// 在调色板中查找与 `color` 最接近的颜色，返回其下标 (调色板为空时返回 `None`)
+    fn find_index(&mut self, color: Color) -> Option<usize> {
+        let key = (color.r, color.g, color.b);
+        if let Some(&hit) = self.cache.get(&key) {
+            return Some(hit);
+        }
+        
+        let nearest = match self.space {
+            ColorSpace::Rgb => self.palette
+                .iter()
+                .enumerate()
+                .min_by_key(|&(_, &p)| rgb_distance_sq(p, color))
+                .map(|(i, _)| i),
+            ColorSpace::Lab => {
+                let lab = rgb_to_lab(color);
+                self.palette_lab
+                    .iter()
+                    .map(|p| lab_distance_sq(p, &lab))
+                    .enumerate()
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(i, _)| i)
+            }
+        }?;
+        
+        self.cache.insert(key, nearest);
+        Some(nearest)
+    }
+    
+    /// 在调色板中查找与 `color` 最接近的颜色
+    fn find(&mut self, color: Color) -> Color {
+        self.find_index(color).map_or(color, |i| self.palette[i])
+    }
+}
+
+/// 将扁平的 RGB 三元组解析为调色板
+fn parse_palette(palette: &[u8]) -> Result<Vec<Color>, JsValue> {
+    if palette.is_empty() || !palette.len().is_multiple_of(3) {
+        return Err(JsValue::from_str(&format!(
+            "调色板长度必须是 3 的正整数倍 (RGB 三元组), 实际 {} 字节",
+            palette.len()
+        )));
+    }
+    Ok(palette.chunks_exact(3).map(|c| Color::new(c[0], c[1], c[2])).collect())
+}
+
+/// 对图像执行一次聚类，取覆盖面积最大的 `color_count` 种聚类颜色作为调色板
+fn extract_palette(color_image: ColorImage, cfg: &TracerConfig, color_count: u8) -> Vec<Color> {
+    let clusters = cluster_image(color_image, cfg);
+    let view = clusters.view();
+    
+    // 相同颜色的聚类合并计算面积
+    let mut areas: Vec<(Color, usize)> = Vec::new();
+    for &index in view.clusters_output {
+        let cluster = view.get_cluster(index);
+        let color = cluster_color(cluster, cfg);
+        match areas.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, area)) => *area += cluster.area(),
+            None => areas.push((color, cluster.area())),
+        }
+    }
+    
+    areas.sort_by_key(|&(_, area)| std::cmp::Reverse(area));
+    areas.truncate(color_count.max(2) as usize);
+    areas.into_iter().map(|(color, _)| color).collect()
+}
+
+/// 两遍追踪的调色板：先粗聚类取主色，再以主色为初始中心做 k-means 迭代修正颜色
+/// 
+/// 第一遍聚类得到的颜色是各色块的平均色，相邻色块合并时会被拉向中间色；
+/// 每轮迭代把每个像素分配给最近的中心，再把中心移到所分配像素的均值，
+/// 共迭代 `cfg.refine_passes - 1` 轮。透明像素 (alpha < 128) 不参与；
+/// 没有分配到像素的中心保持不动。
+pub(crate) fn refine_palette(color_image: &ColorImage, cfg: &TracerConfig) -> Vec<Color> {
+    let mut centers = extract_palette(color_image.clone(), cfg, cfg.color_count);
+    
+    // 与聚类看到的像素保持一致：先做同样的预处理
+    let mut pixels = color_image.pixels.clone();
+    preprocess::apply(&mut pixels, color_image.width, cfg);
+    
+    for _ in 1..cfg.refine_passes {
+        let mut sums = vec![[0u64; 4]; centers.len()];
+        let mut nearest = NearestColor::new(&centers, cfg.color_space);
+        for px in pixels.chunks_exact(4) {
+            if px[3] < 128 {
+                continue;
+            }
+            if let Some(i) = nearest.find_index(Color::new(px[0], px[1], px[2])) {
+                let sum = &mut sums[i];
+                sum[0] += px[0] as u64;
+                sum[1] += px[1] as u64;
+                sum[2] += px[2] as u64;
+                sum[3] += 1;
+            }
+        }
+        
+        let mut moved = false;
+        for (center, sum) in centers.iter_mut().zip(&sums) {
+            if sum[3] == 0 {
+                continue;
+            }
+            let n = sum[3];
+            let mean = Color::new(
+                ((sum[0] + n / 2) / n) as u8,
+                ((sum[1] + n / 2) / n) as u8,
+                ((sum[2] + n / 2) / n) as u8,
+            );
+            moved |= mean != *center;
+            *center = mean;
+        }
+        // 中心不再移动时提前结束
+        if !moved {
+            break;
+        }
+    }
+    
+    centers
+}
+
+/// 把像素原地映射到调色板中最近的颜色 (alpha 保持不变)
+pub(crate) fn quantize_pixels(pixels: &mut [u8], palette: &[Color], space: ColorSpace) {
+    let mut nearest = NearestColor::new(palette, space);
+    for px in pixels.chunks_exact_mut(4) {
+        let mapped = nearest.find(Color::new(px[0], px[1], px[2]));
+        px[0] = mapped.r;
+        px[1] = mapped.g;
+        px[2] = mapped.b;
+    }
+}
+
+/// 把输出颜色吸附到调色板
+/// 
+/// 层次聚类可能把相邻色块合并成平均色，吸附后保证只出现调色板中的颜色
+pub(crate) fn snap_colors(path_results: &mut [(CompoundPath, Color)], palette: &[Color], space: ColorSpace) {
+    let mut nearest = NearestColor::new(palette, space);
+    for (_, color) in path_results.iter_mut() {
+        *color = nearest.find(*color);
+    }
+}
+
+/// 合并颜色相近的路径：颜色距离不超过 `tolerance` 的路径并入最早绘制的那一条
+/// 
+/// 按绘制顺序逐条处理，与已保留路径的当前颜色比较 (`space` 为 LAB 时使用 CIE76 色差，
+/// 否则为 RGB 欧氏距离，取值 0~255 量级)；合并后的颜色为各路径按面积加权的 RGB 平均色。
+/// 与路径预算相同，并入的子路径改用保留路径的绘制层级，可能被两者之间绘制的路径遮挡。
+/// 返回每条输入路径在结果中的下标。
+pub(crate) fn merge_similar(
+    path_results: &mut Vec<(CompoundPath, Color)>,
+    tolerance: f64,
+    space: ColorSpace,
+) -> Vec<usize> {
+    let distance = |a: Color, b: Color| match space {
+        ColorSpace::Lab => lab_distance_sq(&rgb_to_lab(a), &rgb_to_lab(b)).sqrt(),
+        ColorSpace::Rgb => (rgb_distance_sq(a, b) as f64).sqrt(),
+    };
+    
+    // 保留的路径及其颜色累计 (按面积加权的 RGB 分量之和与总面积)
+    let mut merged: Vec<(CompoundPath, Color, [f64; 3], f64)> = Vec::with_capacity(path_results.len());
+    let mut targets = Vec::with_capacity(path_results.len());
+    for (path, color) in path_results.drain(..) {
+        let area = geometry::compound_path_area(&path).max(1e-9);
+        let weighted = [color.r as f64 * area, color.g as f64 * area, color.b as f64 * area];
+        let found = merged.iter().position(|(_, c, _, _)| distance(*c, color) <= tolerance);
+        targets.push(found.unwrap_or(merged.len()));
+        match found.map(|i| &mut merged[i]) {
+            Some((target, target_color, sums, total)) => {
+                target.paths.extend(path.paths);
+                for (sum, w) in sums.iter_mut().zip(weighted) {
+                    *sum += w;
+                }
+                *total += area;
+                let channel = |sum: f64| (sum / *total).round().clamp(0.0, 255.0) as u8;
+                *target_color = Color::new(channel(sums[0]), channel(sums[1]), channel(sums[2]));
+            }
+            None => merged.push((path, color, weighted, area)),
+        }
+    }
+    
+    *path_results = merged.into_iter().map(|(path, color, _, _)| (path, color)).collect();
+    targets
+}
+
+/// 使用给定调色板追踪一帧
+fn trace_with_palette(
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    palette: &[Color],
+    cfg: &TracerConfig,
+) -> String {
+    reset_rng();
+    
+    let mut cfg = cfg.clone();
+    cfg.palette = Some(palette.to_vec());
+    
+    let color_image = ColorImage { pixels, width, height };
+    let path_results = cluster_and_fit(color_image, &cfg);
+    
+    build_svg(width, height, &path_results, &cfg)
+}
+
+/// 使用固定调色板矢量化
+/// 
+/// `palette` 为扁平的 RGB 三元组 (`[r0, g0, b0, r1, g1, b1, ...]`)。
+/// 每个像素按 `options.color_space` 指定空间中的欧氏距离映射到最近的调色板颜色后再聚类，
+/// 输出只包含调色板中的颜色。
+#[wasm_bindgen]
+pub fn trace_rgba_with_palette(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    palette: &[u8],
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    let palette = parse_palette(palette)?;
+    
+    Ok(trace_with_palette(rgba_data.to_vec(), w, h, &palette, &options.to_config()))
+}
+
+/// 批量矢量化多帧，所有帧共用同一调色板，避免逐帧聚类导致的颜色闪烁
+/// 
+/// `frames` 为按顺序拼接的若干帧 RGBA 数据，每帧 `width * height * 4` 字节。
+/// 
+/// 共享调色板的来源由 `palette_source` 决定：
+/// - `"first"`（默认）：对第一帧聚类，取覆盖面积最大的 `color_count` 种颜色
+/// - `"union"`：把所有帧纵向拼成一张大图一起聚类，再取面积最大的 `color_count` 种颜色；
+///   能照顾到只在后续帧出现的颜色，但聚类耗时与内存随帧数线性增长
+/// 
+/// 返回每帧对应的 SVG 字符串。
+#[wasm_bindgen]
+pub fn trace_batch_fixed_palette(
+    frames: &[u8],
+    width: u32,
+    height: u32,
+    palette_source: &str,
+    options: &TraceOptions,
+) -> Result<Vec<String>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    let w = width as usize;
+    let h = height as usize;
+    let frame_len = w * h * 4;
+    
+    if frame_len == 0 || frames.is_empty() || !frames.len().is_multiple_of(frame_len) {
+        return Err(JsValue::from_str(&format!(
+            "帧数据长度必须是单帧大小 ({} 字节) 的正整数倍, 实际 {} 字节",
+            frame_len, frames.len()
+        )));
+    }
+    let frame_count = frames.len() / frame_len;
+    
+    let cfg = options.to_config();
+    
+    reset_rng();
+    let palette = match palette_source {
+        "union" => extract_palette(
+            ColorImage { pixels: frames.to_vec(), width: w, height: h * frame_count },
+            &cfg,
+            cfg.color_count,
+        ),
+        _ => extract_palette(
+            ColorImage { pixels: frames[..frame_len].to_vec(), width: w, height: h },
+            &cfg,
+            cfg.color_count,
+        ),
+    };
+    
+    Ok(frames
+        .chunks_exact(frame_len)
+        .map(|frame| trace_with_palette(frame.to_vec(), w, h, &palette, &cfg))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{fill_rect, fills, solid};
+    use crate::{trace_rgba_with_options, TraceOptions};
+
+    #[test]
+    fn near_identical_reds_merge_into_one_layer() {
+        // 两个互不相连的色块各自成为一个聚类
+        let mut pixels = solid(40, 20, [255, 255, 255, 255]);
+        fill_rect(&mut pixels, 40, (4, 4), (16, 16), [255, 0, 0, 255]);
+        fill_rect(&mut pixels, 40, (24, 4), (36, 16), [254, 0, 0, 255]);
+        let mut options = TraceOptions::new();
+        let svg = trace_rgba_with_options(&pixels, 40, 20, &options).unwrap();
+        assert_eq!(fills(&svg), ["#ffffff", "#fe0000", "#ff0000"]);
+
+        // 面积相同，平均色 254.5 取整为 255
+        options.post_merge_tolerance = 2.0;
+        let svg = trace_rgba_with_options(&pixels, 40, 20, &options).unwrap();
+        assert_eq!(fills(&svg), ["#ffffff", "#ff0000"]);
+
+        // LAB 空间中聚类颜色经过换算，只要求合并为一层
+        options.color_space = "lab".to_owned();
+        let svg = trace_rgba_with_options(&pixels, 40, 20, &options).unwrap();
+        assert_eq!(fills(&svg).len(), 2, "{}", svg);
+    }
+}
@@ -0,0 +1,104 @@
+//! 多边形导出：按颜色分组的闭合多边形列表，供 Unity/Godot 等引擎的导入工具直接使用
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Float32Array, Object};
+use visioncortex::{Color, ColorImage};
+
+use crate::geometry::{flatten_element_adaptive, polygon_area};
+use crate::{check_rgba_len, cluster_and_fit, reset_rng, set_js_property, warnings, TraceOptions};
+
+/// 矢量化并以多边形列表输出，省去引擎端解析 SVG 的步骤
+///
+/// 返回数组，每个元素对应一种颜色 (按首次绘制的顺序)：
+/// `{ r, g, b, polygons: [{ points: Float32Array, hole: boolean }] }`，
+/// `points` 为 `[x0, y0, x1, y1, ...]` 像素坐标，首尾不重复。
+///
+/// 绕向：外轮廓统一为像素坐标系 (y 轴向下) 中有向面积为正，即屏幕上顺时针；
+/// 孔洞 (`hole: true`) 统一为相反方向。孔洞按所在复合路径中面积最大的子路径判定，
+/// 与其方向相反的子路径即为孔洞。
+///
+/// `flatness` 为曲线展平的最大允许偏差 (像素)：曲线被递归细分到折线与曲线的距离
+/// 不超过该值为止。偏差每减小到 1/4，顶点数约翻倍；0.25 左右在视觉上与曲线无差别，
+/// 1~2 适合碰撞体等对精度不敏感的用途。
+#[wasm_bindgen]
+pub fn trace_rgba_to_polygons(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    flatness: f64,
+    options: &TraceOptions,
+) -> Result<JsValue, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+    if flatness.is_nan() || flatness <= 0.0 {
+        return Err(JsValue::from_str("flatness 必须大于 0"));
+    }
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let path_results = cluster_and_fit(color_image, &cfg);
+
+    // 按颜色分组，保持首次出现的顺序
+    let mut groups: Vec<(Color, Array)> = Vec::new();
+
+    for (compound_path, color) in &path_results {
+        let mut rings: Vec<(Vec<f32>, f64)> = Vec::with_capacity(compound_path.paths.len());
+        for element in &compound_path.paths {
+            let mut points = flatten_element_adaptive(element, flatness);
+            if points.len() > 1 && points[0].distance_to(points[points.len() - 1]) < 1e-9 {
+                points.pop();
+            }
+            if points.len() < 3 {
+                continue;
+            }
+            let area = polygon_area(&points);
+            let flat = points.iter().flat_map(|p| [p.x as f32, p.y as f32]).collect();
+            rings.push((flat, area));
+        }
+
+        let outer_sign = rings
+            .iter()
+            .map(|(_, area)| *area)
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .map(f64::signum)
+            .unwrap_or(1.0);
+
+        let index = match groups.iter().position(|(c, _)| c == color) {
+            Some(index) => index,
+            None => {
+                groups.push((*color, Array::new()));
+                groups.len() - 1
+            }
+        };
+
+        for (mut flat, area) in rings {
+            let hole = area.signum() != outer_sign;
+            // 外轮廓为正向、孔洞为反向
+            if (area < 0.0) != hole {
+                let reversed: Vec<f32> = flat.chunks_exact(2).rev().flatten().copied().collect();
+                flat = reversed;
+            }
+
+            let polygon = Object::new();
+            set_js_property(&polygon, "points", &Float32Array::from(flat.as_slice()).into());
+            set_js_property(&polygon, "hole", &JsValue::from_bool(hole));
+            groups[index].1.push(&polygon);
+        }
+    }
+
+    let result = Array::new();
+    for (color, polygons) in groups {
+        let entry = Object::new();
+        set_js_property(&entry, "r", &color.r.into());
+        set_js_property(&entry, "g", &color.g.into());
+        set_js_property(&entry, "b", &color.b.into());
+        set_js_property(&entry, "polygons", &polygons.into());
+        result.push(&entry);
+    }
+
+    Ok(result.into())
+}
@@ -0,0 +1,274 @@
+//! 后处理链：在内存中的路径数据上依次执行合并、简化与紧凑输出
+//!
+//! 各步骤直接作用于拟合结果与最终的序列化过程，不需要先输出 SVG 再逐个交给工具函数解析、改写，
+//! 省去每一步一次的解析与序列化往返。
+
+use wasm_bindgen::prelude::*;
+use visioncortex::{CompoundPath, CompoundPathElement, PointF64};
+
+use crate::format_number;
+use crate::geometry::{douglas_peucker, line_distance, simplify};
+
+/// 后处理选项，通过 `TraceOptions.postprocess` 传入，各步骤默认关闭
+///
+/// 执行顺序固定为：
+/// 1. `merge_same_color`：合并颜色相同的路径 (与 `post_merge_tolerance` 的合并为同一步，在 `max_paths` 之前)；
+/// 2. `simplify_tolerance`：简化合并后的路径；
+/// 3. `relative_commands` 与 `minify`：输出路径数据时生效。
+///
+/// JS 端赋值 `opts.postprocess = pp` 会转移 `pp` 的所有权，之后应通过 `opts.postprocess` 读取副本。
+#[wasm_bindgen]
+#[derive(Clone, Copy, Default)]
+pub struct PostprocessOptions {
+    /// 颜色完全相同的路径并入最早绘制的那一条，减少元素数量 (并入的子路径改用保留路径的绘制层级)
+    pub merge_same_color: bool,
+    /// 简化容差 (像素)，0 表示不简化
+    ///
+    /// 折线路径用 Douglas-Peucker 删除偏离不超过容差的顶点；样条路径只处理
+    /// 控制点都在弦上 (偏离不超过容差) 的直线段，连续共线的直线段合并为一段，曲线段保持不变。
+    pub simplify_tolerance: f64,
+    /// 路径数据改用相对命令 (`m`/`l`/`c`/`z`)，坐标直接相对画布，不再输出 `transform`
+    ///
+    /// 相对坐标的增量按已取整的坐标计算，误差不会沿路径累积。
+    pub relative_commands: bool,
+    /// 紧凑输出：省略路径数据中可省略的空格、重复的命令字母与小数前导 0，元素之间不换行
+    ///
+    /// 坐标同样直接相对画布，不再输出 `transform`。
+    pub minify: bool,
+}
+
+#[wasm_bindgen]
+impl PostprocessOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PostprocessOptions {
+        PostprocessOptions::default()
+    }
+}
+
+/// 简化样条：控制点都在弦附近的连续直线段按锚点做 Douglas-Peucker，曲线段原样保留
+fn simplify_spline(points: &[PointF64], tolerance: f64) -> Vec<PointF64> {
+    let straight = |i: usize| {
+        let (a, b) = (points[i], points[i + 3]);
+        line_distance(points[i + 1], a, b) <= tolerance && line_distance(points[i + 2], a, b) <= tolerance
+    };
+
+    let mut out = vec![points[0]];
+    let mut i = 0;
+    while i + 3 < points.len() {
+        if !straight(i) {
+            out.extend_from_slice(&points[i + 1..i + 4]);
+            i += 3;
+            continue;
+        }
+        let run_start = i;
+        let mut anchors = vec![points[i]];
+        while i + 3 < points.len() && straight(i) {
+            anchors.push(points[i + 3]);
+            i += 3;
+        }
+        let kept = simplify(&anchors, tolerance);
+        if kept.len() == anchors.len() {
+            // 没有可合并的段，保留原有控制点
+            out.extend_from_slice(&points[run_start + 1..=i]);
+            continue;
+        }
+        for pair in kept.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let at = |t: f64| PointF64::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+            out.extend([at(1.0 / 3.0), at(2.0 / 3.0), b]);
+        }
+    }
+    out
+}
+
+/// 按容差简化复合路径的每条子路径，简化后不足以构成封闭形状的子路径保持原样
+pub(crate) fn simplify_path(path: &mut CompoundPath, tolerance: f64) {
+    for element in &mut path.paths {
+        match element {
+            CompoundPathElement::PathI32(p) => {
+                let points: Vec<PointF64> = p.path.iter().map(|q| PointF64::new(q.x as f64, q.y as f64)).collect();
+                let keep = douglas_peucker(&points, tolerance);
+                if keep.iter().filter(|&&k| k).count() >= 4 {
+                    p.path = p.path.iter().zip(keep).filter(|(_, k)| *k).map(|(q, _)| *q).collect();
+                }
+            }
+            CompoundPathElement::PathF64(p) => {
+                let simplified = simplify(&p.path, tolerance);
+                if simplified.len() >= 4 {
+                    p.path = simplified;
+                }
+            }
+            CompoundPathElement::Spline(s) => {
+                if s.points.len() >= 7 {
+                    let simplified = simplify_spline(&s.points, tolerance);
+                    if simplified.len() >= 7 {
+                        s.points = simplified;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 清理输出前的路径命令：合并连续重复的坐标，删去零长度的线段与曲线段
+///
+/// `offset` 是写出坐标时加到各点上的平移量，坐标加上它后按输出精度格式化再比较，
+/// 取整后才重合的点同样视为重复。曲线段只在两端点与两个控制点全部重合时删去；
+/// 清理后退化为一个点的子路径整条删去。
+pub(crate) fn clean_commands(path: &CompoundPath, offset: PointF64, precision: Option<u32>) -> CompoundPath {
+    // 按输出的格式化结果比较，取整方式 (如 0.5 的舍入方向) 与写出的数字完全一致，
+    // 解析回数值后 `-0` 与 `0` 也视为相同
+    let written = |v: f64| format_number(v, precision).parse::<f64>().unwrap_or(v);
+    let key = |p: PointF64| (written(p.x + offset.x), written(p.y + offset.y));
+    let mut cleaned = CompoundPath::new();
+    for element in &path.paths {
+        match element {
+            CompoundPathElement::PathI32(p) => {
+                let mut out = p.clone();
+                out.path.dedup_by(|b, a| key(PointF64::new(a.x as f64, a.y as f64)) == key(PointF64::new(b.x as f64, b.y as f64)));
+                if out.path.len() >= 2 {
+                    cleaned.add_path_i32(out);
+                }
+            }
+            CompoundPathElement::PathF64(p) => {
+                let mut out = p.clone();
+                out.path.dedup_by(|b, a| key(*a) == key(*b));
+                if out.path.len() >= 2 {
+                    cleaned.add_path_f64(out);
+                }
+            }
+            CompoundPathElement::Spline(s) => {
+                let mut out = s.clone();
+                if let Some(&first) = s.points.first() {
+                    out.points = vec![first];
+                    for segment in s.points[1..].chunks_exact(3) {
+                        let start = key(*out.points.last().unwrap());
+                        if segment.iter().any(|&q| key(q) != start) {
+                            out.points.extend_from_slice(segment);
+                        }
+                    }
+                }
+                if out.points.len() >= 4 {
+                    cleaned.add_spline(out);
+                }
+            }
+        }
+    }
+    cleaned
+}
+
+/// 路径数据的逐个记号写出，负责命令字母与数字之间的分隔
+struct PathWriter {
+    out: String,
+    precision: Option<u32>,
+    minify: bool,
+    last_command: Option<char>,
+    /// 上一个记号是数字时，是否含有小数点
+    last_number: Option<bool>,
+}
+
+impl PathWriter {
+    fn command(&mut self, command: char) {
+        // 紧凑模式下连续相同的 L/C 命令可以省略字母 (M 后的隐式命令是 L，不能省略)
+        if self.minify && self.last_command == Some(command) && "LlCc".contains(command) {
+            return;
+        }
+        if !self.minify && !self.out.is_empty() {
+            self.out.push(' ');
+        }
+        self.out.push(command);
+        self.last_command = Some(command);
+        self.last_number = None;
+    }
+
+    fn number(&mut self, value: f64) {
+        let mut text = format_number(value, self.precision);
+        if text == "-0" {
+            text = "0".to_owned();
+        }
+        if self.minify {
+            if let Some(rest) = text.strip_prefix("0.") {
+                text = format!(".{}", rest);
+            } else if let Some(rest) = text.strip_prefix("-0.") {
+                text = format!("-.{}", rest);
+            }
+        }
+        if let Some(had_dot) = self.last_number {
+            let joinable = text.starts_with('-') || (text.starts_with('.') && had_dot);
+            if !self.minify || !joinable {
+                self.out.push(' ');
+            }
+        }
+        self.last_number = Some(text.contains('.'));
+        self.out.push_str(&text);
+    }
+
+    fn point(&mut self, p: PointF64) {
+        self.number(p.x);
+        self.number(p.y);
+    }
+}
+
+/// 生成复合路径的 `d` 数据，坐标加上 `origin` 后直接相对画布 (不需要 `transform`)
+pub(crate) fn path_data(path: &CompoundPath, origin: PointF64, precision: Option<u32>, relative: bool, minify: bool) -> String {
+    let round = |v: f64| match precision {
+        Some(p) => {
+            let factor = 10f64.powi(p as i32);
+            (v * factor).round() / factor
+        }
+        None => v,
+    };
+    let absolute = |p: &PointF64| PointF64::new(round(p.x + origin.x), round(p.y + origin.y));
+
+    let mut writer = PathWriter { out: String::new(), precision, minify, last_command: None, last_number: None };
+    let mut current = PointF64::default();
+    for element in &path.paths {
+        let points: Vec<PointF64> = match element {
+            CompoundPathElement::PathI32(p) => p.path.iter().map(|q| absolute(&PointF64::new(q.x as f64, q.y as f64))).collect(),
+            CompoundPathElement::PathF64(p) => p.path.iter().map(absolute).collect(),
+            CompoundPathElement::Spline(s) => s.points.iter().map(absolute).collect(),
+        };
+        let Some(&start) = points.first() else { continue };
+        let delta = |p: PointF64, from: PointF64| PointF64::new(p.x - from.x, p.y - from.y);
+
+        if relative && writer.last_command.is_some() {
+            writer.command('m');
+            writer.point(delta(start, current));
+        } else {
+            writer.command('M');
+            writer.point(start);
+        }
+        current = start;
+
+        match element {
+            CompoundPathElement::Spline(_) => {
+                for segment in points[1..].chunks_exact(3) {
+                    if relative {
+                        writer.command('c');
+                        segment.iter().for_each(|&p| writer.point(delta(p, current)));
+                    } else {
+                        writer.command('C');
+                        segment.iter().for_each(|&p| writer.point(p));
+                    }
+                    current = segment[2];
+                }
+            }
+            _ => {
+                // 封闭折线的最后一个点与起点重合，由 Z 代替
+                for &p in points.get(1..points.len() - 1).unwrap_or(&[]) {
+                    if relative {
+                        writer.command('l');
+                        writer.point(delta(p, current));
+                    } else {
+                        writer.command('L');
+                        writer.point(p);
+                    }
+                    current = p;
+                }
+            }
+        }
+        writer.command(if relative { 'z' } else { 'Z' });
+        current = start;
+    }
+    writer.out
+}
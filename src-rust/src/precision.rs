@@ -0,0 +1,145 @@
+//! 分项输出精度：路径坐标、`translate` 偏移与渐变色标各自的小数位数
+
+use wasm_bindgen::prelude::*;
+use visioncortex::{CompoundPath, CompoundPathElement, PointF64, PointI32};
+
+use crate::geometry;
+
+/// 分项精度选项，通过 `TraceOptions.precision` 传入，各项省略时沿用 `coordinate_precision`
+///
+/// 例如坐标保留 1 位小数、偏移取整：`coord_precision = 1`、`transform_precision = 0`。
+/// JS 端赋值 `opts.precision = p` 会转移 `p` 的所有权，之后应通过 `opts.precision` 读取副本。
+#[wasm_bindgen]
+#[derive(Clone, Copy, Default)]
+pub struct PrecisionOptions {
+    /// 路径数据 (`d`) 与其它几何数字 (`viewBox`、描边宽度等) 的小数位数，覆盖 `coordinate_precision`
+    pub coord_precision: Option<u8>,
+    /// `transform="translate(x,y)"` 偏移的小数位数
+    ///
+    /// 偏移先按此精度取整，路径坐标改为相对取整后的偏移输出，取整误差不会使形状整体错位；
+    /// 代价是各路径的首个点不再恰好位于原点，`d` 中的数字可能略长。
+    /// 使用相对命令或紧凑输出 (见 `PostprocessOptions`) 时不输出 `transform`，此项不生效。
+    pub transform_precision: Option<u8>,
+    /// 渐变色标 (`<stop offset>`) 的小数位数，为今后的渐变输出预留，目前没有输出渐变，不生效
+    pub stop_precision: Option<u8>,
+}
+
+#[wasm_bindgen]
+impl PrecisionOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PrecisionOptions {
+        PrecisionOptions::default()
+    }
+}
+
+/// 按 `precision` 位小数四舍五入
+fn round_to(value: f64, precision: u32) -> f64 {
+    let scale = 10f64.powi(precision as i32);
+    (value * scale).round() / scale
+}
+
+/// 取整后的 `translate` 偏移，以及写出各点坐标时加上的平移量；空路径返回 `None`
+pub(crate) fn rounded_offset(path: &CompoundPath, origin: PointF64, transform_precision: u32) -> Option<(PointF64, PointF64)> {
+    let first = geometry::first_point(path)?;
+    let offset = PointF64::new(
+        round_to(first.x + origin.x, transform_precision),
+        round_to(first.y + origin.y, transform_precision),
+    );
+    Some((offset, PointF64::new(origin.x - offset.x, origin.y - offset.y)))
+}
+
+/// 各点坐标加上 `shift` 后按 `precision` 输出的路径数据 (每条子路径都闭合)
+pub(crate) fn path_data_with_shift(path: &CompoundPath, shift: PointF64, precision: Option<u32>) -> String {
+    path.paths
+        .iter()
+        .map(|element| match element {
+            // 整数路径的首个点为整数，取整后的偏移同样是整数，平移量可以精确表示
+            CompoundPathElement::PathI32(p) => {
+                p.to_svg_string(true, &PointI32::new(shift.x.round() as i32, shift.y.round() as i32), precision)
+            }
+            CompoundPathElement::PathF64(p) => p.to_svg_string(true, &shift, precision),
+            CompoundPathElement::Spline(p) => p.to_svg_string(true, &shift, precision),
+        })
+        .collect()
+}
+
+/// 与 `CompoundPath::to_svg_string` 相同地以首个点为偏移输出路径数据，但偏移先按
+/// `transform_precision` 取整，各点坐标相对取整后的偏移、按 `precision` 输出
+pub(crate) fn path_data_with_rounded_offset(
+    path: &CompoundPath,
+    origin: PointF64,
+    precision: Option<u32>,
+    transform_precision: u32,
+) -> (String, PointF64) {
+    let Some((offset, shift)) = rounded_offset(path, origin, transform_precision) else {
+        return (String::new(), origin);
+    };
+    (path_data_with_shift(path, shift, precision), offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{path_data, shapes, transforms};
+    use crate::{trace_rgba_with_options, TraceOptions};
+    use visioncortex::Spline;
+
+    #[test]
+    fn rounded_offset_has_no_decimals_at_precision_zero() {
+        let mut spline = Spline::new(PointF64::new(3.37, 4.61));
+        spline.add(PointF64::new(5.2, 4.61), PointF64::new(6.8, 7.05), PointF64::new(3.37, 4.61));
+        let mut path = CompoundPath::new();
+        path.add_spline(spline);
+
+        let origin = PointF64::new(0.25, 0.0);
+        let (data, offset) = path_data_with_rounded_offset(&path, origin, Some(2), 0);
+        assert_eq!((offset.x, offset.y), (4.0, 5.0));
+        // 首个点相对取整后的偏移输出，绝对位置不变
+        assert!(data.starts_with("M-0.38 -0.39 "), "{}", data);
+    }
+
+    #[test]
+    fn translate_honors_coordinate_precision_zero() {
+        let pixels = shapes(60, 40);
+        let mut options = TraceOptions::new();
+        options.coordinate_precision = 0;
+        let svg = trace_rgba_with_options(&pixels, 60, 40, &options).unwrap();
+
+        let translates = transforms(&svg);
+        assert!(!translates.is_empty());
+        for translate in translates {
+            assert!(translate.starts_with("translate(") && !translate.contains('.'), "{}", translate);
+        }
+    }
+
+    /// 文本中各个数字的小数位数
+    fn decimals(text: &str) -> Vec<usize> {
+        text.split(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+            .filter(|t| !t.is_empty())
+            .map(|t| t.split('.').nth(1).map_or(0, str::len))
+            .collect()
+    }
+
+    #[test]
+    fn coord_and_transform_precision_are_independent() {
+        let pixels = shapes(60, 40);
+        // (coordinate_precision, coord_precision, transform_precision) -> (d 的最多小数位数, translate 的小数位数)
+        let cases = [
+            ((2, Some(1), None), (1, 1)),
+            ((2, Some(1), Some(0)), (1, 0)),
+            ((2, Some(3), Some(1)), (3, 1)),
+            ((2, None, Some(4)), (2, 4)),
+        ];
+        for ((coordinate_precision, coord_precision, transform_precision), (coord_digits, transform_digits)) in cases {
+            let mut options = TraceOptions::new();
+            options.coordinate_precision = coordinate_precision;
+            options.precision = PrecisionOptions { coord_precision, transform_precision, ..PrecisionOptions::default() };
+            let svg = trace_rgba_with_options(&pixels, 60, 40, &options).unwrap();
+
+            let coords: Vec<usize> = path_data(&svg).into_iter().flat_map(decimals).collect();
+            assert_eq!(coords.iter().max(), Some(&coord_digits), "{:?}", (coord_precision, transform_precision));
+            let translates: Vec<usize> = transforms(&svg).into_iter().flat_map(decimals).collect();
+            assert!(!translates.is_empty() && translates.iter().all(|&d| d == transform_digits), "{:?}", translates);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+//! 聚类前的像素预处理
+//! 
+//! 所有预处理都在 `cluster_image` 入口处原地作用于 RGBA 缓冲区，
+//! 因此各个追踪入口 (选项版、调色板、分块、GIF 等) 行为一致
+
+use visioncortex::Color;
+
+use crate::{palette, TracerConfig};
+
+/// 输入像素的 alpha 语义
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AlphaMode {
+    /// 非预乘 (straight) alpha，RGB 与 alpha 互相独立
+    Straight,
+    /// 预乘 alpha，RGB 已乘以 alpha/255
+    Premultiplied,
+}
+
+impl AlphaMode {
+    /// 解析 `"straight"` / `"premultiplied"`，未知取值回退为 straight
+    pub(crate) fn parse(name: &str) -> AlphaMode {
+        match name {
+            "premultiplied" => AlphaMode::Premultiplied,
+            _ => AlphaMode::Straight,
+        }
+    }
+}
+
+/// 反预乘：RGB 除以 alpha 还原为非预乘颜色
+/// 
+/// alpha 为 0 的像素没有可还原的颜色信息 (预乘后 RGB 必为 0)，保持原值不做除法
+fn unpremultiply(pixels: &mut [u8]) {
+    for px in pixels.chunks_exact_mut(4) {
+        let a = px[3] as u32;
+        if a == 0 || a == 255 {
+            continue;
+        }
+        for c in &mut px[..3] {
+            *c = ((*c as u32 * 255 + a / 2) / a).min(255) as u8;
+        }
+    }
+}
+
+/// 把掩码为 0 一侧 (小于 128) 的像素置为完全透明
+fn apply_mask(pixels: &mut [u8], mask: &[u8]) {
+    for (px, &m) in pixels.chunks_exact_mut(4).zip(mask) {
+        if m < 128 {
+            px[3] = 0;
+        }
+    }
+}
+
+/// 色相窗口：只保留色相落在窗口内、且饱和度与明度不低于下限的像素
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct HueWindow {
+    /// 窗口起点 (度，0~360)
+    pub start: f64,
+    /// 从起点沿色相增大方向的跨度 (度)，可以跨过 360°/0°
+    pub span: f64,
+    /// HSV 饱和度下限 (0~1)
+    pub min_saturation: f64,
+    /// HSV 明度下限 (0~1)
+    pub min_value: f64,
+}
+
+impl HueWindow {
+    /// 由起止色相构造：`end` 小于 `start` 时窗口跨过 0°，例如 (330, 30) 覆盖红色两侧；
+    /// 两者相差 360° 或以上时覆盖全部色相
+    pub(crate) fn new(start: f64, end: f64, min_saturation: f64, min_value: f64) -> HueWindow {
+        let span = if end - start >= 360.0 { 360.0 } else { (end - start).rem_euclid(360.0) };
+        HueWindow { start: start.rem_euclid(360.0), span, min_saturation, min_value }
+    }
+
+    fn contains(&self, px: &[u8]) -> bool {
+        let [r, g, b] = [px[0], px[1], px[2]].map(|c| c as f64 / 255.0);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        if max < self.min_value || max == 0.0 || delta / max < self.min_saturation {
+            return false;
+        }
+        // 无彩色 (delta 为 0) 没有色相，只有窗口覆盖全部色相时保留
+        if delta == 0.0 {
+            return self.span >= 360.0;
+        }
+        let hue = if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue - self.start).rem_euclid(360.0) <= self.span
+    }
+}
+
+/// 把色相窗口之外的像素置为完全透明
+fn apply_hue_window(pixels: &mut [u8], window: &HueWindow) {
+    for px in pixels.chunks_exact_mut(4) {
+        if !window.contains(px) {
+            px[3] = 0;
+        }
+    }
+}
+
+/// 把完全透明的像素统一替换为图中未出现过的不透明颜色，返回该颜色作为聚类的键控色
+/// 
+/// visioncortex 只丢弃与键控色完全相同的像素，且键控色不能是默认的透明黑，
+/// 因此需要找一个图中没有的颜色；图中没有透明像素时返回 `None`。
+/// 必须在颜色空间编码之后调用，保证键控色不会被再次变换。
+pub(crate) fn key_transparent(pixels: &mut [u8]) -> Option<Color> {
+    if !pixels.chunks_exact(4).any(|px| px[3] == 0) {
+        return None;
+    }
+    
+    // 24 位 RGB 的占用位图 (2MB)
+    let mut used = vec![0u64; (1 << 24) / 64];
+    for px in pixels.chunks_exact(4).filter(|px| px[3] != 0) {
+        let rgb = (px[0] as usize) << 16 | (px[1] as usize) << 8 | px[2] as usize;
+        used[rgb / 64] |= 1 << (rgb % 64);
+    }
+    let (word, bits) = used.iter().enumerate().find(|(_, &bits)| bits != u64::MAX)?;
+    let rgb = (word * 64 + bits.trailing_ones() as usize) as u32;
+    let key = Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+    
+    for px in pixels.chunks_exact_mut(4).filter(|px| px[3] == 0) {
+        px.copy_from_slice(&[key.r, key.g, key.b, 255]);
+    }
+    Some(key)
+}
+
+/// 反锐化掩模：`out = orig + amount * (orig - blur)`，只作用于 RGB
+/// 
+/// 模糊核为可分离的 `[1, 2, 1] / 4` (半径 1 像素)，边缘像素按复制边界处理。
+fn unsharp(pixels: &mut [u8], width: usize, amount: f64) {
+    let height = (pixels.len() / 4).checked_div(width).unwrap_or(0);
+    if height == 0 {
+        return;
+    }
+    
+    // 水平、垂直两遍模糊，结果放大 16 倍保存为整数
+    let idx = |x: usize, y: usize, c: usize| (y * width + x) * 4 + c;
+    let mut horizontal = vec![0u32; width * height * 3];
+    for y in 0..height {
+        for x in 0..width {
+            let (l, r) = (x.saturating_sub(1), (x + 1).min(width - 1));
+            for c in 0..3 {
+                horizontal[(y * width + x) * 3 + c] = pixels[idx(l, y, c)] as u32
+                    + 2 * pixels[idx(x, y, c)] as u32
+                    + pixels[idx(r, y, c)] as u32;
+            }
+        }
+    }
+    
+    let mut sharpened = pixels.to_vec();
+    for y in 0..height {
+        let (u, d) = (y.saturating_sub(1), (y + 1).min(height - 1));
+        for x in 0..width {
+            for c in 0..3 {
+                let h = |row: usize| horizontal[(row * width + x) * 3 + c];
+                let blur = (h(u) + 2 * h(y) + h(d)) as f64 / 16.0;
+                let orig = pixels[idx(x, y, c)] as f64;
+                sharpened[idx(x, y, c)] = (orig + amount * (orig - blur)).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    pixels.copy_from_slice(&sharpened);
+}
+
+/// 背景判定的逐通道容差
+const BACKGROUND_TOLERANCE: i32 = 16;
+
+/// 角落采样区域的边长 (像素)
+const CORNER_PATCH: usize = 4;
+
+pub(crate) fn within_tolerance(px: &[u8], color: Color) -> bool {
+    (px[0] as i32 - color.r as i32).abs() <= BACKGROUND_TOLERANCE
+        && (px[1] as i32 - color.g as i32).abs() <= BACKGROUND_TOLERANCE
+        && (px[2] as i32 - color.b as i32).abs() <= BACKGROUND_TOLERANCE
+}
+
+/// 从四个角落推测纯色背景
+/// 
+/// 取四角各 4x4 像素求平均，所有采样像素都与总平均色相差在容差内 (且不透明) 时
+/// 认为存在统一背景；角落颜色不一致 (如纹理背景) 时返回 `None`。
+pub(crate) fn detect_background(pixels: &[u8], width: usize, height: usize) -> Option<Color> {
+    if width == 0 || height == 0 || pixels.len() < width * height * 4 {
+        return None;
+    }
+    let patch_w = CORNER_PATCH.min(width);
+    let patch_h = CORNER_PATCH.min(height);
+    
+    let mut samples = Vec::with_capacity(patch_w * patch_h * 4);
+    for (x0, y0) in [(0, 0), (width - patch_w, 0), (0, height - patch_h), (width - patch_w, height - patch_h)] {
+        for y in y0..y0 + patch_h {
+            for x in x0..x0 + patch_w {
+                let i = (y * width + x) * 4;
+                samples.push(&pixels[i..i + 4]);
+            }
+        }
+    }
+    
+    if samples.iter().any(|px| px[3] < 255) {
+        return None;
+    }
+    let n = samples.len() as u32;
+    let mean = |c: usize| (samples.iter().map(|px| px[c] as u32).sum::<u32>() / n) as u8;
+    let background = Color::new(mean(0), mean(1), mean(2));
+    
+    samples.iter().all(|px| within_tolerance(px, background)).then_some(background)
+}
+
+/// 从图像边缘向内泛洪，把与背景色相近且与边缘连通的像素置为完全透明
+/// 
+/// 只移除与边缘连通的区域，主体内部与背景同色的高光等不受影响。
+fn remove_background(pixels: &mut [u8], width: usize, background: Color) {
+    let height = (pixels.len() / 4).checked_div(width).unwrap_or(0);
+    let mut visited = vec![false; width * height];
+    let mut stack = Vec::new();
+    
+    for x in 0..width {
+        stack.push((x, 0));
+        stack.push((x, height - 1));
+    }
+    for y in 0..height {
+        stack.push((0, y));
+        stack.push((width - 1, y));
+    }
+    
+    while let Some((x, y)) = stack.pop() {
+        let i = y * width + x;
+        if visited[i] || !within_tolerance(&pixels[i * 4..i * 4 + 4], background) {
+            continue;
+        }
+        visited[i] = true;
+        pixels[i * 4 + 3] = 0;
+        
+        if x > 0 { stack.push((x - 1, y)); }
+        if x + 1 < width { stack.push((x + 1, y)); }
+        if y > 0 { stack.push((x, y - 1)); }
+        if y + 1 < height { stack.push((x, y + 1)); }
+    }
+}
+
+/// 按配置依次执行所有预处理
+pub(crate) fn apply(pixels: &mut [u8], width: usize, cfg: &TracerConfig) {
+    // 背景检测基于原始输入，与 `trace_rgba_to_result` 报告的背景色保持一致
+    if cfg.auto_background {
+        let height = (pixels.len() / 4).checked_div(width).unwrap_or(0);
+        if let Some(background) = detect_background(pixels, width, height) {
+            remove_background(pixels, width, background);
+        }
+    }
+    if cfg.alpha_mode == AlphaMode::Premultiplied {
+        unpremultiply(pixels);
+    }
+    if cfg.unsharp_amount > 0.0 {
+        unsharp(pixels, width, cfg.unsharp_amount);
+    }
+    if let Some(mask) = &cfg.mask {
+        apply_mask(pixels, mask);
+    }
+    if let Some(window) = &cfg.hue_window {
+        apply_hue_window(pixels, window);
+    }
+    if cfg.transparent_as_color && !cfg.key_transparent {
+        for px in pixels.chunks_exact_mut(4).filter(|px| px[3] == 0) {
+            px.copy_from_slice(&[0, 0, 0, 0]);
+        }
+    }
+    // 调色板映射放在最后，作用于其他预处理完成后的颜色
+    if let Some(colors) = &cfg.palette {
+        palette::quantize_pixels(pixels, colors, cfg.color_space);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fill_rect, fills, numbers, rings, solid};
+    use crate::{trace_rgba_to_result, trace_rgba_with_options, TraceOptions};
+
+    /// 两遍 3×3 均值模糊，得到边缘柔和的输入
+    fn blurred(pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let mut out = pixels.to_vec();
+        for _ in 0..2 {
+            let source = out.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    let neighbours: Vec<usize> = (y.saturating_sub(1)..(y + 2).min(height))
+                        .flat_map(|yy| (x.saturating_sub(1)..(x + 2).min(width)).map(move |xx| yy * width + xx))
+                        .collect();
+                    for c in 0..3 {
+                        let sum: u32 = neighbours.iter().map(|&i| source[i * 4 + c] as u32).sum();
+                        out[(y * width + x) * 4 + c] = (sum / neighbours.len() as u32) as u8;
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// 深色路径到正方形四个角的最近距离之和 (像素)
+    fn corner_error(svg: &str, corners: [(f64, f64); 4]) -> f64 {
+        let tag = svg.split("<path").find(|t| t.contains("fill=\"#0") || t.contains("fill=\"#1")).unwrap();
+        let d = tag.split(" d=\"").nth(1).and_then(|rest| rest.split('"').next()).unwrap();
+        let offset = numbers(tag.split("translate(").nth(1).unwrap());
+        let points = rings(d, (offset[0], offset[1])).concat();
+        corners
+            .iter()
+            .map(|&(cx, cy)| points.iter().map(|&(x, y)| (x - cx).hypot(y - cy)).fold(f64::MAX, f64::min))
+            .sum()
+    }
+
+    #[test]
+    fn unsharp_steepens_a_step_edge() {
+        let mut row: Vec<u8> = [100, 100, 200, 200].iter().flat_map(|&v| [v, v, v, 255]).collect();
+        unsharp(&mut row, 4, 1.0);
+        let red: Vec<u8> = row.chunks_exact(4).map(|px| px[0]).collect();
+        assert_eq!(red, [100, 75, 225, 200]);
+    }
+
+    #[test]
+    fn unsharp_recovers_corners_of_a_blurred_square() {
+        let mut square = solid(48, 48, [255, 255, 255, 255]);
+        fill_rect(&mut square, 48, (12, 12), (36, 36), [0, 0, 0, 255]);
+        let pixels = blurred(&square, 48, 48);
+        let corners = [(12.0, 12.0), (36.0, 12.0), (36.0, 36.0), (12.0, 36.0)];
+
+        let mut options = TraceOptions::new();
+        let soft = trace_rgba_with_options(&pixels, 48, 48, &options).unwrap();
+        options.unsharp_amount = 1.0;
+        let sharp = trace_rgba_with_options(&pixels, 48, 48, &options).unwrap();
+
+        // 模糊边缘原本形成一圈灰色过渡层，锐化后消失，角点也更贴近原正方形
+        assert_eq!(fills(&soft).len(), 3, "{}", soft);
+        assert_eq!(fills(&sharp).len(), 2, "{}", sharp);
+        assert!(corner_error(&sharp, corners) < corner_error(&soft, corners));
+    }
+
+    /// 白底上一个蓝色圆形主体，中间有一块与背景同色的高光
+    fn subject_on_white() -> Vec<u8> {
+        let mut pixels = solid(40, 40, [255, 255, 255, 255]);
+        for y in 0..40 {
+            for x in 0..40 {
+                if (x as f64 - 19.5).hypot(y as f64 - 19.5) < 12.0 {
+                    pixels[(y * 40 + x) * 4..(y * 40 + x) * 4 + 4].copy_from_slice(&[20, 60, 200, 255]);
+                }
+            }
+        }
+        fill_rect(&mut pixels, 40, (18, 18), (22, 22), [255, 255, 255, 255]);
+        pixels
+    }
+
+    #[test]
+    fn solid_white_background_is_detected_and_removed() {
+        let mut pixels = subject_on_white();
+        // 角落的轻微噪声在容差内
+        pixels[..4].copy_from_slice(&[250, 252, 255, 255]);
+        let background = detect_background(&pixels, 40, 40).unwrap();
+        assert!(within_tolerance(&[255, 255, 255, 255], background));
+
+        remove_background(&mut pixels, 40, background);
+        let alpha = |x: usize, y: usize| pixels[(y * 40 + x) * 4 + 3];
+        assert_eq!((alpha(0, 0), alpha(39, 20), alpha(19, 10), alpha(20, 20)), (0, 0, 255, 255));
+
+        let mut options = TraceOptions::new();
+        options.auto_background = true;
+        let result = trace_rgba_to_result(&subject_on_white(), 40, 40, &options).unwrap();
+        assert_eq!(result.background.as_deref(), Some("#ffffff"));
+        assert_eq!(fills(&result.svg), ["#143cc8", "#ffffff"], "{}", result.svg);
+    }
+
+    #[test]
+    fn textured_background_is_left_alone() {
+        // 棋盘格纹理：角落颜色互相矛盾
+        let mut pixels = subject_on_white();
+        for y in 0..40 {
+            for x in 0..40 {
+                if (x / 2 + y / 2) % 2 == 0 && pixels[(y * 40 + x) * 4] == 255 {
+                    pixels[(y * 40 + x) * 4..(y * 40 + x) * 4 + 4].copy_from_slice(&[120, 120, 120, 255]);
+                }
+            }
+        }
+        assert_eq!(detect_background(&pixels, 40, 40), None);
+
+        let mut options = TraceOptions::new();
+        options.auto_background = true;
+        let result = trace_rgba_to_result(&pixels, 40, 40, &options).unwrap();
+        assert_eq!(result.background, None);
+        assert_eq!(result.svg, trace_rgba_with_options(&pixels, 40, 40, &TraceOptions::new()).unwrap());
+    }
+
+    #[test]
+    fn hue_window_wraps_across_zero() {
+        let window = HueWindow::new(330.0, 30.0, 0.15, 0.15);
+        let hue_of = |rgb: [u8; 3]| window.contains(&[rgb[0], rgb[1], rgb[2], 255]);
+        // 0°、350° (偏品红的红)、20° 与 90°、200°、灰色
+        assert!(hue_of([220, 20, 20]) && hue_of([220, 20, 50]) && hue_of([220, 90, 20]));
+        assert!(!hue_of([120, 220, 20]) && !hue_of([20, 150, 220]) && !hue_of([128, 128, 128]));
+
+        let complement = HueWindow::new(30.0, 330.0, 0.15, 0.15);
+        assert!(!complement.contains(&[220, 20, 20, 255]) && complement.contains(&[20, 150, 220, 255]));
+        assert!(HueWindow::new(0.0, 360.0, 0.0, 0.0).contains(&[128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn hue_window_extracts_red_elements() {
+        // 白底上红、绿、蓝三个色块，以及一个 350° 的红色
+        let mut pixels = solid(60, 20, [255, 255, 255, 255]);
+        fill_rect(&mut pixels, 60, (2, 4), (12, 16), [220, 20, 20, 255]);
+        fill_rect(&mut pixels, 60, (16, 4), (26, 16), [30, 180, 40, 255]);
+        fill_rect(&mut pixels, 60, (30, 4), (40, 16), [20, 60, 220, 255]);
+        fill_rect(&mut pixels, 60, (44, 4), (54, 16), [210, 20, 60, 255]);
+        let mut options = TraceOptions::new();
+        options.hue_range_start = Some(330.0);
+        options.hue_range_end = Some(30.0);
+        let svg = trace_rgba_with_options(&pixels, 60, 20, &options).unwrap();
+        let mut red = fills(&svg);
+        red.sort();
+        assert_eq!(red, ["#d2143c", "#dc1414"], "{}", svg);
+    }
+}
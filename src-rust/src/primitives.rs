@@ -0,0 +1,108 @@
+//! 图元识别：把接近矩形/圆/椭圆的路径输出为 `<rect>`/`<circle>`/`<ellipse>`
+//! 
+//! 只处理没有孔洞的单一轮廓。以包围盒为模板做拟合：
+//! - 矩形：轮廓上每个采样点到包围盒最近边的距离
+//! - 椭圆：以包围盒中心为圆心、半宽/半高为半径，采样点的径向偏差
+//! 
+//! 最大残差不超过 `max(FIT_TOLERANCE_PX, 短边 * FIT_TOLERANCE_RATIO)` 时认为拟合成功，
+//! 否则返回 `None`，调用方回退为普通 `<path>` 输出。
+
+use std::fmt::Write;
+use visioncortex::{CompoundPath, PointF64};
+
+use crate::format_number;
+use crate::geometry::{densify, flatten_element};
+
+/// 拟合容差的绝对下限（像素）
+const FIT_TOLERANCE_PX: f64 = 0.75;
+/// 拟合容差相对短边的比例
+const FIT_TOLERANCE_RATIO: f64 = 0.03;
+/// 小于该尺寸（像素）的形状不做识别，避免把噪点识别成图元
+const MIN_PRIMITIVE_SIZE: f64 = 3.0;
+
+pub(crate) enum Primitive {
+    Rect { x: f64, y: f64, width: f64, height: f64 },
+    Circle { cx: f64, cy: f64, r: f64 },
+    Ellipse { cx: f64, cy: f64, rx: f64, ry: f64 },
+}
+
+/// 尝试将路径识别为图元，拟合失败时返回 `None`
+pub(crate) fn detect(path: &CompoundPath) -> Option<Primitive> {
+    // 带孔洞的复合路径无法用单个图元表达
+    if path.paths.len() != 1 {
+        return None;
+    }
+    
+    // 加密采样，避免只检查顶点而漏掉边中间的偏差
+    let points = densify(&flatten_element(&path.paths[0], 8), 1.0);
+    if points.len() < 4 {
+        return None;
+    }
+    
+    let (mut left, mut top) = (f64::MAX, f64::MAX);
+    let (mut right, mut bottom) = (f64::MIN, f64::MIN);
+    for p in &points {
+        left = left.min(p.x);
+        top = top.min(p.y);
+        right = right.max(p.x);
+        bottom = bottom.max(p.y);
+    }
+    let (width, height) = (right - left, bottom - top);
+    if width < MIN_PRIMITIVE_SIZE || height < MIN_PRIMITIVE_SIZE {
+        return None;
+    }
+    
+    let tolerance = FIT_TOLERANCE_PX.max(width.min(height) * FIT_TOLERANCE_RATIO);
+    
+    let rect_residual = points.iter()
+        .map(|p| (p.x - left).abs()
+            .min((p.x - right).abs())
+            .min((p.y - top).abs())
+            .min((p.y - bottom).abs()))
+        .fold(0.0, f64::max);
+    if rect_residual <= tolerance {
+        return Some(Primitive::Rect { x: left, y: top, width, height });
+    }
+    
+    let (cx, cy) = ((left + right) / 2.0, (top + bottom) / 2.0);
+    let (rx, ry) = (width / 2.0, height / 2.0);
+    let ellipse_residual = points.iter()
+        .map(|p| {
+            let d = ((p.x - cx) / rx).hypot((p.y - cy) / ry);
+            (d - 1.0).abs() * rx.min(ry)
+        })
+        .fold(0.0, f64::max);
+    if ellipse_residual <= tolerance {
+        if (rx - ry).abs() <= tolerance {
+            return Some(Primitive::Circle { cx, cy, r: (rx + ry) / 2.0 });
+        }
+        return Some(Primitive::Ellipse { cx, cy, rx, ry });
+    }
+    
+    None
+}
+
+impl Primitive {
+    /// 写出图元元素，`paint` 为着色属性，`origin` 为整体坐标偏移
+    pub(crate) fn write_svg(&self, svg: &mut String, paint: &str, origin: PointF64, precision: Option<u32>) {
+        let f = |v: f64| format_number(v, precision);
+        match *self {
+            Primitive::Rect { x, y, width, height } => writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                f(x + origin.x), f(y + origin.y), f(width), f(height), paint
+            ),
+            Primitive::Circle { cx, cy, r } => writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}" {}/>"#,
+                f(cx + origin.x), f(cy + origin.y), f(r), paint
+            ),
+            Primitive::Ellipse { cx, cy, rx, ry } => writeln!(
+                svg,
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" {}/>"#,
+                f(cx + origin.x), f(cy + origin.y), f(rx), f(ry), paint
+            ),
+        }
+        .ok();
+    }
+}
@@ -0,0 +1,66 @@
+//! 渐进式追踪：大图先按缩小的尺寸快速出一版预览，再按原尺寸完成最终追踪
+//!
+//! 解码本身仍需完整的图片字节 (`image` 不支持边下载边解码)，
+//! 但缩小后的聚类与拟合耗时只有原尺寸的一小部分，可尽早给用户反馈。
+
+use wasm_bindgen::prelude::*;
+use visioncortex::ColorImage;
+
+use crate::memory::halve;
+use crate::{build_svg, check_empty, cluster_and_fit, decode, lines, reset_rng, scale_compound_path, warnings, TraceOptions};
+
+/// 预览图的长边上限，超过时逐次缩小一半
+const PREVIEW_MAX_SIDE: usize = 512;
+
+/// 渐进式矢量化图片字节：先以缩小的尺寸追踪一版预览并通过 `on_preview(svg)` 回调，再按原尺寸追踪并返回最终 SVG
+///
+/// 预览把图像逐次缩小一半直到长边不超过 512 像素，追踪后坐标放大回原尺寸，
+/// `viewBox` 与最终结果一致，可以直接替换显示。最终结果与先解码再调用
+/// `trace_rgba_with_options` 逐字节相同。
+///
+/// 以下情况不生成预览、回调不会被调用：图像长边本身不超过 512 像素 (直接追踪已经足够快)；
+/// 开启了 `line_extraction` (线稿输出无法按比例放大)。预览没有路径时不受 `fail_on_empty` 影响，
+/// 也不报告 `empty` 警告，只有最终结果会检查。回调抛出的异常会中止追踪并原样返回。
+#[wasm_bindgen]
+pub fn trace_image_progressive(
+    image_bytes: &[u8],
+    options: &TraceOptions,
+    on_preview: &js_sys::Function,
+) -> Result<String, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+
+    let rgba = decode::load_rgba(image_bytes)?;
+    let w = rgba.width() as usize;
+    let h = rgba.height() as usize;
+    let pixels = rgba.into_raw();
+
+    let cfg = options.to_config();
+    if let Some(threshold) = cfg.line_extraction {
+        return Ok(lines::trace_lines(&pixels, w, h, threshold, &cfg));
+    }
+
+    let mut preview = None;
+    let (mut cw, mut ch) = (w, h);
+    let mut scale = 1usize;
+    while cw.max(ch) > PREVIEW_MAX_SIDE && cw >= 2 && ch >= 2 {
+        let (small, sw, sh) = halve(preview.as_deref().unwrap_or(&pixels), cw, ch);
+        (preview, cw, ch) = (Some(small), sw, sh);
+        scale *= 2;
+    }
+
+    if let Some(small) = preview {
+        reset_rng();
+        let mut path_results = cluster_and_fit(ColorImage { pixels: small, width: cw, height: ch }, &cfg);
+        for (path, _) in &mut path_results {
+            *path = scale_compound_path(path, scale as f64);
+        }
+        let svg = build_svg(w, h, &path_results, &cfg);
+        on_preview.call1(&JsValue::NULL, &JsValue::from_str(&svg))?;
+    }
+
+    reset_rng();
+    let path_results = cluster_and_fit(ColorImage { pixels, width: w, height: h }, &cfg);
+    check_empty(&path_results, &cfg)?;
+
+    Ok(build_svg(w, h, &path_results, &cfg))
+}
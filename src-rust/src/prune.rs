@@ -0,0 +1,103 @@
+//! 层次剪枝：在已有的聚类层次上减少颜色数，不必重新聚类或拟合
+
+use visioncortex::color_clusters::{ClusterIndex, ClustersView};
+use visioncortex::{Color, ColorSum, CompoundPath};
+
+use crate::color_space::{self, ColorSpace};
+
+/// 每条输出路径在聚类层次中的位置，下标与 `fit_clusters` 的绘制顺序一致
+#[derive(Clone)]
+pub(crate) struct Hierarchy {
+    /// 直接包含该路径的下层路径，`None` 表示最底层
+    parents: Vec<Option<usize>>,
+    /// 路径自身露出部分的颜色累加值 (聚类颜色空间)，用于合并后重新求平均色
+    residues: Vec<ColorSum>,
+}
+
+impl Hierarchy {
+    /// 记录各输出聚类的父子关系
+    ///
+    /// 叠放模式下聚类的区域包含画在其上的全部子聚类，区域之间要么嵌套、要么不相交。
+    /// 按绘制顺序逐个铺色，铺到某个聚类时它首个像素上已有的聚类就是直接包含它的父聚类。
+    pub(crate) fn new(view: &ClustersView) -> Hierarchy {
+        let order: Vec<ClusterIndex> = view.clusters_output.iter().rev().copied().collect();
+        let mut cover: Vec<Option<usize>> = vec![None; view.width as usize * view.height as usize];
+        let mut parents = Vec::with_capacity(order.len());
+        for (i, &index) in order.iter().enumerate() {
+            let cluster = view.get_cluster(index);
+            parents.push(cluster.indices.first().and_then(|&p| cover[p as usize]));
+            for &p in cluster.iter() {
+                cover[p as usize] = Some(i);
+            }
+        }
+        let residues = order.iter().map(|&index| view.get_cluster(index).residue_sum).collect();
+        Hierarchy { parents, residues }
+    }
+
+    /// 只保留 `keep` 为 `true` 的路径，被移除路径的子路径改挂到最近的保留祖先
+    pub(crate) fn retain(&mut self, keep: &[bool]) {
+        let mut new_index = vec![None; keep.len()];
+        let mut next = 0;
+        for (i, &k) in keep.iter().enumerate() {
+            if k {
+                new_index[i] = Some(next);
+                next += 1;
+            }
+        }
+        let mut resolved: Vec<Option<usize>> = Vec::with_capacity(keep.len());
+        for i in 0..keep.len() {
+            // 父路径总在子路径之前，其保留祖先已经求出
+            let parent = self.parents[i].and_then(|p| if keep[p] { new_index[p] } else { resolved[p] });
+            resolved.push(if keep[i] { new_index[i] } else { parent });
+            self.parents[i] = parent;
+        }
+        self.parents = self.parents.iter().zip(keep).filter(|(_, &k)| k).map(|(&p, _)| p).collect();
+        self.residues = self.residues.iter().zip(keep).filter(|(_, &k)| k).map(|(&r, _)| r).collect();
+    }
+
+    /// 把与父路径颜色差 (RGB 各通道差之和) 不超过 `layer_difference` 的路径并入父路径
+    ///
+    /// 从最上层向下处理，子路径先于父路径判定，并入的颜色累加到父路径上，父路径改用合并后的
+    /// 平均色。父路径的区域本来就覆盖子路径，因此只需删去子路径，其余路径的几何保持不变。
+    /// 返回剪枝后的路径与对应的层次。
+    pub(crate) fn prune(
+        &self,
+        paths: &[(CompoundPath, Color)],
+        layer_difference: i32,
+        color_space: ColorSpace,
+    ) -> (Vec<(CompoundPath, Color)>, Hierarchy) {
+        let mut residues = self.residues.clone();
+        let mut keep = vec![true; paths.len()];
+        let mut recolored = vec![false; paths.len()];
+        for i in (0..paths.len()).rev() {
+            let Some(parent) = self.parents[i] else { continue };
+            let (a, b) = (residues[i].average(), residues[parent].average());
+            let diff = a.r.abs_diff(b.r) as i32 + a.g.abs_diff(b.g) as i32 + a.b.abs_diff(b.b) as i32;
+            if diff <= layer_difference {
+                let residue = residues[i];
+                residues[parent].merge(&residue);
+                keep[i] = false;
+                recolored[parent] = true;
+            }
+        }
+        let pruned = paths
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| keep[i])
+            .map(|(i, (path, color))| {
+                let color = if recolored[i] {
+                    match color_space {
+                        ColorSpace::Rgb => residues[i].average(),
+                        ColorSpace::Lab => color_space::decode_lab_color(residues[i].average()),
+                    }
+                } else {
+                    *color
+                };
+                (path.clone(), color)
+            })
+            .collect();
+        let mut hierarchy = Hierarchy { parents: self.parents.clone(), residues };
+        hierarchy.retain(&keep);
+        (pruned, hierarchy)
+    }
+}
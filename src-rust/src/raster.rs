@@ -0,0 +1,406 @@
+//! 栅格输出：调试/预览类功能直接在 WASM 内编码为 PNG、JPEG 或 WebP
+//! 
+//! 由 `raster` 特性控制，不需要这些功能的构建可以不编译此模块。
+//! 编码器均为 `image` 库的纯 Rust 实现：PNG、JPEG (有损，可调质量) 与 WebP (仅无损)；
+//! 有损 WebP 依赖 C 库 libwebp，无法编译到 WASM，因此不提供。
+
+use wasm_bindgen::prelude::*;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::{ColorType, ImageEncoder};
+use visioncortex::{Color, CompoundPath, PointF64};
+
+use crate::geometry::flatten_element_adaptive;
+use crate::{build_config, check_rgba_len, cluster_and_fit, cluster_color, cluster_image, reset_rng, warnings, TraceOptions};
+
+/// 将 RGBA（非预乘 alpha）像素编码为 PNG 字节
+/// 
+/// 使用快速压缩档位：预览图只在内存中短暂存在，编码速度比体积更重要。
+/// 512x512 的预览在原生环境 (release) 下编码约 1.5 毫秒。
+pub(crate) fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut png = Vec::with_capacity(rgba.len() / 4);
+    PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::Sub)
+        .write_image(rgba, width, height, ColorType::Rgba8)
+        .map_err(|e| format!("PNG 编码失败: {}", e))?;
+    Ok(png)
+}
+
+/// 将单通道灰度像素编码为 PNG 字节，压缩档位与 `encode_png` 相同
+fn encode_gray_png(gray: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut png = Vec::with_capacity(gray.len() / 4);
+    PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::Sub)
+        .write_image(gray, width, height, ColorType::L8)
+        .map_err(|e| format!("PNG 编码失败: {}", e))?;
+    Ok(png)
+}
+
+/// 按 `format` 编码 RGBA 像素：`"png"`、`"jpeg"` (`quality` 1~100) 或 `"webp"` (无损，忽略 `quality`)
+/// 
+/// JPEG 没有 alpha 通道，先与白色背景合成再编码。
+fn encode_image(rgba: &[u8], width: u32, height: u32, format: &str, quality: u8) -> Result<Vec<u8>, String> {
+    match format {
+        "png" => encode_png(rgba, width, height),
+        "jpeg" | "jpg" => {
+            let rgb: Vec<u8> = rgba
+                .chunks_exact(4)
+                .flat_map(|px| {
+                    let alpha = px[3] as u32;
+                    [0, 1, 2].map(|c| ((px[c] as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8)
+                })
+                .collect();
+            let mut jpeg = Vec::with_capacity(rgb.len() / 8);
+            JpegEncoder::new_with_quality(&mut jpeg, quality.clamp(1, 100))
+                .write_image(&rgb, width, height, ColorType::Rgb8)
+                .map_err(|e| format!("JPEG 编码失败: {}", e))?;
+            Ok(jpeg)
+        }
+        "webp" => {
+            let mut webp = Vec::with_capacity(rgba.len() / 4);
+            WebPEncoder::new_lossless(&mut webp)
+                .write_image(rgba, width, height, ColorType::Rgba8)
+                .map_err(|e| format!("WebP 编码失败: {}", e))?;
+            Ok(webp)
+        }
+        other => Err(format!("不支持的栅格格式 \"{}\"，可选 \"png\"、\"jpeg\"、\"webp\"", other)),
+    }
+}
+
+/// 调试调色板的默认种子
+const DEFAULT_PALETTE_SEED: u64 = 1;
+
+/// 调试输出：把每个聚类用随机颜色填充后返回 PNG，便于观察分割是否过细/过粗
+/// 
+/// 参数含义与 `trace_rgba_parallel` 相同，只执行聚类阶段，不做曲线拟合。
+/// 
+/// `palette_seed` 为调试配色的随机种子 (省略时为 1)，与追踪本身使用的随机数互不影响。
+/// 每个聚类的颜色由种子与聚类中最靠前 (按扫描顺序) 的像素位置共同决定，而不是按绘制顺序依次取色，
+/// 因此调整参数后，覆盖同一位置的聚类仍显示同一种颜色，便于对比前后的分割差异。
+/// PNG 按规范存储非预乘 alpha，浏览器解码时自行处理，因此没有预乘选项。
+#[wasm_bindgen]
+pub fn debug_clusters(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    color_count: u8,
+    path_precision: u8,
+    corner_threshold: u8,
+    filter_speckle: u32,
+    palette_seed: Option<u64>,
+) -> Result<Vec<u8>, JsValue> {
+    reset_rng();
+    let palette_seed = palette_seed.unwrap_or(DEFAULT_PALETTE_SEED);
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    
+    let cfg = build_config(color_count, path_precision, corner_threshold, filter_speckle);
+    let color_image = visioncortex::ColorImage {
+        pixels: rgba_data.to_vec(),
+        width: w,
+        height: h,
+    };
+    let clusters = cluster_image(color_image, &cfg);
+    let view = clusters.view();
+    
+    // 按绘制顺序用随机颜色覆盖，后绘制的聚类在上层
+    let mut image = visioncortex::ColorImage::new_w_h(w, h);
+    for &cluster_index in view.clusters_output.iter().rev() {
+        let cluster = view.get_cluster(cluster_index);
+        let first_pixel = cluster.iter().min().copied().unwrap_or(0) as u64;
+        let mut rng = fastrand::Rng::with_seed(palette_seed ^ first_pixel.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let color = Color::new(rng.u8(..), rng.u8(..), rng.u8(..));
+        cluster.render_to_color_image_with_color(&view, &mut image, &color);
+    }
+    
+    encode_png(&image.pixels, width, height).map_err(|e| JsValue::from_str(&e))
+}
+
+/// 栅格预览：每个聚类用其输出颜色填充后按 `format` 编码，用于缩略图等无需矢量的场景
+/// 
+/// 只执行聚类阶段，不做曲线拟合，色块边缘是像素级的，与 SVG 渲染结果的整体配色一致。
+/// `format` 可选 `"png"`、`"jpeg"` (`quality` 1~100，省略时为 80，透明区域合成为白色) 与
+/// `"webp"` (只支持无损编码，`quality` 被忽略，体积通常仍小于 PNG)；其它取值返回错误。
+/// 线稿提取等只影响 SVG 输出的选项在这里被忽略。
+#[wasm_bindgen]
+pub fn render_trace_preview(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    format: &str,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    
+    let cfg = options.to_config();
+    let color_image = visioncortex::ColorImage {
+        pixels: rgba_data.to_vec(),
+        width: w,
+        height: h,
+    };
+    let clusters = cluster_image(color_image, &cfg);
+    let view = clusters.view();
+    
+    // 与 SVG 相同的绘制顺序，后绘制的聚类在上层
+    let mut image = visioncortex::ColorImage::new_w_h(w, h);
+    for &cluster_index in view.clusters_output.iter().rev() {
+        let cluster = view.get_cluster(cluster_index);
+        cluster.render_to_color_image_with_color(&view, &mut image, &cluster_color(cluster, &cfg));
+    }
+    
+    encode_image(&image.pixels, width, height, format, quality.unwrap_or(80)).map_err(|e| JsValue::from_str(&e))
+}
+
+/// 把曲线展平为折线时允许的最大偏差 (像素)，远小于一个子采样间距
+const PREVIEW_FLATNESS: f64 = 0.05;
+
+/// `render_svg_preview` 每个方向的默认子采样数
+const DEFAULT_SAMPLE_COUNT: u8 = 4;
+
+/// 按非零环绕规则把一条路径以 `samples` x `samples` 超采样填充到预乘 alpha 的画布上
+fn fill_path(canvas: &mut [[f32; 4]], width: usize, height: usize, path: &CompoundPath, color: &Color, samples: usize) {
+    let mut edges: Vec<(PointF64, PointF64)> = Vec::new();
+    for element in path.iter() {
+        let points = flatten_element_adaptive(element, PREVIEW_FLATNESS);
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            if a.y != b.y {
+                edges.push((a, b));
+            }
+        }
+    }
+    let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+    for (a, b) in &edges {
+        min_y = min_y.min(a.y.min(b.y));
+        max_y = max_y.max(a.y.max(b.y));
+    }
+    if edges.is_empty() {
+        return;
+    }
+
+    let s = samples as f64;
+    let full = (samples * samples) as f32;
+    let rgb = [color.r, color.g, color.b].map(|c| c as f32 / 255.0);
+    let row_start = min_y.floor().max(0.0) as usize;
+    let row_end = (max_y.ceil().max(0.0) as usize).min(height);
+    let mut coverage = vec![0u16; width];
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    for row in row_start..row_end {
+        coverage.iter_mut().for_each(|c| *c = 0);
+        for sub in 0..samples {
+            let y = row as f64 + (sub as f64 + 0.5) / s;
+            crossings.clear();
+            for (a, b) in &edges {
+                if (a.y <= y) != (b.y <= y) {
+                    let x = a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x);
+                    crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                if winding == 0 {
+                    continue;
+                }
+                // 子采样列 c 的中心为 (c + 0.5) / s，落在 [x0, x1) 内的列被覆盖
+                let first = (pair[0].0 * s - 0.5).ceil().max(0.0) as usize;
+                let last = ((pair[1].0 * s - 0.5).ceil().max(0.0) as usize).min(width * samples);
+                for column in first..last {
+                    coverage[column / samples] += 1;
+                }
+            }
+        }
+        for (pixel, &covered) in canvas[row * width..(row + 1) * width].iter_mut().zip(&coverage) {
+            if covered == 0 {
+                continue;
+            }
+            let alpha = covered as f32 / full;
+            for c in 0..3 {
+                pixel[c] = rgb[c] * alpha + pixel[c] * (1.0 - alpha);
+            }
+            pixel[3] = alpha + pixel[3] * (1.0 - alpha);
+        }
+    }
+}
+
+/// 追踪并把拟合出的路径按绘制顺序栅格化到原图大小、预乘 alpha 的画布上
+fn rasterize_trace(rgba_data: &[u8], w: usize, h: usize, options: &TraceOptions, sample_count: Option<u8>) -> Vec<[f32; 4]> {
+    let cfg = options.to_config();
+    let color_image = visioncortex::ColorImage {
+        pixels: rgba_data.to_vec(),
+        width: w,
+        height: h,
+    };
+    let path_results = cluster_and_fit(color_image, &cfg);
+    rasterize_paths(&path_results, w, h, sample_count)
+}
+
+/// 把拟合结果按绘制顺序栅格化到 `w` x `h`、预乘 alpha 的画布上，`sample_count` 见 `render_svg_preview`
+pub(crate) fn rasterize_paths(
+    path_results: &[(CompoundPath, Color)],
+    w: usize,
+    h: usize,
+    sample_count: Option<u8>,
+) -> Vec<[f32; 4]> {
+    let samples = sample_count.unwrap_or(DEFAULT_SAMPLE_COUNT).clamp(1, 8) as usize;
+    let mut canvas = vec![[0.0f32; 4]; w * h];
+    for (path, color) in path_results {
+        fill_path(&mut canvas, w, h, path, color, samples);
+    }
+    canvas
+}
+
+/// SVG 渲染预览：追踪后把拟合出的路径直接栅格化，按 `format` 编码，结果与浏览器渲染 SVG 基本一致
+/// 
+/// 与 `render_trace_preview` 只填充聚类像素不同，这里按曲线拟合后的路径填充，能看出平滑、
+/// 拐角等拟合参数的效果。`sample_count` 为每个像素在每个方向上的子采样数 (1~8，省略时为 4)：
+/// 1 不做抗锯齿，边缘呈锯齿状，适合快速生成图库缩略图；4 即每像素 4x4 = 16 个采样点，边缘平滑，
+/// 适合大图展示。栅格化耗时大致与 `sample_count` 成正比 (每个子采样行都要与全部边求交)，
+/// 边缘很多时更接近平方关系；编码耗时不受影响。
+/// 
+/// `format` 与 `quality` 的含义与 `render_trace_preview` 相同。路径按 SVG 输出的顺序叠放，
+/// 使用非零环绕规则，坐标为原始画布坐标：`crop_to_content`、`origin`、`normalize_to` 等只改变
+/// SVG 坐标系的选项被忽略，描边模式的路径同样按填充渲染，`line_extraction` 不生效。
+#[wasm_bindgen]
+pub fn render_svg_preview(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    format: &str,
+    quality: Option<u8>,
+    sample_count: Option<u8>,
+) -> Result<Vec<u8>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    
+    let canvas = rasterize_trace(rgba_data, w, h, options, sample_count);
+    // PNG 等格式按规范存储非预乘 alpha
+    let pixels = canvas_to_rgba(&canvas, false);
+    encode_image(&pixels, width, height, format, quality.unwrap_or(80)).map_err(|e| JsValue::from_str(&e))
+}
+
+/// 与 `render_svg_preview` 相同的栅格化，但不编码，直接返回原图大小的 RGBA 缓冲区
+///
+/// 可直接交给 `putImageData` 或上传为 WebGL 纹理，省去编码与解码。抗锯齿的边缘像素为半透明，
+/// `options.premultiply_output` 决定颜色是否预乘 alpha (默认非预乘，见该选项)。
+#[wasm_bindgen]
+pub fn render_svg_preview_rgba(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    sample_count: Option<u8>,
+) -> Result<Vec<u8>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    
+    let canvas = rasterize_trace(rgba_data, w, h, options, sample_count);
+    Ok(canvas_to_rgba(&canvas, options.premultiply_output))
+}
+
+/// 预乘 alpha 的浮点画布转为 8 位 RGBA，`premultiplied` 为 `false` 时转回非预乘
+fn canvas_to_rgba(canvas: &[[f32; 4]], premultiplied: bool) -> Vec<u8> {
+    canvas
+        .iter()
+        .flat_map(|&[r, g, b, a]| {
+            let channel = |c: f32| {
+                let straight = if premultiplied { c } else if a > 0.0 { c / a } else { 0.0 };
+                (straight * 255.0).round().clamp(0.0, 255.0) as u8
+            };
+            [channel(r), channel(g), channel(b), (a * 255.0).round() as u8]
+        })
+        .collect()
+}
+
+/// 覆盖范围蒙版：追踪后把全部路径的并集栅格化为单通道 PNG，有路径覆盖处为白色，透明处为黑色
+/// 
+/// 蒙版与原图同样大小 (`width` x `height`，即 SVG 的 `viewBox` 按 1 单位 1 像素渲染)，
+/// 可直接作为下游编辑器的图层蒙版与原图或 SVG 对齐。`sample_count` 与 `render_svg_preview` 相同
+/// (1~8，省略时为 4)：大于 1 时边缘像素为按覆盖比例的灰度，1 则只有黑白两值。
+/// 坐标系的处理与 `render_svg_preview` 相同，`crop_to_content`、`origin`、`normalize_to` 被忽略。
+/// 
+/// 叠放模式下的底层色块通常铺满整张画布，只有开启了会丢弃像素的选项
+/// (`auto_background`、`hue_range_start`/`hue_range_end` 等) 时才会出现透明区域。
+#[wasm_bindgen]
+pub fn trace_rgba_to_coverage_mask(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    sample_count: Option<u8>,
+) -> Result<Vec<u8>, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+    
+    check_rgba_len(rgba_data, w, h)?;
+    
+    let canvas = rasterize_trace(rgba_data, w, h, options, sample_count);
+    let coverage: Vec<u8> = canvas.iter().map(|px| (px[3] * 255.0).round() as u8).collect();
+    encode_gray_png(&coverage, width, height).map_err(|e| JsValue::from_str(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::solid;
+
+    /// 透明背景上的实心圆
+    fn disc_on_transparent(size: usize) -> Vec<u8> {
+        let mut pixels = solid(size, size, [0, 0, 0, 0]);
+        let r = size as f64 * 0.35;
+        for y in 0..size {
+            for x in 0..size {
+                let (dx, dy) = (x as f64 + 0.5 - size as f64 / 2.0, y as f64 + 0.5 - size as f64 / 2.0);
+                if dx * dx + dy * dy < r * r {
+                    pixels[(y * size + x) * 4..(y * size + x) * 4 + 4].copy_from_slice(&[40, 200, 120, 255]);
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn premultiplied_preview_scales_edge_colors_by_alpha() {
+        let pixels = disc_on_transparent(40);
+        let mut options = TraceOptions::new();
+        let straight = render_svg_preview_rgba(&pixels, 40, 40, &options, None).unwrap();
+        options.premultiply_output = true;
+        let premultiplied = render_svg_preview_rgba(&pixels, 40, 40, &options, None).unwrap();
+
+        let mut partial = 0;
+        for (s, p) in straight.chunks_exact(4).zip(premultiplied.chunks_exact(4)) {
+            assert_eq!(s[3], p[3]);
+            let a = s[3] as f64 / 255.0;
+            for c in 0..3 {
+                assert!((p[c] as f64 - s[c] as f64 * a).abs() <= 1.0, "{:?} {:?}", s, p);
+            }
+            if s[3] == 255 {
+                assert_eq!(s, p);
+            } else if s[3] > 0 {
+                partial += 1;
+                // 非预乘的边缘像素保持圆的原色
+                assert!((s[1] as i32 - 200).abs() <= 2, "{:?}", s);
+            }
+        }
+        assert!(partial > 0);
+    }
+}
@@ -0,0 +1,59 @@
+//! 描边揭示动画：路径按绘制顺序逐条 "画出" 轮廓，随后淡入填充
+
+use visioncortex::{Color, CompoundPath};
+
+use crate::{geometry, TracerConfig};
+
+/// 每条路径的描边动画占总时长的比例，其余时间用于错开各路径的起点
+const DRAW_SHARE: f64 = 0.5;
+
+/// 填充淡入时长占总时长的比例
+const FILL_SHARE: f64 = 0.25;
+
+/// 填充模式下临时描边的宽度 (像素)
+const STROKE_WIDTH: f64 = 1.0;
+
+/// 动画用的 `<style>` 元素，作为根元素的子元素输出一次
+///
+/// 描边从属性给出的 `stroke-dashoffset` (等于路径长度) 过渡到 0；填充从透明过渡到属性值。
+/// 用户系统开启 "减少动态效果" 时不播放，直接显示静止状态。
+pub(crate) fn style_element(duration_ms: f64) -> String {
+    format!(
+        "<style>.snapsvg-draw{{animation:snapsvg-draw {:.0}ms ease-in-out both,snapsvg-fill {:.0}ms ease-in both}}\
+         @keyframes snapsvg-draw{{to{{stroke-dashoffset:0}}}}\
+         @keyframes snapsvg-fill{{from{{fill-opacity:0}}}}\
+         @media (prefers-reduced-motion:reduce){{.snapsvg-draw{{animation:none}}}}</style>\n",
+        duration_ms * DRAW_SHARE,
+        duration_ms * FILL_SHARE,
+    )
+}
+
+/// 第 `index` 条 (共 `count` 条) 路径附加的动画属性
+///
+/// 各路径的起点在前一半时长内均匀错开，最后一条恰好在 `duration_ms` 时画完；
+/// 每条路径画完后开始淡入自己的填充。填充模式下额外加上与图层同色的细描边。
+pub(crate) fn path_attributes(
+    path: &CompoundPath,
+    color: &Color,
+    index: usize,
+    count: usize,
+    duration_ms: f64,
+    cfg: &TracerConfig,
+) -> String {
+    // 向上取整并留出余量，展平误差不会在动画开始前露出一小段描边
+    let length = geometry::path_length(path).ceil() + 1.0;
+    let stagger = if count > 1 { duration_ms * (1.0 - DRAW_SHARE) / (count - 1) as f64 } else { 0.0 };
+    let draw_delay = stagger * index as f64;
+    let fill_delay = draw_delay + duration_ms * DRAW_SHARE;
+    let stroke = match cfg.stroke_width {
+        Some(_) => String::new(),
+        None => format!(
+            r##"stroke="#{:02x}{:02x}{:02x}" stroke-width="{}" "##,
+            color.r, color.g, color.b, STROKE_WIDTH
+        ),
+    };
+    format!(
+        r#"class="snapsvg-draw" {}stroke-dasharray="{}" stroke-dashoffset="{}" style="animation-delay:{:.0}ms,{:.0}ms""#,
+        stroke, length, length, draw_delay, fill_delay,
+    )
+}
@@ -0,0 +1,104 @@
+//! 自定义根元素属性：校验调用方提供的属性名，转义属性值
+
+use wasm_bindgen::prelude::*;
+
+use crate::{escape_xml, warnings};
+
+/// 根元素总是输出的属性，自定义属性不能覆盖，否则会产生重复属性
+const RESERVED: [&str; 2] = ["xmlns", "viewBox"];
+
+/// 属性名是否合法且安全：ASCII 字母或 `_` 开头，其后为字母、数字、`-`、`_`、`.`
+///
+/// 命名空间前缀只接受 `xml:` 与 `xmlns:` (其它前缀需要另行声明)；
+/// `on` 开头的事件处理属性会在内联到页面时执行脚本，一律拒绝。
+fn is_allowed_name(name: &str) -> bool {
+    let local = name.strip_prefix("xmlns:").or_else(|| name.strip_prefix("xml:")).unwrap_or(name);
+    let mut chars = local.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid && !name.to_ascii_lowercase().starts_with("on")
+}
+
+/// 把 JS 对象 (属性名 → 值) 解析为属性列表，按对象自身可枚举属性的顺序排列
+///
+/// 值接受字符串、数字与布尔值，统一转为字符串，其余规则见 `validate`。
+pub(crate) fn parse(object: &js_sys::Object) -> Vec<(String, String)> {
+    validate(js_sys::Object::entries(object).iter().map(|entry| {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let name = entry.get(0).as_string().unwrap_or_default();
+        let raw = entry.get(1);
+        let text = raw
+            .as_string()
+            .or_else(|| raw.as_f64().filter(|v| v.is_finite()).map(|v| v.to_string()))
+            .or_else(|| raw.as_bool().map(|v| v.to_string()));
+        (name, text)
+    }))
+}
+
+/// 校验属性列表并转义属性值，`None` 表示值的类型不受支持
+///
+/// 不合法的属性名、保留属性名、不受支持的值以及以 `javascript:` 开头的值都被跳过，
+/// 并通过警告通道以 `option` 类别报告。
+fn validate(entries: impl IntoIterator<Item = (String, Option<String>)>) -> Vec<(String, String)> {
+    let mut attrs: Vec<(String, String)> = Vec::new();
+    for (name, text) in entries {
+        if !is_allowed_name(&name) || RESERVED.contains(&name.as_str()) {
+            warnings::emit("option", &format!("root_attributes 中的属性名 \"{}\" 不合法或不允许使用，已跳过", name));
+            continue;
+        }
+        let Some(text) = text else {
+            warnings::emit("option", &format!("root_attributes 中 \"{}\" 的值不是字符串、数字或布尔值，已跳过", name));
+            continue;
+        };
+        if text.trim_start().to_ascii_lowercase().starts_with("javascript:") {
+            warnings::emit("option", &format!("root_attributes 中 \"{}\" 的值含有脚本 URL，已跳过", name));
+            continue;
+        }
+        attrs.push((name, escape_xml(&text)));
+    }
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_svg, TraceOptions};
+
+    fn entries(pairs: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+        pairs.iter().map(|&(name, value)| (name.to_owned(), value.map(str::to_owned))).collect()
+    }
+
+    #[test]
+    fn custom_attributes_are_validated_and_escaped() {
+        let (attrs, warnings) = warnings::capture(|| validate(entries(&[
+            ("class", Some("icon icon-large")),
+            ("focusable", Some("false")),
+            ("data-source", Some("a \"quoted\" <name> & more")),
+            ("xml:lang", Some("zh")),
+            ("_private", Some("1")),
+            ("onload", Some("alert(1)")),
+            ("OnClick", Some("alert(1)")),
+            ("viewBox", Some("0 0 1 1")),
+            ("1st", Some("x")),
+            ("foo:bar", Some("x")),
+            ("href", Some("  JavaScript:alert(1)")),
+            ("tabindex", None),
+        ])));
+        let names: Vec<&str> = attrs.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["class", "focusable", "data-source", "xml:lang", "_private"]);
+        assert_eq!(attrs[2].1, "a &quot;quoted&quot; &lt;name&gt; &amp; more");
+        assert_eq!(warnings.len(), 7, "{:?}", warnings);
+        assert!(warnings.iter().all(|w| w.starts_with("option: root_attributes")));
+    }
+
+    #[test]
+    fn custom_attributes_are_written_on_the_root() {
+        let mut cfg = TraceOptions::new().to_config();
+        cfg.title = Some("标题".to_owned());
+        cfg.root_attributes = validate(entries(&[("class", Some("logo")), ("role", Some("presentation")), ("data-id", Some("7"))]));
+        let (svg, warnings) = warnings::capture(|| build_svg(10, 10, &[], &cfg));
+        // 与生成的 `role` 重复的自定义属性被跳过
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" role="img" aria-labelledby="snapsvg-title" class="logo" data-id="7" viewBox="0 0 10 10">"#), "{}", svg);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    }
+}
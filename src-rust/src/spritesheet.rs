@@ -0,0 +1,106 @@
+//! 精灵图输出：每个颜色图层栅格化为一帧，打包成一张 PNG 并附带 JSON 图集，供基于位图的游戏管线使用
+
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use visioncortex::color_clusters::ClusterIndex;
+use visioncortex::{Color, ColorImage};
+
+use crate::raster::encode_png;
+use crate::{check_rgba_len, cluster_color, cluster_image, reset_rng, warnings, TraceOptions};
+
+/// 精灵图与描述各帧位置的图集
+#[wasm_bindgen(getter_with_clone)]
+pub struct SpriteSheet {
+    /// 打包后的 PNG 字节
+    #[wasm_bindgen(readonly)]
+    pub png: Vec<u8>,
+    /// JSON 图集：`{"width", "height", "frames": [{"index", "x", "y", "w", "h", "color"}]}`
+    #[wasm_bindgen(readonly)]
+    pub atlas: String,
+}
+
+/// 按颜色分层后把每个图层栅格化为一帧，打包成一张精灵图
+///
+/// 图层划分与 `trace_rgba_to_layers` 相同：同色的色块归为一层，按首次绘制的顺序排列。
+/// 每帧都是原图尺寸 (`width` x `height`)，只有本图层的像素不透明，其余透明，
+/// 因此各帧按下标顺序叠放即还原完整图像。与 `render_trace_preview` 一样只执行聚类阶段，
+/// 帧内色块边缘是像素级的，不做曲线拟合。
+///
+/// 打包布局：帧按下标逐行从左到右排列，每行 `columns` 帧，帧之间没有间隙；
+/// `columns` 省略 (或大于帧数) 时所有帧排成一行。第 `i` 帧位于
+/// `x = (i % columns) * width`、`y = (i / columns) * height`，图集中的 `color` 为 `#rrggbb`。
+///
+/// 精灵图面积为原图的 帧数 倍 (向上补齐到整行)，颜色较多的大图应先缩小或减少 `color_count`；
+/// 像素总数超出可分配范围时返回错误。没有任何图层时返回错误。
+#[wasm_bindgen]
+pub fn trace_rgba_to_spritesheet(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+    columns: Option<u32>,
+) -> Result<SpriteSheet, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let clusters = cluster_image(color_image, &cfg);
+    let view = clusters.view();
+
+    // 按颜色分组，保持与 SVG 相同的绘制顺序中首次出现的顺序
+    let mut layers: Vec<(Color, Vec<ClusterIndex>)> = Vec::new();
+    for &cluster_index in view.clusters_output.iter().rev() {
+        let color = cluster_color(view.get_cluster(cluster_index), &cfg);
+        match layers.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, indices)) => indices.push(cluster_index),
+            None => layers.push((color, vec![cluster_index])),
+        }
+    }
+    if layers.is_empty() || w == 0 || h == 0 {
+        return Err(JsValue::from_str("没有可导出的图层"));
+    }
+
+    let count = layers.len();
+    let columns = columns.map_or(count, |c| (c as usize).clamp(1, count));
+    let rows = count.div_ceil(columns);
+    let sheet_w = columns * w;
+    let sheet_h = rows * h;
+    let sheet_bytes = sheet_w
+        .checked_mul(sheet_h)
+        .and_then(|n| n.checked_mul(4))
+        .filter(|_| sheet_w <= u32::MAX as usize && sheet_h <= u32::MAX as usize)
+        .ok_or_else(|| JsValue::from_str(&format!("精灵图尺寸过大: {} 帧 {}x{}", count, w, h)))?;
+
+    let mut sheet = vec![0u8; sheet_bytes];
+    let mut frames = String::new();
+    for (i, (color, indices)) in layers.iter().enumerate() {
+        let mut frame = ColorImage::new_w_h(w, h);
+        for &cluster_index in indices {
+            view.get_cluster(cluster_index).render_to_color_image_with_color(&view, &mut frame, color);
+        }
+
+        let (x0, y0) = ((i % columns) * w, (i / columns) * h);
+        for (y, row) in frame.pixels.chunks_exact(w * 4).enumerate() {
+            let start = ((y0 + y) * sheet_w + x0) * 4;
+            sheet[start..start + w * 4].copy_from_slice(row);
+        }
+
+        if i > 0 {
+            frames.push(',');
+        }
+        write!(
+            frames,
+            r##"{{"index":{},"x":{},"y":{},"w":{},"h":{},"color":"#{:02x}{:02x}{:02x}"}}"##,
+            i, x0, y0, w, h, color.r, color.g, color.b
+        ).ok();
+    }
+
+    let png = encode_png(&sheet, sheet_w as u32, sheet_h as u32).map_err(|e| JsValue::from_str(&e))?;
+    let atlas = format!(r#"{{"width":{},"height":{},"frames":[{}]}}"#, sheet_w, sheet_h, frames);
+    Ok(SpriteSheet { png, atlas })
+}
@@ -0,0 +1,511 @@
+//! SVG 后处理：对已生成的 SVG 字符串做合并、换色等操作
+//!
+//! 只面向本库输出的简单结构 (单个根 `<svg>` + 扁平子元素)，不是通用 XML 解析器
+
+use std::borrow::Cow;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use visioncortex::Color;
+
+use crate::palette::rgb_distance_sq;
+
+/// 读取标签中某个属性的值 (仅支持双引号)
+pub(crate) fn attr_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {}=\"", name);
+    let start = tag.find(&pattern)? + pattern.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+/// 拆分 SVG 文档，返回 (根标签, 根元素内部内容)
+pub(crate) fn split_root(svg: &str) -> Option<(&str, &str)> {
+    let open_start = svg.find("<svg")?;
+    let open_end = open_start + svg[open_start..].find('>')? + 1;
+    let close_start = svg.rfind("</svg>")?;
+    if close_start < open_end {
+        return None;
+    }
+    Some((&svg[open_start..open_end], &svg[open_end..close_start]))
+}
+
+/// 合并多次追踪得到的 SVG：按顺序叠放各层内容 (靠后的层在上) 并共用一个 `viewBox`
+///
+/// 每个输入都必须带有 `viewBox="0 0 width height"`，与给定尺寸不一致时报错，
+/// 避免坐标空间不同的图层被悄悄错位叠加。
+///
+/// 各层的 `snapsvg-*` id (共享形状、纹理、分块裁剪、`snapsvg-layer-N` 等) 改写为
+/// `snapsvg-<层号>-*`，层内的 `href="#…"`、`url(#…)` 引用随之改写，各层仍引用自己的定义；
+/// 各层的 `<title>`/`<desc>` 属于单层的根元素，合并后被删去。
+#[wasm_bindgen]
+pub fn merge_svgs(layers: Vec<String>, width: u32, height: u32) -> Result<String, JsValue> {
+    let expected = format!("0 0 {} {}", width, height);
+
+    let mut svg = String::with_capacity(layers.iter().map(|l| l.len()).sum());
+    writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{}">"#, expected).ok();
+
+    for (i, layer) in layers.iter().enumerate() {
+        let (root, body) = split_root(layer)
+            .ok_or_else(|| JsValue::from_str(&format!("第 {} 层不是有效的 SVG 文档", i)))?;
+        let view_box = attr_value(root, "viewBox")
+            .ok_or_else(|| JsValue::from_str(&format!("第 {} 层缺少 viewBox", i)))?;
+        if view_box.split_whitespace().ne(expected.split_whitespace()) {
+            return Err(JsValue::from_str(&format!(
+                "第 {} 层尺寸不一致: 期望 viewBox=\"{}\", 实际 \"{}\"",
+                i, expected, view_box
+            )));
+        }
+
+        let body = layer_body(body, i);
+        if !body.is_empty() {
+            writeln!(svg, "{}", body).ok();
+        }
+    }
+
+    writeln!(svg, "</svg>").ok();
+
+    Ok(svg)
+}
+
+/// 合并用的单层内容：删去 `<title>`/`<desc>`，id 与引用加上层号前缀
+fn layer_body(body: &str, layer: usize) -> String {
+    let mut kept = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("<title").into_iter().chain(rest.find("<desc")).min() {
+        kept.push_str(&rest[..start]);
+        let close = if rest[start..].starts_with("<title") { "</title>" } else { "</desc>" };
+        let Some(len) = rest[start..].find(close) else {
+            rest = &rest[start..];
+            break;
+        };
+        rest = &rest[start + len + close.len()..];
+    }
+    kept.push_str(rest);
+
+    let prefix = format!("snapsvg-{}-", layer);
+    kept.trim()
+        .replace(" id=\"snapsvg-", &format!(" id=\"{}", prefix))
+        .replace("#snapsvg-", &format!("#{}", prefix))
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 解析 `#rgb`、`#rgba`、`#rrggbb`、`#rrggbbaa` 形式的十六进制颜色，返回颜色与可选的不透明度
+fn parse_hex(value: &str) -> Option<(Color, Option<u8>)> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.is_ascii() {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        // 短格式每位重复一次，如 `#f80` 等价于 `#ff8800`
+        3 | 4 => hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8 * 17))
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    Some((Color::new(digits[0], digits[1], digits[2]), digits.get(3).copied()))
+}
+
+/// 删除标签中的某个属性 (仅支持双引号)
+fn remove_attr(tag: &mut String, name: &str) {
+    let pattern = format!(" {}=\"", name);
+    if let Some(start) = tag.find(&pattern) {
+        if let Some(len) = tag[start + pattern.len()..].find('"') {
+            tag.replace_range(start..start + pattern.len() + len + 1, "");
+        }
+    }
+}
+
+/// 逐个标签替换 `fill` 颜色；`mapping` 的目标色带有不透明度时同步改写 `fill-opacity`
+fn recolor(svg: &str, mapping: &[(Color, Color, Option<u8>)], snap: bool) -> String {
+    let mut out = String::with_capacity(svg.len());
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start..start + len + 1];
+        rest = &rest[start + len + 1..];
+
+        let target = attr_value(tag, "fill").and_then(parse_hex).and_then(|(color, _)| {
+            mapping
+                .iter()
+                .find(|(from, _, _)| *from == color)
+                .or_else(|| snap.then(|| mapping.iter().min_by_key(|(from, _, _)| rgb_distance_sq(*from, color)))?)
+        });
+        let Some(&(_, to, opacity)) = target else {
+            out.push_str(tag);
+            continue;
+        };
+
+        let mut new_tag = tag.to_owned();
+        if opacity.is_some() {
+            remove_attr(&mut new_tag, "fill-opacity");
+        }
+        let pattern = " fill=\"";
+        let value_start = new_tag.find(pattern).map(|i| i + pattern.len()).unwrap_or(0);
+        let value_len = new_tag[value_start..].find('"').unwrap_or(0);
+        let mut replacement = format!("#{:02x}{:02x}{:02x}", to.r, to.g, to.b);
+        if let Some(alpha) = opacity {
+            write!(replacement, "\" fill-opacity=\"{}", format_opacity(alpha)).ok();
+        }
+        new_tag.replace_range(value_start..value_start + value_len, &replacement);
+        out.push_str(&new_tag);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 0~255 的不透明度格式化为最多 3 位小数
+fn format_opacity(alpha: u8) -> String {
+    let text = format!("{:.3}", alpha as f64 / 255.0);
+    text.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// 按颜色映射表给已生成的 SVG 换色，无需重新追踪
+///
+/// `mapping` 为 `{ "#旧色": "#新色" }` 形式的对象，颜色支持 `#rgb` 与 `#rrggbb`，
+/// 新色还可以写成 `#rgba`/`#rrggbbaa`，此时同时设置 `fill-opacity` (覆盖原有值)；
+/// 不带透明度的新色保留元素原有的 `fill-opacity`。只改写十六进制的 `fill` 属性，
+/// `fill="none"` 等其它取值原样保留。
+///
+/// `snap` 为 `true` 时，不在映射表中的颜色改用与其最接近 (RGB 距离) 的旧色所对应的新色；
+/// 为 `false` 时这些颜色保持不变。
+#[wasm_bindgen]
+pub fn recolor_svg(svg: &str, mapping: JsValue, snap: bool) -> Result<String, JsValue> {
+    let object = mapping
+        .dyn_ref::<js_sys::Object>()
+        .ok_or_else(|| JsValue::from_str("mapping 必须是 { \"#旧色\": \"#新色\" } 形式的对象"))?;
+
+    let mut pairs = Vec::new();
+    for entry in js_sys::Object::entries(object).iter() {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let from = entry.get(0).as_string().unwrap_or_default();
+        let to = entry.get(1).as_string().unwrap_or_default();
+        let (from_color, _) = parse_hex(&from)
+            .ok_or_else(|| JsValue::from_str(&format!("无法解析的颜色 \"{}\"", from)))?;
+        let (to_color, opacity) = parse_hex(&to)
+            .ok_or_else(|| JsValue::from_str(&format!("无法解析的颜色 \"{}\"", to)))?;
+        pairs.push((from_color, to_color, opacity));
+    }
+
+    Ok(recolor(svg, &pairs, snap))
+}
+
+/// 找出路径数据中没有以 `Z` 结束的子路径，返回应插入 `Z` 的字节位置 (下一个 `M` 之前或末尾)
+///
+/// `Z` 之后未经 `M` 直接出现的绘制命令会从同一点开始新的子路径，同样需要闭合。
+fn unclosed_subpaths(d: &str) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut open = false;
+    for (i, c) in d.char_indices() {
+        match c {
+            'M' | 'm' => {
+                if open {
+                    positions.push(i);
+                }
+                open = true;
+            }
+            'Z' | 'z' => open = false,
+            c if "LlHhVvCcSsQqTtAa".contains(c) => open = true,
+            _ => {}
+        }
+    }
+    if open {
+        positions.push(d.len());
+    }
+    positions
+}
+
+/// 保证每个子路径都以 `Z` 闭合，缺少时在下一个 `M` 之前 (或末尾) 补上
+///
+/// 拟合结果的路径总是封闭的，输出时仍做一次检查，部分渲染器遇到未闭合的子路径后紧跟新的 `M` 会出错。
+pub(crate) fn close_subpaths(d: &str) -> Cow<'_, str> {
+    let positions = unclosed_subpaths(d);
+    if positions.is_empty() {
+        return Cow::Borrowed(d);
+    }
+    let mut closed = String::with_capacity(d.len() + positions.len() * 2);
+    let mut last = 0;
+    for at in positions {
+        closed.push_str(d[last..at].trim_end());
+        closed.push_str(if d.len() == at { "Z" } else { "Z " });
+        last = at;
+    }
+    closed.push_str(&d[last..]);
+    Cow::Owned(closed)
+}
+
+/// 检查路径数据：坐标必须是有限数字，每个命令的参数个数必须是其参数组大小的正整数倍
+fn check_path_data(d: &str) -> Result<(), String> {
+    let mut command: Option<char> = None;
+    let mut args = 0usize;
+
+    let finish = |command: Option<char>, args: usize| -> Result<(), String> {
+        let Some(c) = command else {
+            return Ok(());
+        };
+        let arity = match c.to_ascii_uppercase() {
+            'Z' => 0,
+            'H' | 'V' => 1,
+            'M' | 'L' | 'T' => 2,
+            'S' | 'Q' => 4,
+            'C' => 6,
+            _ => 7,
+        };
+        let balanced = if arity == 0 { args == 0 } else { args > 0 && args.is_multiple_of(arity) };
+        if balanced {
+            Ok(())
+        } else {
+            Err(format!("命令 {} 的参数个数为 {}，应为 {} 的正整数倍", c, args, arity.max(1)))
+        }
+    };
+
+    let chars: Vec<char> = d.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if "MmLlHhVvCcSsQqTtAaZz".contains(c) {
+            finish(command, args)?;
+            if command.is_none() && c != 'M' && c != 'm' {
+                return Err(format!("路径数据必须以 M 开头，实际为 {}", c));
+            }
+            command = Some(c);
+            args = 0;
+            i += 1;
+        } else if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') {
+            // 数字：符号、整数部分、小数部分、指数
+            let start = i;
+            i += 1;
+            let mut seen_dot = c == '.';
+            while i < chars.len() {
+                let ch = chars[i];
+                let is_exponent_sign = matches!(ch, '-' | '+') && matches!(chars[i - 1], 'e' | 'E');
+                if ch.is_ascii_digit() || matches!(ch, 'e' | 'E') || is_exponent_sign || (ch == '.' && !seen_dot) {
+                    seen_dot |= ch == '.';
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse::<f64>() {
+                Ok(v) if v.is_finite() => {}
+                _ => return Err(format!("无效的数字 \"{}\"", text)),
+            }
+            if command.is_none() {
+                return Err("路径数据必须以 M 开头".to_owned());
+            }
+            args += 1;
+        } else {
+            // NaN、inf 等非法字符
+            let text: String = chars[i..].iter().take_while(|ch| ch.is_alphanumeric()).collect();
+            return Err(format!("无效的内容 \"{}\"", if text.is_empty() { c.to_string() } else { text }));
+        }
+    }
+    finish(command, args)
+}
+
+/// 需要是有限数字的几何属性
+const NUMERIC_ATTRS: [&str; 11] = ["x", "y", "width", "height", "cx", "cy", "r", "rx", "ry", "stroke-width", "viewBox"];
+
+/// 检查单个元素标签的属性
+fn check_tag(tag: &str, is_root: bool) -> Result<(), String> {
+    if let Some(d) = attr_value(tag, "d") {
+        check_path_data(d).map_err(|e| format!("d 属性: {}", e))?;
+        // 填充形状的每个子路径都应闭合；`fill="none"` 的描边 (如线稿) 允许开放的折线
+        let unclosed = unclosed_subpaths(d).len();
+        if unclosed > 0 && attr_value(tag, "fill") != Some("none") {
+            return Err(format!("d 属性: {} 个子路径没有以 Z 闭合", unclosed));
+        }
+    }
+    for name in NUMERIC_ATTRS {
+        // 根元素的 width/height 可能带物理单位
+        if is_root && (name == "width" || name == "height") {
+            continue;
+        }
+        if let Some(value) = attr_value(tag, name) {
+            let valid = value
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|t| !t.is_empty())
+                .all(|t| t.parse::<f64>().is_ok_and(f64::is_finite));
+            if !valid {
+                return Err(format!("{} 属性不是有效的数字: \"{}\"", name, value));
+            }
+        }
+    }
+    if let Some(transform) = attr_value(tag, "transform") {
+        let invalid = transform
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')'))
+            .any(|t| t.contains("NaN") || t.contains("inf"));
+        if invalid {
+            return Err(format!("transform 属性含有无效数字: \"{}\"", transform));
+        }
+    }
+    Ok(())
+}
+
+/// 检查整个文档：标签配对、根元素，以及每个元素的几何属性
+fn check_document(svg: &str) -> Result<(), String> {
+    let mut stack: Vec<&str> = Vec::new();
+    let mut element_count = 0;
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        let len = rest[start..].find('>').ok_or("标签没有闭合的 '>'")?;
+        let tag = &rest[start..start + len + 1];
+        rest = &rest[start + len + 1..];
+
+        // XML 声明、注释与 DOCTYPE 不参与配对
+        if tag.starts_with("<?") || tag.starts_with("<!") {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix("</") {
+            let name = name.trim_end_matches('>').trim();
+            match stack.pop() {
+                Some(open) if open == name => {}
+                Some(open) => return Err(format!("</{}> 与 <{}> 不匹配", name, open)),
+                None => return Err(format!("多余的结束标签 </{}>", name)),
+            }
+            continue;
+        }
+
+        let name = tag[1..]
+            .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .next()
+            .unwrap_or("");
+        if name.is_empty() {
+            return Err("空的标签名".to_owned());
+        }
+        if element_count == 0 && name != "svg" {
+            return Err(format!("根元素应为 <svg>，实际为 <{}>", name));
+        }
+        if element_count > 0 && stack.is_empty() {
+            return Err(format!("根元素之外还有元素 <{}>", name));
+        }
+        check_tag(tag, element_count == 0).map_err(|e| format!("第 {} 个元素 <{}> 的 {}", element_count, name, e))?;
+        element_count += 1;
+        if !tag.ends_with("/>") {
+            stack.push(name);
+        }
+    }
+
+    if element_count == 0 {
+        return Err("没有找到 <svg> 根元素".to_owned());
+    }
+    if let Some(open) = stack.pop() {
+        return Err(format!("<{}> 没有结束标签", open));
+    }
+    Ok(())
+}
+
+/// 校验 SVG 文档，返回发现的第一个问题
+///
+/// 检查标签是否正确配对、根元素是否为 `<svg>`，以及每个元素的 `d` 路径数据
+/// (NaN/无穷等非法数字、命令参数个数不完整、填充形状的子路径没有以 `Z` 闭合) 和 `x`、`width`、`viewBox` 等几何属性。
+/// 部分渲染器遇到这类问题会拒绝整个文档；本库的输出在写出前已丢弃坐标无效的路径。
+///
+/// 与其它 SVG 后处理函数一样只面向简单结构，不处理 CDATA 与属性值中的 `>`。
+#[wasm_bindgen]
+pub fn validate_svg(svg: &str) -> Result<(), JsValue> {
+    check_document(svg).map_err(|e| JsValue::from_str(&format!("SVG 校验失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fill_rect, path_data, shapes, solid};
+    use crate::{trace_rgba_with_options, PostprocessOptions, TraceOptions};
+
+    /// 白底上四个相同的色块，`size` 为边长
+    fn repeated(size: usize, rgba: [u8; 4]) -> Vec<u8> {
+        let mut pixels = solid(60, 40, [255, 255, 255, 255]);
+        for (x, y) in [(4, 4), (34, 4), (4, 24), (34, 24)] {
+            fill_rect(&mut pixels, 60, (x, y), (x + size, y + size), rgba);
+        }
+        pixels
+    }
+
+    /// 文档中形如 `<attr>="<value>"` 的全部属性值
+    fn attr_values<'a>(svg: &'a str, attr: &str) -> Vec<&'a str> {
+        let pattern = format!(" {}=\"", attr);
+        svg.split(&pattern).skip(1).filter_map(|rest| rest.split('"').next()).collect()
+    }
+
+    /// 共享形状 `id` 的路径数据
+    fn shape_data<'a>(svg: &'a str, id: &str) -> &'a str {
+        let line = svg.lines().find(|line| line.contains(&format!(" id=\"{}\"", id))).unwrap();
+        attr_value(line, "d").unwrap()
+    }
+
+    #[test]
+    fn merged_layers_keep_their_own_shared_shapes() {
+        let mut options = TraceOptions::new();
+        options.dedupe_shapes = true;
+        options.title = Some("图层".to_owned());
+        let squares = trace_rgba_with_options(&repeated(12, [220, 40, 40, 255]), 60, 40, &options).unwrap();
+        let small = trace_rgba_with_options(&repeated(6, [40, 40, 220, 255]), 60, 40, &options).unwrap();
+        assert!(squares.contains("href=\"#snapsvg-shape-0\"") && small.contains("href=\"#snapsvg-shape-0\""));
+
+        let merged = merge_svgs(vec![squares.clone(), small.clone()], 60, 40).unwrap();
+        assert!(!merged.contains("<title"), "{}", merged);
+        let ids = attr_values(&merged, "id");
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ids.len(), "{:?}", ids);
+        for reference in attr_values(&merged, "href") {
+            assert!(ids.contains(&reference.trim_start_matches('#')), "{}", reference);
+        }
+        // 第二层的引用指向它自己的形状
+        assert!(merged.contains("href=\"#snapsvg-1-shape-0\""));
+        assert_eq!(shape_data(&merged, "snapsvg-0-shape-0"), shape_data(&squares, "snapsvg-shape-0"));
+        assert_eq!(shape_data(&merged, "snapsvg-1-shape-0"), shape_data(&small, "snapsvg-shape-0"));
+        assert_eq!(check_document(&merged), Ok(()));
+    }
+
+    #[test]
+    fn close_subpaths_inserts_missing_z() {
+        assert_eq!(close_subpaths("M0 0 L4 0 L4 4 Z"), "M0 0 L4 0 L4 4 Z");
+        assert_eq!(close_subpaths("M0 0 L4 0 L4 4 M8 8 L9 9 Z"), "M0 0 L4 0 L4 4Z M8 8 L9 9 Z");
+        assert_eq!(close_subpaths("M0 0L4 0L4 4"), "M0 0L4 0L4 4Z");
+        // Z 之后直接绘制会开始新的子路径
+        assert_eq!(close_subpaths("M0 0 L4 4 Z L8 0"), "M0 0 L4 4 Z L8 0Z");
+    }
+
+    #[test]
+    fn unclosed_fill_paths_fail_validation() {
+        let document = |tag: &str| format!("<svg viewBox=\"0 0 8 8\">\n{}\n</svg>\n", tag);
+        assert!(check_document(&document(r##"<path d="M0 0 L4 0 L4 4 Z" fill="#ff0000"/>"##)).is_ok());
+        assert!(check_document(&document(r##"<path d="M0 0 L4 0 L4 4 M1 1 L2 2 Z" fill="#ff0000"/>"##)).is_err());
+        // 描边线稿允许开放的折线
+        assert!(check_document(&document(r#"<path d="M0 0 L4 0 L4 4" fill="none"/>"#)).is_ok());
+        assert!(check_document(&document(r##"<path d="M0 0 LNaN 0 Z" fill="#ff0000"/>"##)).is_err());
+    }
+
+    #[test]
+    fn traced_subpaths_are_always_closed() {
+        let pixels = shapes(60, 40);
+        let mut options = TraceOptions::new();
+        for (relative_commands, minify) in [(false, false), (true, false), (false, true), (true, true)] {
+            let mut postprocess = PostprocessOptions::new();
+            postprocess.relative_commands = relative_commands;
+            postprocess.minify = minify;
+            options.postprocess = postprocess;
+            let svg = trace_rgba_with_options(&pixels, 60, 40, &options).unwrap();
+            for d in path_data(&svg) {
+                assert_eq!(unclosed_subpaths(d), [0usize; 0], "{}", d);
+            }
+            assert_eq!(check_document(&svg), Ok(()));
+        }
+    }
+}
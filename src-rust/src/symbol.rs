@@ -0,0 +1,76 @@
+//! 图标 symbol 输出：追踪结果写成 `<symbol>` 片段，多个图标可拼接进同一份精灵 SVG，以 `<use href="#id">` 引用
+
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use visioncortex::ColorImage;
+
+use crate::svg_tools::{attr_value, split_root};
+use crate::{build_svg, check_empty, check_rgba_len, cluster_and_fit, escape_xml, lines, reset_rng, warnings, TraceOptions};
+
+/// 是否为合法的 XML id (NCName)：以字母或 `_` 开头，其余为字母、数字、`-`、`_`、`.`
+fn is_valid_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// 矢量化并输出 `<symbol id="..." viewBox="...">` 片段，而非独立的 `<svg>` 文档
+///
+/// `viewBox` 与 `trace_rgba_with_options` 的输出相同 (`crop_to_content`、`origin` 等选项照常生效)。
+/// 片段不带 `xmlns` 与 `width`/`height`，应放进某个 `<svg>` 根元素 (通常为隐藏的精灵图) 中使用。
+/// `title`/`desc` 作为 symbol 的子元素输出但不带 `id`，多个 symbol 拼接时不会出现重复的 id；
+/// 同样原因，`dedupe_shapes` 在此被忽略。
+///
+/// `id` 必须是合法的 XML id：以字母或 `_` 开头，其余字符为字母、数字、`-`、`_`、`.`，否则返回错误。
+#[wasm_bindgen]
+pub fn trace_rgba_to_symbol(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    id: &str,
+    options: &TraceOptions,
+) -> Result<String, JsValue> {
+    if !is_valid_id(id) {
+        return Err(JsValue::from_str(&format!(
+            "无效的 symbol id \"{}\"：必须以字母或 _ 开头，只能包含字母、数字、-、_、.",
+            id
+        )));
+    }
+
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let mut cfg = options.to_config();
+    let title = cfg.title.take();
+    let desc = cfg.desc.take();
+    cfg.dedupe_shapes = false;
+
+    let svg = match cfg.line_extraction {
+        Some(threshold) => lines::trace_lines(rgba_data, w, h, threshold, &cfg),
+        None => {
+            let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+            let path_results = cluster_and_fit(color_image, &cfg);
+            check_empty(&path_results, &cfg)?;
+            build_svg(w, h, &path_results, &cfg)
+        }
+    };
+
+    let (root, content) = split_root(&svg).ok_or_else(|| JsValue::from_str("生成的 SVG 缺少根元素"))?;
+    let view_box = attr_value(root, "viewBox").unwrap_or_default();
+
+    let mut symbol = String::with_capacity(svg.len() + 64);
+    writeln!(symbol, r#"<symbol id="{}" viewBox="{}">"#, escape_xml(id), view_box).ok();
+    if let Some(title) = title {
+        writeln!(symbol, "<title>{}</title>", escape_xml(&title)).ok();
+    }
+    if let Some(desc) = desc {
+        writeln!(symbol, "<desc>{}</desc>", escape_xml(&desc)).ok();
+    }
+    symbol.push_str(content.trim_start_matches('\n'));
+    writeln!(symbol, "</symbol>").ok();
+    Ok(symbol)
+}
@@ -0,0 +1,195 @@
+//! 测试用的合成图像与输出解析
+
+/// `width` x `height` 的纯色 RGBA 图像
+pub(crate) fn solid(width: usize, height: usize, rgba: [u8; 4]) -> Vec<u8> {
+    rgba.repeat(width * height)
+}
+
+/// 把 `[x0, x1) x [y0, y1)` 范围内的像素设为 `rgba`
+pub(crate) fn fill_rect(pixels: &mut [u8], width: usize, (x0, y0): (usize, usize), (x1, y1): (usize, usize), rgba: [u8; 4]) {
+    for y in y0..y1 {
+        for x in x0..x1 {
+            pixels[(y * width + x) * 4..(y * width + x) * 4 + 4].copy_from_slice(&rgba);
+        }
+    }
+}
+
+/// 白底上一个蓝色椭圆与一个红色矩形，曲线边界的拟合坐标带有小数
+pub(crate) fn shapes(width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = solid(width, height, [255, 255, 255, 255]);
+    let (cx, cy, rx, ry) = (width as f64 * 0.35, height as f64 * 0.5, width as f64 * 0.25, height as f64 * 0.3);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = ((x as f64 + 0.5 - cx) / rx, (y as f64 + 0.5 - cy) / ry);
+            if dx * dx + dy * dy < 1.0 {
+                pixels[(y * width + x) * 4..(y * width + x) * 4 + 4].copy_from_slice(&[30, 90, 200, 255]);
+            }
+        }
+    }
+    fill_rect(&mut pixels, width, (width * 7 / 10, height / 5), (width * 9 / 10, height * 4 / 5), [220, 30, 30, 255]);
+    pixels
+}
+
+/// 文档中全部 `<path>` 元素的 `fill` 属性值
+pub(crate) fn fills(svg: &str) -> Vec<&str> {
+    svg.split("<path").skip(1).filter_map(|tag| tag.split(" fill=\"").nth(1)?.split('"').next()).collect()
+}
+
+/// 文档中全部 `transform` 属性值
+pub(crate) fn transforms(svg: &str) -> Vec<&str> {
+    svg.split(" transform=\"").skip(1).filter_map(|rest| rest.split('"').next()).collect()
+}
+
+/// 文档中全部 `<path>` 元素的 `d` 属性值
+pub(crate) fn path_data(svg: &str) -> Vec<&str> {
+    svg.split("<path").skip(1).filter_map(|tag| tag.split(" d=\"").nth(1)?.split('"').next()).collect()
+}
+
+/// 路径数据 (或任意属性值) 中的全部数字，按出现顺序排列
+pub(crate) fn numbers(text: &str) -> Vec<f64> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        if bytes[i] == b'-' || bytes[i] == b'+' {
+            i += 1;
+        }
+        let mut seen_dot = false;
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || (bytes[i] == b'.' && !seen_dot)) {
+            seen_dot |= bytes[i] == b'.';
+            i += 1;
+        }
+        if text[start..i].chars().any(|c| c.is_ascii_digit()) {
+            out.push(text[start..i].parse().unwrap());
+        } else {
+            i = start + 1;
+        }
+    }
+    out
+}
+
+/// 按命令拆分路径数据：命令字母与其后的全部数字
+pub(crate) fn commands(d: &str) -> Vec<(char, Vec<f64>)> {
+    let mut out = Vec::new();
+    let mut rest = d;
+    while let Some(pos) = rest.find(|c: char| c.is_ascii_alphabetic()) {
+        let command = rest[pos..].chars().next().unwrap();
+        rest = &rest[pos + 1..];
+        let end = rest.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        out.push((command, numbers(&rest[..end])));
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// 把路径数据展开为折线环 (每段曲线均分为 16 段)，`offset` 加到全部坐标上
+///
+/// 只支持本库输出用到的 `M`/`L`/`C`/`Z` 及其相对形式。
+pub(crate) fn rings(d: &str, offset: (f64, f64)) -> Vec<Vec<(f64, f64)>> {
+    let mut rings: Vec<Vec<(f64, f64)>> = Vec::new();
+    let (mut x, mut y) = (0.0, 0.0);
+    for (command, args) in commands(d) {
+        let relative = command.is_ascii_lowercase();
+        let at = |p: &[f64], x: f64, y: f64| if relative { (x + p[0], y + p[1]) } else { (p[0], p[1]) };
+        match command.to_ascii_uppercase() {
+            'M' | 'L' => {
+                for (k, p) in args.chunks_exact(2).enumerate() {
+                    (x, y) = at(p, x, y);
+                    if k == 0 && command.eq_ignore_ascii_case(&'m') {
+                        rings.push(Vec::new());
+                    }
+                    rings.last_mut().unwrap().push((x + offset.0, y + offset.1));
+                }
+            }
+            'C' => {
+                for p in args.chunks_exact(6) {
+                    let p0 = (x, y);
+                    let (p1, p2, p3) = (at(&p[0..2], x, y), at(&p[2..4], x, y), at(&p[4..6], x, y));
+                    for step in 1..=16 {
+                        let t = step as f64 / 16.0;
+                        let s = 1.0 - t;
+                        let (a, b, c, e) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+                        let q = (a * p0.0 + b * p1.0 + c * p2.0 + e * p3.0, a * p0.1 + b * p1.1 + c * p2.1 + e * p3.1);
+                        rings.last_mut().unwrap().push((q.0 + offset.0, q.1 + offset.1));
+                    }
+                    (x, y) = p3;
+                }
+            }
+            'Z' => {
+                if let Some(&(sx, sy)) = rings.last().and_then(|ring| ring.first()) {
+                    (x, y) = (sx - offset.0, sy - offset.1);
+                }
+            }
+            other => panic!("不支持的路径命令 {}", other),
+        }
+    }
+    rings
+}
+
+/// 点 `(px, py)` 相对一组封闭环的环绕数
+fn winding(rings: &[Vec<(f64, f64)>], px: f64, py: f64) -> i32 {
+    let mut winding = 0;
+    for ring in rings {
+        for (i, &(x0, y0)) in ring.iter().enumerate() {
+            let (x1, y1) = ring[(i + 1) % ring.len()];
+            if (y0 <= py) != (y1 <= py) && x0 + (py - y0) / (y1 - y0) * (x1 - x0) > px {
+                winding += if y1 > y0 { 1 } else { -1 };
+            }
+        }
+    }
+    winding
+}
+
+/// 在 `width` x `height` 的用户坐标网格上按像素中心采样，返回每个像素最终可见的 `fill`
+///
+/// 按元素顺序绘制，处理 `transform="translate(..)"` 与 `fill-rule`，不处理 `viewBox` 缩放。
+pub(crate) fn rasterize(svg: &str, width: usize, height: usize) -> Vec<Option<String>> {
+    rasterize_scaled(svg, width, height, 1)
+}
+
+/// 与 `rasterize` 相同，但每个用户单位采样 `scale` x `scale` 次，结果为 `width * scale` 列
+pub(crate) fn rasterize_scaled(svg: &str, width: usize, height: usize, scale: usize) -> Vec<Option<String>> {
+    let (columns, rows) = (width * scale, height * scale);
+    let mut canvas = vec![None; columns * rows];
+    for tag in svg.split("<path").skip(1) {
+        let tag = &tag[..tag.find("/>").unwrap_or(tag.len())];
+        let attr = |name: &str| tag.split(&format!(" {}=\"", name)).nth(1).and_then(|v| v.split('"').next());
+        let (Some(d), Some(fill)) = (attr("d"), attr("fill")) else { continue };
+        let offset = match attr("transform").map(numbers).as_deref() {
+            Some(&[x, y]) => (x, y),
+            _ => (0.0, 0.0),
+        };
+        let even_odd = attr("fill-rule") == Some("evenodd");
+        let rings = rings(d, offset);
+        for y in 0..rows {
+            for x in 0..columns {
+                let sample = |v: usize| (v as f64 + 0.5) / scale as f64;
+                let w = winding(&rings, sample(x), sample(y));
+                if (even_odd && w % 2 != 0) || (!even_odd && w != 0) {
+                    canvas[y * columns + x] = Some(fill.to_owned());
+                }
+            }
+        }
+    }
+    canvas
+}
+
+/// 逐条路径的像素覆盖率 (0~1)，每个像素按 `scale` x `scale` 个采样点估计，用于模拟抗锯齿渲染
+pub(crate) fn coverage(svg: &str, width: usize, height: usize, scale: usize) -> Vec<Vec<f64>> {
+    svg.split("<path")
+        .skip(1)
+        .map(|tag| {
+            let samples = rasterize_scaled(&format!("<path{}", tag), width, height, scale);
+            (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let covered = (0..scale * scale)
+                        .filter(|k| samples[(y * scale + k / scale) * width * scale + x * scale + k % scale].is_some())
+                        .count();
+                    covered as f64 / (scale * scale) as f64
+                })
+                .collect()
+        })
+        .collect()
+}
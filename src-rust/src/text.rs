@@ -0,0 +1,138 @@
+//! 文字感知追踪：把疑似文字的深色小色块按精细参数单独追踪，其余部分仍按常规参数处理
+
+use visioncortex::color_clusters::Cluster;
+use visioncortex::{Color, ColorImage, CompoundPath};
+
+use crate::{
+    cluster_color, cluster_image, cluster_sources, fit_cluster_indices, luma, Source, TracerConfig, DARK_LUMA_THRESHOLD,
+};
+
+/// 文字字形的包围盒高度范围 (像素)
+const GLYPH_MIN_HEIGHT: i32 = 4;
+const GLYPH_MAX_HEIGHT: i32 = 64;
+
+/// 包围盒宽高比的上限：相互粘连的字形会连成一整行，宽度可达高度的数倍
+const GLYPH_MAX_ASPECT: i32 = 12;
+
+/// 笔画密度 (深色像素数 / 包围盒面积) 的范围，实心色块与稀疏噪点都不是文字
+const GLYPH_MIN_DENSITY: f64 = 0.08;
+const GLYPH_MAX_DENSITY: f64 = 0.7;
+
+/// 包围盒短边不超过此值 (像素) 时视为单根笔画 (如 `l`、`-`)，本身就是实心的，不检查密度上限
+const GLYPH_MAX_STROKE: i32 = 3;
+
+/// 精细追踪使用的斑点过滤阈值与角度阈值 (度) 上限
+const TEXT_FILTER_SPECKLE: usize = 2;
+const TEXT_CORNER_THRESHOLD: i32 = 30;
+
+/// 色块中原图颜色为深色的像素数
+///
+/// 叠放模式下色块会吞并它包围的孔洞 (如字母 `o` 的内部)，面积不能代表笔画，需回到原图按像素统计。
+fn ink_pixels(cluster: &Cluster, color_image: &ColorImage) -> usize {
+    cluster
+        .iter()
+        .filter(|&&i| {
+            let px = &color_image.pixels[i as usize * 4..i as usize * 4 + 4];
+            px[3] > 0 && luma(Color::new(px[0], px[1], px[2])) < DARK_LUMA_THRESHOLD
+        })
+        .count()
+}
+
+/// 挑出疑似文字的色块，按精细参数拟合后返回，并把这些像素抹成周围的背景色
+///
+/// 判定只看单个色块的尺寸、形状与颜色：足够暗 (与 `protect_dark` 相同的亮度门限)、
+/// 包围盒高度在字号范围内、宽度不超过高度的 `GLYPH_MAX_ASPECT` 倍、笔画密度在
+/// `GLYPH_MIN_DENSITY` ~ `GLYPH_MAX_DENSITY` 之间 (细于 `GLYPH_MAX_STROKE` 的竖线、横线不限密度)。
+/// 抹除后常规追踪不会再把文字糊成色块；返回的字形及其字怀路径叠加在常规结果之上，
+/// 同时返回它们各自的来源信息。
+pub(crate) fn extract(
+    color_image: &mut ColorImage,
+    cfg: &TracerConfig,
+) -> (Vec<(CompoundPath, Color)>, Vec<Source>) {
+    let mut fine_cfg = cfg.clone();
+    fine_cfg.text_aware = false;
+    fine_cfg.filter_speckle = cfg.filter_speckle.min(TEXT_FILTER_SPECKLE);
+    fine_cfg.corner_threshold = cfg.corner_threshold.min(TEXT_CORNER_THRESHOLD);
+
+    let clusters = cluster_image(color_image.clone(), &fine_cfg);
+    let view = clusters.view();
+    let draw_order: Vec<_> = view.clusters_output.iter().rev().copied().collect();
+    let is_glyph: Vec<bool> = draw_order
+        .iter()
+        .map(|&index| {
+            let cluster = view.get_cluster(index);
+            let (width, height) = (cluster.rect.width(), cluster.rect.height());
+            let density = ink_pixels(cluster, color_image) as f64 / (width * height).max(1) as f64;
+            (GLYPH_MIN_HEIGHT..=GLYPH_MAX_HEIGHT).contains(&height)
+                && width <= height * GLYPH_MAX_ASPECT
+                && density >= GLYPH_MIN_DENSITY
+                && (density <= GLYPH_MAX_DENSITY || width.min(height) <= GLYPH_MAX_STROKE)
+                && luma(cluster_color(cluster, cfg)) < DARK_LUMA_THRESHOLD
+        })
+        .collect();
+    let text_indices: Vec<_> = draw_order.iter().zip(&is_glyph).filter(|(_, &g)| g).map(|(&i, _)| i).collect();
+    if text_indices.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let (w, h) = (color_image.width, color_image.height);
+    // 每个像素所属字形在绘制顺序中的位置；叠放模式下字形色块的像素包含它的内部
+    let mut glyph_at: Vec<Option<usize>> = vec![None; w * h];
+    for (position, &index) in draw_order.iter().enumerate().filter(|(position, _)| is_glyph[*position]) {
+        for &i in view.get_cluster(index).iter() {
+            glyph_at[i as usize] = Some(position);
+        }
+    }
+
+    // 字形色块填满了自身的内部 (如 `o`、`e` 的字怀)，字怀是绘制在其上、包围盒落在字形之内的
+    // 另一个色块，需要一并输出，否则字形会变成实心
+    let mut output = Vec::new();
+    for (position, &index) in draw_order.iter().enumerate() {
+        let cluster = view.get_cluster(index);
+        let is_counter = || {
+            let first = cluster.iter().next().and_then(|&i| glyph_at[i as usize]);
+            first.is_some_and(|glyph| {
+                let (outer, inner) = (view.get_cluster(draw_order[glyph]).rect, cluster.rect);
+                glyph < position
+                    && outer.left < inner.left
+                    && outer.top < inner.top
+                    && outer.right > inner.right
+                    && outer.bottom > inner.bottom
+            })
+        };
+        if is_glyph[position] || is_counter() {
+            output.push(index);
+        }
+    }
+
+    // 以包围盒外一圈非文字像素的平均色填充字形，相当于把文字从背景上擦掉
+    for &index in &text_indices {
+        let cluster = view.get_cluster(index);
+        let rect = &cluster.rect;
+        let (left, top) = ((rect.left - 1).max(0) as usize, (rect.top - 1).max(0) as usize);
+        let (right, bottom) = ((rect.right as usize + 1).min(w), (rect.bottom as usize + 1).min(h));
+        let mut sum = [0u32; 4];
+        let mut count = 0u32;
+        for y in top..bottom {
+            for x in left..right {
+                let on_ring = x == left || x + 1 == right || y == top || y + 1 == bottom;
+                if on_ring && glyph_at[y * w + x].is_none() {
+                    for (s, &c) in sum.iter_mut().zip(&color_image.pixels[(y * w + x) * 4..(y * w + x) * 4 + 4]) {
+                        *s += c as u32;
+                    }
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            continue;
+        }
+        let fill = sum.map(|s| ((s + count / 2) / count) as u8);
+        for &i in cluster.iter() {
+            let i = i as usize * 4;
+            color_image.pixels[i..i + 4].copy_from_slice(&fill);
+        }
+    }
+
+    (fit_cluster_indices(&view, &output, &fine_cfg), cluster_sources(&view, &output, &fine_cfg))
+}
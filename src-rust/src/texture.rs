@@ -0,0 +1,188 @@
+//! 纹理填充 (实验性)：把颜色起伏明显的色块改用 `<pattern>` 填充，近似保留噪点、排线等纹理
+
+use std::fmt::Write;
+
+use visioncortex::color_clusters::{ClusterIndex, ClustersView};
+use visioncortex::Color;
+
+use crate::color_space::{self, ColorSpace};
+use crate::{format_number, luma, TracerConfig};
+
+/// 参与判定的色块最小面积 (像素)，太小的色块看不出纹理
+const TEXTURE_MIN_AREA: usize = 64;
+
+/// 判定为纹理的粗糙度下限：色块内相邻像素 RGB 各通道差之和的平均值
+///
+/// 渐变每像素的变化很小，抗锯齿边缘只占色块的一小部分，都达不到这个值。
+const TEXTURE_MIN_ROUGHNESS: f64 = 24.0;
+
+/// 一个方向的粗糙度超过另一方向的倍数时视为排线
+const HATCH_RATIO: f64 = 2.0;
+
+/// 图案单元的边长 (像素)
+const TILE: f64 = 4.0;
+
+/// 图案形状
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Motif {
+    /// 圆点，对应没有方向性的噪点
+    Dots,
+    /// 水平排线 (竖直方向起伏大)
+    HorizontalLines,
+    /// 竖直排线 (水平方向起伏大)
+    VerticalLines,
+}
+
+/// 色块的纹理近似：底色上按覆盖率铺一层点或线
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Texture {
+    motif: Motif,
+    /// 像素较多一侧的平均色，用作底色
+    base: Color,
+    /// 像素较少一侧的平均色，用作点或线的颜色
+    ink: Color,
+    /// 点或线的颜色占色块像素的比例 (0~0.5)
+    coverage: f64,
+}
+
+/// 每个像素在画面上可见的色块：叠放模式下上层色块覆盖下层，取最后绘制的那一个
+///
+/// 层次聚类中色块的像素包含并入它的全部子色块，其中单独输出的子色块绘制在它之上，
+/// 统计纹理时需要排除，只看它自己露出来的部分。
+pub(crate) fn visible_owners(view: &ClustersView) -> Vec<Option<ClusterIndex>> {
+    let mut owners = vec![None; view.width as usize * view.height as usize];
+    for &index in view.clusters_output.iter().rev() {
+        for &i in view.get_cluster(index).iter() {
+            owners[i as usize] = Some(index);
+        }
+    }
+    owners
+}
+
+/// 统计色块可见像素的颜色起伏，起伏足够大时返回对应的纹理
+///
+/// `owners` 来自 `visible_owners`；只比较同属该色块的相邻可见像素，画在其上的其他色块不计入。
+/// 像素按亮度分为亮、暗两组，像素少的一组作为图案的前景色。
+pub(crate) fn detect(
+    view: &ClustersView,
+    index: ClusterIndex,
+    owners: &[Option<ClusterIndex>],
+    cfg: &TracerConfig,
+) -> Option<Texture> {
+    let cluster = view.get_cluster(index);
+    if cluster.area() < TEXTURE_MIN_AREA {
+        return None;
+    }
+    let (pixels, width) = (view.pixels, view.width as usize);
+    let rect = cluster.rect;
+    let (rw, rh) = (rect.width() as usize, rect.height() as usize);
+    let (left, top) = (rect.left as usize, rect.top as usize);
+    let color_at = |i: usize| {
+        let color = Color::new(pixels[i * 4], pixels[i * 4 + 1], pixels[i * 4 + 2]);
+        match cfg.color_space {
+            ColorSpace::Rgb => color,
+            ColorSpace::Lab => color_space::decode_lab_color(color),
+        }
+    };
+
+    // 包围盒内可见像素的位图，值为像素颜色
+    let mut local: Vec<Option<Color>> = vec![None; rw * rh];
+    for &i in cluster.iter().filter(|&&i| owners[i as usize] == Some(index)) {
+        let i = i as usize;
+        local[(i / width - top) * rw + i % width - left] = Some(color_at(i));
+    }
+    if local.iter().flatten().count() < TEXTURE_MIN_AREA {
+        return None;
+    }
+    let diff = |a: Color, b: Color| {
+        (a.r.abs_diff(b.r) as u32 + a.g.abs_diff(b.g) as u32 + a.b.abs_diff(b.b) as u32) as f64
+    };
+    let mut sums = [0.0; 2];
+    let mut counts = [0usize; 2];
+    for y in 0..rh {
+        for x in 0..rw {
+            let Some(color) = local[y * rw + x] else { continue };
+            let neighbours = [(x + 1 < rw).then(|| local[y * rw + x + 1]), (y + 1 < rh).then(|| local[(y + 1) * rw + x])];
+            for (axis, neighbour) in neighbours.into_iter().enumerate() {
+                if let Some(Some(other)) = neighbour {
+                    sums[axis] += diff(color, other);
+                    counts[axis] += 1;
+                }
+            }
+        }
+    }
+    let [horizontal, vertical] = [0, 1].map(|axis| sums[axis] / counts[axis].max(1) as f64);
+    if counts[0] + counts[1] == 0 || (sums[0] + sums[1]) / ((counts[0] + counts[1]) as f64) < TEXTURE_MIN_ROUGHNESS {
+        return None;
+    }
+    let motif = if vertical > horizontal * HATCH_RATIO {
+        Motif::HorizontalLines
+    } else if horizontal > vertical * HATCH_RATIO {
+        Motif::VerticalLines
+    } else {
+        Motif::Dots
+    };
+
+    let colors: Vec<Color> = local.into_iter().flatten().collect();
+    let mean_luma = colors.iter().map(|&c| luma(c) as f64).sum::<f64>() / colors.len() as f64;
+    let mut groups = [([0u64; 3], 0u64); 2];
+    for &c in &colors {
+        let (sum, count) = &mut groups[(luma(c) as f64 >= mean_luma) as usize];
+        for (s, v) in sum.iter_mut().zip([c.r, c.g, c.b]) {
+            *s += v as u64;
+        }
+        *count += 1;
+    }
+    let average = |(sum, count): ([u64; 3], u64)| {
+        let channel = |s: u64| ((s + count / 2) / count.max(1)) as u8;
+        Color::new(channel(sum[0]), channel(sum[1]), channel(sum[2]))
+    };
+    let (dark, light) = (groups[0], groups[1]);
+    let (base, ink) = if dark.1 > light.1 { (dark, light) } else { (light, dark) };
+    Some(Texture {
+        motif,
+        base: average(base),
+        ink: average(ink),
+        coverage: ink.1 as f64 / colors.len() as f64,
+    })
+}
+
+/// 第 `index` 条路径的填充属性
+pub(crate) fn fill_attribute(index: usize) -> String {
+    format!(r##"fill="url(#snapsvg-texture-{})""##, index)
+}
+
+/// 所有纹理的 `<pattern>` 定义，`id` 为 `snapsvg-texture-{路径下标}`
+///
+/// 图案使用 `userSpaceOnUse`，单元为 `TILE` 像素见方，与路径坐标的平移、裁剪无关。
+pub(crate) fn pattern_defs(textures: &[Option<Texture>], cfg: &TracerConfig) -> String {
+    let hex = |c: Color| format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b);
+    let number = |v: f64| format_number(v, cfg.path_precision);
+    let mut defs = String::new();
+    for (i, texture) in textures.iter().enumerate() {
+        let Some(texture) = texture else { continue };
+        write!(
+            defs,
+            r#"<pattern id="snapsvg-texture-{}" width="{}" height="{}" patternUnits="userSpaceOnUse"><rect width="{}" height="{}" fill="{}"/>"#,
+            i, number(TILE), number(TILE), number(TILE), number(TILE), hex(texture.base),
+        ).ok();
+        let thickness = texture.coverage * TILE;
+        match texture.motif {
+            Motif::Dots => {
+                let radius = (TILE * TILE * texture.coverage / std::f64::consts::PI).sqrt().min(TILE / 2.0);
+                write!(
+                    defs, r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    number(TILE / 2.0), number(TILE / 2.0), number(radius), hex(texture.ink),
+                ).ok();
+            }
+            Motif::HorizontalLines => {
+                write!(defs, r#"<rect width="{}" height="{}" fill="{}"/>"#, number(TILE), number(thickness), hex(texture.ink)).ok();
+            }
+            Motif::VerticalLines => {
+                write!(defs, r#"<rect width="{}" height="{}" fill="{}"/>"#, number(thickness), number(TILE), hex(texture.ink)).ok();
+            }
+        }
+        defs.push_str("</pattern>\n");
+    }
+    defs
+}
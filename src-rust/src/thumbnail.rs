@@ -0,0 +1,66 @@
+//! 缩略图快速追踪：固定使用激进的参数，只求速度，用于批量生成网格预览
+
+use wasm_bindgen::prelude::*;
+use visioncortex::ColorImage;
+
+use crate::{build_config, build_svg, check_rgba_len, cluster_and_fit, reset_rng};
+
+/// 缩略图使用的颜色数
+const THUMBNAIL_COLORS: u8 = 6;
+
+/// 缩略图的斑点过滤面积 (缩小后的像素)
+const THUMBNAIL_SPECKLE: u32 = 8;
+
+/// 缩略图的曲线拟合迭代次数
+const THUMBNAIL_ITERATIONS: usize = 2;
+
+/// 最近邻缩放到 `(w, h)`
+fn nearest_downscale(pixels: &[u8], width: usize, height: usize, w: usize, h: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(w * h * 4);
+    for y in 0..h {
+        let sy = y * height / h;
+        for x in 0..w {
+            let sx = x * width / w;
+            let i = (sy * width + sx) * 4;
+            out.extend_from_slice(&pixels[i..i + 4]);
+        }
+    }
+    out
+}
+
+/// 草稿质量的快速追踪：最近邻缩小到长边不超过 `max_dim` 像素后，以固定的激进参数追踪
+///
+/// 不接受质量参数：固定 6 种颜色、斑点过滤 8 像素、曲线拟合只迭代 2 次、坐标保留 1 位小数，
+/// 与完整质量的函数相比通常快一个数量级以上，适合一次生成大量网格预览。
+/// 输出的 `viewBox` 为缩小后的尺寸 (宽高至少 1 像素)；图像本身不大于 `max_dim` 时不缩放。
+/// `max_dim` 为 0 时按 1 处理。
+#[wasm_bindgen]
+pub fn trace_rgba_thumbnail(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    max_dim: u32,
+) -> Result<String, JsValue> {
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let max_dim = max_dim.max(1) as usize;
+    let longest = w.max(h);
+    let (tw, th, pixels) = if longest > max_dim {
+        let tw = (w * max_dim / longest).max(1);
+        let th = (h * max_dim / longest).max(1);
+        (tw, th, nearest_downscale(rgba_data, w, h, tw, th))
+    } else {
+        (w, h, rgba_data.to_vec())
+    };
+
+    let mut cfg = build_config(THUMBNAIL_COLORS, 50, 60, THUMBNAIL_SPECKLE);
+    cfg.max_iterations = THUMBNAIL_ITERATIONS;
+    cfg.path_precision = Some(1);
+
+    let path_results = cluster_and_fit(ColorImage { pixels, width: tw, height: th }, &cfg);
+    Ok(build_svg(tw, th, &path_results, &cfg))
+}
@@ -0,0 +1,112 @@
+//! 环绕追踪：把图像视为左右、上下边缘相接的环面，输出可无缝平铺的路径
+
+use visioncortex::{BoundingRect, Color, ColorImage, CompoundPath, PointF64, PointI32};
+
+use crate::{bench, cluster_and_fit_sourced, geometry, transform_compound_path, Source, TracerConfig};
+
+/// 画布四周环绕扩展的宽度 (像素)
+///
+/// 跨越边缘的色块只需在扩展区内保持连通，超出部分被 `viewBox` 裁掉，
+/// 扩展区外缘的截断不落在画布内，因此不需要扩展整张图像。
+const WRAP_MARGIN: usize = 16;
+
+/// 按对边像素环绕扩展 `margin` 像素，返回扩展后的图像
+fn wrap_pad(image: &ColorImage, margin: usize) -> ColorImage {
+    let (w, h) = (image.width, image.height);
+    let (padded_w, padded_h) = (w + 2 * margin, h + 2 * margin);
+    let mut pixels = Vec::with_capacity(padded_w * padded_h * 4);
+    for py in 0..padded_h {
+        let sy = (py as isize - margin as isize).rem_euclid(h as isize) as usize;
+        for px in 0..padded_w {
+            let sx = (px as isize - margin as isize).rem_euclid(w as isize) as usize;
+            let i = (sy * w + sx) * 4;
+            pixels.extend_from_slice(&image.pixels[i..i + 4]);
+        }
+    }
+    ColorImage { pixels, width: padded_w, height: padded_h }
+}
+
+/// 路径的包围盒是否与 `(0, 0)` ~ `(width, height)` 的画布有面积重叠
+fn overlaps_canvas(path: &CompoundPath, width: f64, height: f64) -> bool {
+    let (mut min, mut max) = (PointF64::new(f64::MAX, f64::MAX), PointF64::new(f64::MIN, f64::MIN));
+    for element in path.iter() {
+        for p in geometry::flatten_element(element, 8) {
+            min = PointF64::new(min.x.min(p.x), min.y.min(p.y));
+            max = PointF64::new(max.x.max(p.x), max.y.max(p.y));
+        }
+    }
+    min.x < width && max.x > 0.0 && min.y < height && max.y > 0.0
+}
+
+/// 在环绕扩展后的图像上完整聚类、拟合，再把路径平移回原画布坐标
+///
+/// 跨越边缘的色块在扩展后的画布上连通，拟合为一条越过边缘的路径；它在对侧环绕出的那一份
+/// 同样保留，两份由相同的像素拟合。完全落在扩展区内的路径被丢弃。
+/// 来源包围盒同样平移回原画布，并裁剪到画布范围内。
+pub(crate) fn cluster_and_fit_wrapped(
+    color_image: ColorImage,
+    cfg: &TracerConfig,
+) -> (Vec<(CompoundPath, Color)>, Vec<Source>, bench::PhaseTimes) {
+    let (w, h) = (color_image.width, color_image.height);
+    let mut wrapped_cfg = cfg.clone();
+    wrapped_cfg.tileable = false;
+    if w == 0 || h == 0 {
+        return cluster_and_fit_sourced(color_image, &wrapped_cfg);
+    }
+
+    let padded = wrap_pad(&color_image, WRAP_MARGIN);
+    let (path_results, sources, times) = cluster_and_fit_sourced(padded, &wrapped_cfg);
+    let shift = PointF64::new(-(WRAP_MARGIN as f64), -(WRAP_MARGIN as f64));
+    let canvas = BoundingRect::new_x_y_w_h(0, 0, w as i32, h as i32);
+    let (path_results, sources) = path_results
+        .into_iter()
+        .zip(sources)
+        .map(|((path, color), mut source)| {
+            source.rect.translate(PointI32::new(-(WRAP_MARGIN as i32), -(WRAP_MARGIN as i32)));
+            source.rect.clip(canvas);
+            ((transform_compound_path(&path, 1.0, shift), color), source)
+        })
+        .filter(|((path, _), _)| overlaps_canvas(path, w as f64, h as f64))
+        .unzip();
+    (path_results, sources, times)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{fill_rect, numbers, rings, solid};
+    use crate::{trace_rgba_with_options, TraceOptions};
+
+    /// 指定颜色的每条路径在画布坐标中的横向范围
+    fn spans(svg: &str, fill: &str) -> Vec<(f64, f64)> {
+        svg.lines()
+            .filter(|line| line.contains(&format!("fill=\"{}\"", fill)))
+            .map(|line| {
+                let d = line.split(" d=\"").nth(1).and_then(|rest| rest.split('"').next()).unwrap();
+                let offset = numbers(line.split("translate(").nth(1).unwrap());
+                let xs = rings(d, (offset[0], offset[1])).concat().into_iter().map(|p| p.0);
+                xs.fold((f64::MAX, f64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shape_straddling_the_seam_is_one_path() {
+        // 蓝色矩形跨越左右边缘：左侧 5 列、右侧 6 列
+        let mut pixels = solid(40, 40, [255, 255, 255, 255]);
+        fill_rect(&mut pixels, 40, (0, 10), (5, 20), [20, 40, 200, 255]);
+        fill_rect(&mut pixels, 40, (34, 10), (40, 20), [20, 40, 200, 255]);
+
+        let mut options = TraceOptions::new();
+        let svg = trace_rgba_with_options(&pixels, 40, 40, &options).unwrap();
+        let mut halves = spans(&svg, "#1428c8");
+        halves.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(halves, [(0.0, 5.0), (34.0, 40.0)]);
+
+        // 环绕后每条路径都是完整的 11 列矩形，越过画布边缘；对侧环绕出的那一份同样保留
+        options.tileable = true;
+        let svg = trace_rgba_with_options(&pixels, 40, 40, &options).unwrap();
+        let mut whole = spans(&svg, "#1428c8");
+        whole.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(whole, [(-6.0, 5.0), (34.0, 45.0)]);
+    }
+}
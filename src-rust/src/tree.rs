@@ -0,0 +1,141 @@
+//! 包含关系树：按几何包含关系把拟合结果组织成嵌套结构，供编辑器的图层面板使用
+
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Object};
+use visioncortex::{ColorImage, CompoundPath, PointF64};
+
+use crate::geometry::flatten_element;
+use crate::{check_rgba_len, cluster_and_fit, reset_rng, set_js_property, warnings, TraceOptions};
+
+/// 判断包含关系时每条路径最多取的采样点数
+const SAMPLES: usize = 16;
+
+/// 展平后的路径：全部子路径的折线与包围盒
+struct Outline {
+    rings: Vec<Vec<PointF64>>,
+    min: PointF64,
+    max: PointF64,
+}
+
+impl Outline {
+    fn new(path: &CompoundPath) -> Outline {
+        let rings: Vec<Vec<PointF64>> = path.iter().map(|element| flatten_element(element, 4)).collect();
+        let (mut min, mut max) = (PointF64::new(f64::MAX, f64::MAX), PointF64::new(f64::MIN, f64::MIN));
+        for p in rings.iter().flatten() {
+            min = PointF64::new(min.x.min(p.x), min.y.min(p.y));
+            max = PointF64::new(max.x.max(p.x), max.y.max(p.y));
+        }
+        Outline { rings, min, max }
+    }
+
+    /// 奇偶规则判断点是否在路径内 (孔洞中的点不算)
+    fn contains_point(&self, p: PointF64) -> bool {
+        let mut inside = false;
+        for ring in &self.rings {
+            for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    /// `other` 的包围盒在本路径的包围盒内，且多数采样点 (最多 `SAMPLES` 个轮廓点) 落在本路径内
+    ///
+    /// 叠放模式下相邻色块共享边界，边界上的点可能被判到任意一侧，因此按多数判断。
+    fn contains(&self, other: &Outline) -> bool {
+        const EPSILON: f64 = 1e-6;
+        if other.min.x < self.min.x - EPSILON
+            || other.min.y < self.min.y - EPSILON
+            || other.max.x > self.max.x + EPSILON
+            || other.max.y > self.max.y + EPSILON
+        {
+            return false;
+        }
+        let points: Vec<PointF64> = other.rings.iter().flatten().copied().collect();
+        if points.is_empty() {
+            return false;
+        }
+        let step = points.len().div_ceil(SAMPLES);
+        let samples: Vec<PointF64> = points.iter().step_by(step).copied().collect();
+        let inside = samples.iter().filter(|&&p| self.contains_point(p)).count();
+        inside * 2 > samples.len()
+    }
+}
+
+/// 矢量化并返回按包含关系嵌套的路径树
+///
+/// 返回根节点数组，每个节点为 `{ index, d, x, y, fill, children }`：`index` 为该路径在 SVG 中的
+/// 绘制顺序 (从 0 开始)；`d` 为路径数据，数据相对 `(x, y)`，与 SVG 输出中的
+/// `transform="translate(x,y)"` 一致；`fill` 为 `#rrggbb`；`children` 为被它包含的节点。
+///
+/// 树的含义：节点 B 是 A 的子节点，当且仅当 A 比 B 先绘制、B 落在 A 的填充区域内
+/// (落在 A 的孔洞中不算)，且 A 是满足条件的路径中最后绘制 (即最靠近 B) 的那一个。
+/// 叠放模式下下层色块会延伸到上层色块之下，因此大致对应 "这个形状在那个形状之上、之内"。
+/// 兄弟节点按绘制顺序排列；但部分重叠而互不包含的路径可能分属不同子树，
+/// 前序遍历不一定等于绘制顺序，还原图像时应按 `index` 排序绘制。
+///
+/// 每条路径都要与之前的路径比较，路径很多 (数千条) 时耗时明显增加。
+#[wasm_bindgen]
+pub fn trace_rgba_to_tree(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<JsValue, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let path_results = cluster_and_fit(color_image, &cfg);
+
+    let outlines: Vec<Outline> = path_results.iter().map(|(path, _)| Outline::new(path)).collect();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); path_results.len()];
+    let mut roots = Vec::new();
+    for i in 0..outlines.len() {
+        match (0..i).rev().find(|&j| outlines[j].contains(&outlines[i])) {
+            Some(parent) => children[parent].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let build_node = |i: usize| {
+        let (path, color) = &path_results[i];
+        let (d, offset) = path.to_svg_string(true, PointF64::default(), cfg.path_precision);
+        let node = Object::new();
+        set_js_property(&node, "index", &(i as u32).into());
+        set_js_property(&node, "d", &JsValue::from_str(&d));
+        set_js_property(&node, "x", &offset.x.into());
+        set_js_property(&node, "y", &offset.y.into());
+        set_js_property(&node, "fill", &JsValue::from_str(&format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)));
+        node
+    };
+
+    // 子节点总是比父节点后绘制，按下标从大到小构建即可保证子节点先于父节点完成
+    let mut nodes: Vec<Option<Object>> = vec![None; path_results.len()];
+    for i in (0..path_results.len()).rev() {
+        let node = build_node(i);
+        let list = Array::new();
+        for &child in &children[i] {
+            if let Some(child_node) = nodes[child].take() {
+                list.push(&child_node);
+            }
+        }
+        set_js_property(&node, "children", &list.into());
+        nodes[i] = Some(node);
+    }
+
+    let result = Array::new();
+    for i in roots {
+        if let Some(node) = nodes[i].take() {
+            result.push(&node);
+        }
+    }
+    Ok(result.into())
+}
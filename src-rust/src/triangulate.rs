@@ -0,0 +1,147 @@
+//! 三角网格输出：把拟合后的路径剖分为三角形，供 WebGL 等 GPU 渲染器直接使用
+//!
+//! 剖分使用简单的耳切法 (ear clipping)，不引入额外依赖。
+
+use wasm_bindgen::prelude::*;
+use visioncortex::{ColorImage, PointF64};
+
+use crate::geometry::{flatten_element, polygon_area};
+use crate::{check_rgba_len, cluster_and_fit, reset_rng, warnings, TraceOptions};
+
+/// 样条每段三次曲线展平时的采样段数
+const SEGMENTS_PER_CURVE: usize = 8;
+
+/// 三角网格：扁平顶点缓冲 + 逐顶点颜色
+#[wasm_bindgen]
+pub struct TriangleMesh {
+    positions: Vec<f32>,
+    colors: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl TriangleMesh {
+    /// 顶点坐标 `[x0, y0, x1, y1, ...]` (像素坐标)，每 3 个顶点构成一个三角形
+    #[wasm_bindgen(getter)]
+    pub fn positions(&self) -> Vec<f32> {
+        self.positions.clone()
+    }
+
+    /// 逐顶点颜色 `[r0, g0, b0, a0, ...]`，与 `positions` 中的顶点一一对应
+    #[wasm_bindgen(getter)]
+    pub fn colors(&self) -> Vec<u8> {
+        self.colors.clone()
+    }
+
+    /// 三角形数量
+    #[wasm_bindgen(getter)]
+    pub fn triangle_count(&self) -> usize {
+        self.positions.len() / 6
+    }
+}
+
+fn cross(o: PointF64, a: PointF64, b: PointF64) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+fn in_triangle(p: PointF64, a: PointF64, b: PointF64, c: PointF64) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+/// 耳切法剖分简单多边形，返回三角形顶点索引
+///
+/// 输入需为正向 (有向面积为正) 的顶点序列；遇到自交等无法找到耳朵的情况时强行切掉当前顶点，
+/// 保证算法终止。
+fn ear_clip(points: &[PointF64]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+
+    let mut i = 0;
+    let mut misses = 0;
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let (ia, ib, ic) = (remaining[(i + n - 1) % n], remaining[i % n], remaining[(i + 1) % n]);
+        let (a, b, c) = (points[ia], points[ib], points[ic]);
+
+        let is_ear = cross(a, b, c) > 0.0
+            && !remaining
+                .iter()
+                .filter(|&&j| j != ia && j != ib && j != ic)
+                .any(|&j| in_triangle(points[j], a, b, c));
+
+        if is_ear || misses >= n {
+            triangles.push([ia, ib, ic]);
+            remaining.remove(i % n);
+            misses = 0;
+        } else {
+            i += 1;
+            misses += 1;
+        }
+        i %= remaining.len();
+    }
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+/// 去除闭合点与重复点，并统一为正向
+fn prepare_ring(mut points: Vec<PointF64>) -> Vec<PointF64> {
+    points.dedup_by(|a, b| a.distance_to(*b) < 1e-9);
+    if points.len() > 1 && points[0].distance_to(points[points.len() - 1]) < 1e-9 {
+        points.pop();
+    }
+    if polygon_area(&points) < 0.0 {
+        points.reverse();
+    }
+    points
+}
+
+/// 矢量化并输出三角网格
+///
+/// 所有三角形统一为正向：在像素坐标系 (y 轴向下) 中有向面积为正，即屏幕上看是顺时针；
+/// 若 GPU 端开启背面剔除，需要相应设置正面朝向。三角形按路径绘制顺序输出，
+/// 依次绘制 (关闭深度测试) 即可得到与 SVG 相同的叠放效果。
+///
+/// 每条子路径独立剖分并填充，暂不挖空洞：聚类轮廓中的孔洞子路径也会被填成实心。
+/// 堆叠输出下孔洞通常会被后绘制的上层色块覆盖，但要得到严格正确的孔洞，
+/// 需要先完成路径的 evenodd 填充修正。
+#[wasm_bindgen]
+pub fn trace_rgba_to_triangles(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    options: &TraceOptions,
+) -> Result<TriangleMesh, JsValue> {
+    let _warnings = warnings::install(options.on_warning.clone());
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    reset_rng();
+
+    let cfg = options.to_config();
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    let path_results = cluster_and_fit(color_image, &cfg);
+
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+
+    for (compound_path, color) in &path_results {
+        for element in &compound_path.paths {
+            let ring = prepare_ring(flatten_element(element, SEGMENTS_PER_CURVE));
+            if ring.len() < 3 {
+                continue;
+            }
+            for triangle in ear_clip(&ring) {
+                for index in triangle {
+                    let p = ring[index];
+                    positions.extend_from_slice(&[p.x as f32, p.y as f32]);
+                    colors.extend_from_slice(&[color.r, color.g, color.b, 255]);
+                }
+            }
+        }
+    }
+
+    Ok(TriangleMesh { positions, colors })
+}
@@ -0,0 +1,96 @@
+//! 非致命警告通道：追踪过程中的参数截断、聚类丢弃等事件通过 JS 回调通知调用方
+//!
+//! 回调保存在调用线程的线程局部变量中，只有在调用线程上触发的事件会被送达；
+//! Rayon 工作线程内部不发出警告。未设置回调时所有警告被直接忽略。
+//!
+//! 警告为 `"<类别>: <说明>"` 形式的字符串，类别包括：
+//! - `clamp`：参数超出有效范围，已截断
+//! - `option`：无法识别的选项取值，已回退为默认值
+//! - `dropped`：有聚类在拟合后没有产生路径、坐标无效 (NaN/无穷) 或点数少于 `min_points_per_path`，输出时被跳过
+//! - `fallback`：无法创建独立线程池，改用全局线程池
+//! - `max_paths`：路径数量预算生效，报告保留与合并的路径数
+//! - `downscale`：估算内存超出预算，输入已缩小后再追踪
+//! - `empty`：没有生成任何路径，输出为空的 SVG (开启 `fail_on_empty` 时改为返回错误)
+
+use std::cell::RefCell;
+
+thread_local! {
+    static SINK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+#[cfg(test)]
+thread_local! {
+    /// 测试中代替 JS 回调记录警告，`None` 表示未在记录
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// 记录 `f` 执行期间在当前线程上发出的全部警告 (测试中无法构造 JS 回调)
+#[cfg(test)]
+pub(crate) fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let previous = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    let result = f();
+    let warnings = CAPTURED.with(|captured| captured.replace(previous)).unwrap_or_default();
+    (result, warnings)
+}
+
+/// 回调的作用域守卫：离开作用域时恢复之前的回调
+pub(crate) struct SinkGuard {
+    previous: Option<js_sys::Function>,
+}
+
+impl Drop for SinkGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SINK.with(|sink| *sink.borrow_mut() = previous);
+    }
+}
+
+/// 在当前线程上安装警告回调，返回的守卫被释放前一直有效
+pub(crate) fn install(callback: Option<js_sys::Function>) -> SinkGuard {
+    let previous = SINK.with(|sink| sink.replace(callback));
+    SinkGuard { previous }
+}
+
+/// 发出一条警告
+pub(crate) fn emit(category: &str, message: &str) {
+    #[cfg(test)]
+    CAPTURED.with(|captured| {
+        if let Some(warnings) = captured.borrow_mut().as_mut() {
+            warnings.push(format!("{}: {}", category, message));
+        }
+    });
+    SINK.with(|sink| {
+        if let Some(callback) = sink.borrow().as_ref() {
+            // 回调自身抛出的异常不影响追踪
+            let text = format!("{}: {}", category, message);
+            let _ = callback.call1(&wasm_bindgen::JsValue::NULL, &wasm_bindgen::JsValue::from_str(&text));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::solid;
+    use crate::{trace_rgba_to_result, trace_rgba_with_options, TraceOptions};
+
+    /// 超出范围的 `color_count`，同时开启两项自动估计 (会在追踪前额外转换配置)
+    fn out_of_range_options() -> TraceOptions {
+        let mut options = TraceOptions::new();
+        options.color_count = 100;
+        options.auto_speckle = true;
+        options.auto_color_count = true;
+        options
+    }
+
+    #[test]
+    fn clamp_warning_is_reported_once_per_call() {
+        let pixels = solid(16, 16, [40, 120, 200, 255]);
+        let is_clamp = |w: &&String| w.starts_with("clamp: color_count=100");
+
+        let (_, warnings) = super::capture(|| trace_rgba_to_result(&pixels, 16, 16, &out_of_range_options()).unwrap());
+        assert_eq!(warnings.iter().filter(is_clamp).count(), 1, "{:?}", warnings);
+
+        let (_, warnings) = super::capture(|| trace_rgba_with_options(&pixels, 16, 16, &out_of_range_options()).unwrap());
+        assert_eq!(warnings.iter().filter(is_clamp).count(), 1, "{:?}", warnings);
+    }
+}
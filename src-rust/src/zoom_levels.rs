@@ -0,0 +1,114 @@
+//! 缩放级别：聚类一次，按多组拟合参数分别输出，各级别的坐标空间完全一致
+
+use wasm_bindgen::prelude::*;
+use visioncortex::color_clusters::ClustersView;
+use visioncortex::{Color, ColorImage, CompoundPath};
+
+use crate::{
+    build_svg, check_rgba_len, cluster_image, cluster_scale, finish_paths, fit_clusters, reset_rng, resolve_config,
+    warnings, TraceOptions, TracerConfig,
+};
+
+/// 某一级别的配置：拟合与输出参数取自该级别，聚类阶段与画布布局相关的参数取自第一级
+fn level_config(mut cfg: TracerConfig, base: &TracerConfig) -> TracerConfig {
+    // 影响拟合结果解读的聚类参数
+    cfg.color_space = base.color_space;
+    cfg.diagonal = base.diagonal;
+    cfg.palette = base.palette.clone();
+    // 画布布局：按内容变化的布局会使各级别错位，一律关闭
+    cfg.crop_to_content = false;
+    cfg.center_origin = false;
+    cfg.normalize_to = None;
+    cfg.normalize_coords = base.normalize_coords;
+    cfg.physical_unit = base.physical_unit;
+    cfg
+}
+
+/// 只聚类一次，按 `configs` 中每组选项的拟合参数分别输出一份 SVG，用于可缩放地图的多级切换
+///
+/// 聚类使用第一组选项 (颜色数、斑点过滤、颜色空间、预处理等，包括 `cluster_scale` 与
+/// 自动颜色数等自动参数)，后续各组的聚类参数被忽略；各组自己的拟合与输出参数生效，例如
+/// `corner_threshold`/`corner_angle_degrees`、`path_precision` (拼接阈值)、`coordinate_precision`、
+/// `precision`、`postprocess`、`max_paths`、`min_points_per_path`、`stroke_mode`、`overlap` 等。
+///
+/// 所有级别的 `viewBox` 都是 `0 0 width height` (`normalize_coords` 时为 `0 0 1 1`)，
+/// 根元素的物理尺寸也相同，坐标都位于同一原图像素空间，互相替换时完全对齐。为此
+/// `crop_to_content`、`center_origin`、`normalize_width`/`normalize_height` 在所有级别都不生效，
+/// `normalize_coords`、`units`/`dpi` 取第一组的设置。`text_aware`、`protect_dark`、`tileable`、
+/// `detect_texture` 需要额外的聚类或来源信息，`line_extraction` 不做色块聚类，这里都不支持。
+/// 与逐级分别调用 `trace_rgba_with_options` 相比省去了重复的聚类，而聚类通常是耗时的大头。
+/// `configs` 为空时返回错误；警告统一通过第一组的 `on_warning` 报告。
+#[wasm_bindgen]
+pub fn trace_rgba_zoom_levels(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+    configs: Vec<TraceOptions>,
+) -> Result<Vec<String>, JsValue> {
+    let Some(first) = configs.first() else {
+        return Err(JsValue::from_str("configs 至少需要一组选项"));
+    };
+    let _warnings = warnings::install(first.on_warning.clone());
+    reset_rng();
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rgba_len(rgba_data, w, h)?;
+
+    let color_image = ColorImage { pixels: rgba_data.to_vec(), width: w, height: h };
+    // 每组选项只转换一次，选项校验的警告不会重复报告
+    let configs: Vec<TracerConfig> = configs.iter().map(TraceOptions::to_config).collect();
+    let resolved = resolve_config(&color_image, &configs[0]);
+    let base = resolved.as_ref().unwrap_or(&configs[0]);
+    let level_configs: Vec<TracerConfig> = configs.iter().map(|cfg| level_config(cfg.clone(), base)).collect();
+
+    let fit_levels = |view: &ClustersView| -> Vec<Vec<(CompoundPath, Color)>> {
+        level_configs
+            .iter()
+            .map(|cfg| {
+                let (path_results, sources) = fit_clusters(view, cfg);
+                finish_paths(path_results, sources, cfg).0
+            })
+            .collect()
+    };
+    let levels = match base.cluster_scale {
+        Some(scale) => fit_levels(&cluster_scale::cluster(color_image, scale, base).view()),
+        None => fit_levels(&cluster_image(color_image, base).view()),
+    };
+
+    Ok(levels
+        .iter()
+        .zip(&level_configs)
+        .map(|(path_results, cfg)| build_svg(w, h, path_results, cfg))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::shapes;
+    use crate::trace_rgba_with_options;
+
+    #[test]
+    fn each_level_matches_a_standalone_trace() {
+        let pixels = shapes(60, 40);
+        let configs: Vec<TraceOptions> = [(60, 2), (20, 1), (120, 0)]
+            .into_iter()
+            .map(|(corner_threshold, coordinate_precision)| {
+                let mut options = TraceOptions::new();
+                options.corner_threshold = corner_threshold;
+                options.coordinate_precision = coordinate_precision;
+                options
+            })
+            .collect();
+        let standalone: Vec<String> = configs
+            .iter()
+            .map(|options| trace_rgba_with_options(&pixels, 60, 40, options).unwrap())
+            .collect();
+        let levels = trace_rgba_zoom_levels(&pixels, 60, 40, configs).unwrap();
+        assert_eq!(levels, standalone);
+        // 各级别的拟合参数确实生效
+        assert_ne!(levels[0], levels[1]);
+        assert_ne!(levels[0], levels[2]);
+    }
+}